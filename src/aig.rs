@@ -0,0 +1,368 @@
+/*!
+And-inverter graphs (AIGs), the circuit representation of the AIGER format.
+
+An AIG literal is `2 * variable + negated`, where variable 0 is the constant false.
+Variables are inputs, latches (state elements updated at every step), or AND gates.
+
+Conversion to CNF applies constant propagation and structural hashing on the fly:
+gates with a constant, repeated, or complementary input are simplified away,
+and gates with the same inputs share a single variable with the three Tseitin clauses.
+*/
+
+use std::{collections::HashMap, fmt::Display, ops::Not};
+
+use crate::encode::{CnfBuilder, Signal};
+use crate::formula::{Clause, Cnf, Literal, Variable};
+
+/// A literal of an AIG, possibly negated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AigLiteral(u32);
+
+impl AigLiteral {
+    pub const FALSE: AigLiteral = AigLiteral(0);
+    pub const TRUE: AigLiteral = AigLiteral(1);
+
+    /// Creates a literal from its AIGER number.
+    pub fn new(number: u32) -> Self {
+        AigLiteral(number)
+    }
+
+    pub fn number(self) -> u32 {
+        self.0
+    }
+
+    pub fn variable(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    pub fn is_negated(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    /// Returns the value under the values of the variables.
+    pub fn value(self, values: &[bool]) -> bool {
+        values[self.variable()] != self.is_negated()
+    }
+}
+
+impl Not for AigLiteral {
+    type Output = AigLiteral;
+
+    fn not(self) -> Self::Output {
+        AigLiteral(self.0 ^ 1)
+    }
+}
+
+impl Display for AigLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A state element, which takes the value of `next` at the next step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latch {
+    pub literal: AigLiteral,
+    pub next: AigLiteral,
+    /// The initial value, or `None` if uninitialized
+    pub reset: Option<bool>,
+}
+
+/// `output = inputs[0] ∧ inputs[1]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AndGate {
+    pub output: AigLiteral,
+    pub inputs: [AigLiteral; 2],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Definition {
+    Undefined,
+    Constant,
+    Input,
+    Latch,
+    And,
+}
+
+/// An and-inverter graph with its outputs and properties.
+#[derive(Debug, Clone)]
+pub struct Aig {
+    definitions: Vec<Definition>,
+    inputs: Vec<AigLiteral>,
+    latches: Vec<Latch>,
+    outputs: Vec<AigLiteral>,
+    /// Bad state properties
+    bad: Vec<AigLiteral>,
+    /// Invariant constraints, assumed to hold at every step
+    constraints: Vec<AigLiteral>,
+    /// In topological order
+    ands: Vec<AndGate>,
+}
+
+impl Aig {
+    /// Creates an empty AIG with variables up to `max_variable`.
+    pub fn new(max_variable: usize) -> Self {
+        let mut definitions = vec![Definition::Undefined; max_variable + 1];
+        definitions[0] = Definition::Constant;
+
+        Aig {
+            definitions,
+            inputs: Vec::new(),
+            latches: Vec::new(),
+            outputs: Vec::new(),
+            bad: Vec::new(),
+            constraints: Vec::new(),
+            ands: Vec::new(),
+        }
+    }
+
+    pub fn max_variable(&self) -> usize {
+        self.definitions.len() - 1
+    }
+
+    pub fn inputs(&self) -> &[AigLiteral] {
+        &self.inputs
+    }
+
+    pub fn latches(&self) -> &[Latch] {
+        &self.latches
+    }
+
+    pub fn outputs(&self) -> &[AigLiteral] {
+        &self.outputs
+    }
+
+    pub fn bad(&self) -> &[AigLiteral] {
+        &self.bad
+    }
+
+    pub fn constraints(&self) -> &[AigLiteral] {
+        &self.constraints
+    }
+
+    pub fn ands(&self) -> &[AndGate] {
+        &self.ands
+    }
+
+    /// Returns whether the variable of `literal` is in range and not defined yet.
+    pub fn is_undefined(&self, literal: AigLiteral) -> bool {
+        self.definitions.get(literal.variable()) == Some(&Definition::Undefined)
+    }
+
+    /// # Panics
+    ///
+    /// Panics when `literal` is negated or already defined.
+    pub fn add_input(&mut self, literal: AigLiteral) {
+        self.define(literal, Definition::Input);
+        self.inputs.push(literal);
+    }
+
+    /// # Panics
+    ///
+    /// Panics when the latch literal is negated or already defined,
+    /// or when `next` is out of range.
+    pub fn add_latch(&mut self, latch: Latch) {
+        self.define(latch.literal, Definition::Latch);
+        self.assert_in_range(latch.next);
+        self.latches.push(latch);
+    }
+
+    /// # Panics
+    ///
+    /// Panics when `literal` is out of range.
+    pub fn add_output(&mut self, literal: AigLiteral) {
+        self.assert_in_range(literal);
+        self.outputs.push(literal);
+    }
+
+    /// # Panics
+    ///
+    /// Panics when `literal` is out of range.
+    pub fn add_bad(&mut self, literal: AigLiteral) {
+        self.assert_in_range(literal);
+        self.bad.push(literal);
+    }
+
+    /// # Panics
+    ///
+    /// Panics when `literal` is out of range.
+    pub fn add_constraint(&mut self, literal: AigLiteral) {
+        self.assert_in_range(literal);
+        self.constraints.push(literal);
+    }
+
+    /// Adds a gate, whose inputs must have been defined before.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the output is negated or already defined, or an input is undefined.
+    pub fn add_and(&mut self, gate: AndGate) {
+        for input in gate.inputs {
+            self.assert_in_range(input);
+            assert!(self.definitions[input.variable()] != Definition::Undefined);
+        }
+        self.define(gate.output, Definition::And);
+        self.ands.push(gate);
+    }
+
+    /// Returns whether every literal in use is defined.
+    pub fn is_defined(&self) -> bool {
+        let mut literals = self
+            .latches
+            .iter()
+            .map(|latch| latch.next)
+            .chain(self.outputs.iter().copied())
+            .chain(self.bad.iter().copied())
+            .chain(self.constraints.iter().copied());
+        literals.all(|literal| self.definitions[literal.variable()] != Definition::Undefined)
+    }
+
+    fn define(&mut self, literal: AigLiteral, definition: Definition) {
+        assert!(!literal.is_negated());
+        assert!(self.is_undefined(literal));
+        self.definitions[literal.variable()] = definition;
+    }
+
+    fn assert_in_range(&self, literal: AigLiteral) {
+        assert!(literal.variable() < self.definitions.len());
+    }
+
+    /// Simulates one step, returning the values of all variables.
+    /// Uninitialized latches should be given some value in `latches`.
+    pub fn evaluate(&self, inputs: &[bool], latches: &[bool]) -> Vec<bool> {
+        let mut values = vec![false; self.definitions.len()];
+        for (literal, &value) in self.inputs.iter().zip(inputs) {
+            values[literal.variable()] = value;
+        }
+        for (latch, &value) in self.latches.iter().zip(latches) {
+            values[latch.literal.variable()] = value;
+        }
+        for gate in &self.ands {
+            values[gate.output.variable()] =
+                gate.inputs[0].value(&values) && gate.inputs[1].value(&values);
+        }
+        values
+    }
+
+    /// Returns a formula that is satisfiable iff `literal` can be true
+    /// with the latches in their initial state and the invariant constraints true.
+    /// The first variables of the formula are the inputs in order.
+    pub fn to_cnf(&self, literal: AigLiteral) -> Cnf {
+        let mut encoder = AigEncoder::new(CnfBuilder::new(self.inputs.len()));
+        let inputs: Vec<Signal> = (0..self.inputs.len())
+            .map(|index| Signal::Literal(Literal::new(Variable::from_index(index).unwrap(), true)))
+            .collect();
+        let latches: Vec<Signal> = self
+            .latches
+            .iter()
+            .map(|latch| encoder.initial_value(latch))
+            .collect();
+
+        let signals = encoder.encode(self, &inputs, &latches);
+        for &constraint in &self.constraints {
+            encoder.assert(self.signal(&signals, constraint));
+        }
+        encoder.assert(self.signal(&signals, literal));
+        encoder.build()
+    }
+
+    /// Returns the signal of `literal` from the signals of the variables.
+    pub(crate) fn signal(&self, signals: &[Signal], literal: AigLiteral) -> Signal {
+        let signal = signals[literal.variable()];
+        if literal.is_negated() {
+            signal.negate()
+        } else {
+            signal
+        }
+    }
+}
+
+/// Translates copies of an AIG into clauses with constant propagation and structural hashing.
+pub(crate) struct AigEncoder {
+    builder: CnfBuilder,
+    /// The output of the gate of the inputs, ordered
+    gates: HashMap<(Literal, Literal), Literal>,
+}
+
+impl AigEncoder {
+    pub(crate) fn new(builder: CnfBuilder) -> Self {
+        AigEncoder {
+            builder,
+            gates: HashMap::new(),
+        }
+    }
+
+    /// Returns the reset value, or a fresh variable if uninitialized.
+    pub(crate) fn initial_value(&mut self, latch: &Latch) -> Signal {
+        match latch.reset {
+            Some(false) => Signal::False,
+            Some(true) => Signal::True,
+            None => Signal::new(&mut self.builder),
+        }
+    }
+
+    /// Returns a fresh signal for an input.
+    pub(crate) fn input(&mut self) -> Signal {
+        Signal::new(&mut self.builder)
+    }
+
+    /// Encodes the gates of `aig` with the given signals of the inputs and latches,
+    /// returning the signals of all variables.
+    pub(crate) fn encode(
+        &mut self,
+        aig: &Aig,
+        inputs: &[Signal],
+        latches: &[Signal],
+    ) -> Vec<Signal> {
+        let mut signals = vec![Signal::False; aig.definitions.len()];
+        for (literal, &signal) in aig.inputs.iter().zip(inputs) {
+            signals[literal.variable()] = signal;
+        }
+        for (latch, &signal) in aig.latches.iter().zip(latches) {
+            signals[latch.literal.variable()] = signal;
+        }
+        for gate in &aig.ands {
+            let a = aig.signal(&signals, gate.inputs[0]);
+            let b = aig.signal(&signals, gate.inputs[1]);
+            signals[gate.output.variable()] = self.and(a, b);
+        }
+        signals
+    }
+
+    pub(crate) fn and(&mut self, a: Signal, b: Signal) -> Signal {
+        let (a, b) = match (a, b) {
+            (Signal::False, _) | (_, Signal::False) => return Signal::False,
+            (Signal::True, other) | (other, Signal::True) => return other,
+            (Signal::Literal(a), Signal::Literal(b)) => (a.min(b), a.max(b)),
+        };
+        if a == b {
+            return Signal::Literal(a);
+        }
+        if a == !b {
+            return Signal::False;
+        }
+
+        let builder = &mut self.builder;
+        let output = *self.gates.entry((a, b)).or_insert_with(|| {
+            let output = builder.new_literal();
+            builder.add_clause(Clause::new(vec![!output, a]));
+            builder.add_clause(Clause::new(vec![!output, b]));
+            builder.add_clause(Clause::new(vec![output, !a, !b]));
+            output
+        });
+        Signal::Literal(output)
+    }
+
+    /// Asserts that the signal is true.
+    pub(crate) fn assert(&mut self, signal: Signal) {
+        Signal::add_clause(&mut self.builder, &[signal]);
+    }
+
+    pub(crate) fn formula(&self) -> &Cnf {
+        self.builder.formula()
+    }
+
+    pub(crate) fn build(self) -> Cnf {
+        self.builder.build()
+    }
+}
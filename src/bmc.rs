@@ -0,0 +1,285 @@
+/*!
+Bounded model checking of safety properties.
+
+A transition system is unrolled into copies of its state and inputs for steps `0..=bound`,
+and a counterexample is a trace from an initial state to a bad state.
+Systems are given either as AIGs, whose latches are the state, or as `Formula`s
+relating the state variables `x` to their next values `x'`.
+
+The system is unrolled step by step into a single CDCL solver: the clauses of a step
+are added just before it is checked, and the steps are constrained only through
+assumptions, so the solver keeps its learned clauses from one bound to the next.
+The first counterexample found is thus one of the shortest.
+*/
+
+use std::collections::HashMap;
+
+use crate::aig::{Aig, AigEncoder, AigLiteral};
+use crate::encode::{CnfBuilder, Signal};
+use crate::expr::{Formula, Transformation};
+use crate::formula::{Cnf, Literal};
+use crate::solver::{CdclConfig, CdclSolver, SolveResult};
+
+/// A transition system over named Boolean state variables.
+#[derive(Debug, Clone)]
+pub struct TransitionSystem {
+    state: Vec<String>,
+    inputs: Vec<String>,
+    init: Formula,
+    trans: Formula,
+    property: Formula,
+}
+
+impl TransitionSystem {
+    /// Creates a system whose initial states satisfy `init`,
+    /// where `trans` relates each state variable `x` to its value `x'` at the next step,
+    /// and `property` must hold in every reachable state.
+    /// The other variables in the formulas are inputs, which are free at every step.
+    pub fn new(state: Vec<String>, init: Formula, trans: Formula, property: Formula) -> Self {
+        let mut inputs: Vec<String> = Vec::new();
+        for name in init
+            .names()
+            .into_iter()
+            .chain(trans.names())
+            .chain(property.names())
+        {
+            let is_state = state
+                .iter()
+                .any(|variable| *variable == name || format!("{}'", variable) == name);
+            if !is_state && !inputs.contains(&name) {
+                inputs.push(name);
+            }
+        }
+
+        TransitionSystem {
+            state,
+            inputs,
+            init,
+            trans,
+            property,
+        }
+    }
+
+    pub fn state(&self) -> &[String] {
+        &self.state
+    }
+
+    /// Returns the inputs in the order of their first occurrence
+    /// in the initial condition, the transition relation, and the property.
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+}
+
+/// The values of the state and the inputs at a step of a trace,
+/// in the order of the state variables (or latches) and inputs of the system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub state: Vec<bool>,
+    pub inputs: Vec<bool>,
+}
+
+/// The result of bounded model checking.
+#[derive(Debug)]
+pub enum BmcResult {
+    /// A trace whose last state is bad.
+    Counterexample(Vec<Step>),
+    /// No bad state is reachable in `bound` steps or less.
+    NoCounterexample { bound: usize },
+    /// The solver gave up at the step.
+    Unknown { step: usize },
+}
+
+impl BmcResult {
+    pub fn is_counterexample(&self) -> bool {
+        matches!(self, BmcResult::Counterexample(_))
+    }
+
+    /// Returns the trace if a counterexample is found.
+    pub fn trace(&self) -> Option<&[Step]> {
+        match self {
+            BmcResult::Counterexample(trace) => Some(trace),
+            _ => None,
+        }
+    }
+}
+
+/// The signals of a step of the unrolling.
+struct Frame {
+    state: Vec<Signal>,
+    inputs: Vec<Signal>,
+    /// Must hold for a trace to reach this step
+    constraints: Vec<Signal>,
+    /// True in a bad state
+    bad: Signal,
+}
+
+/// Searches for a trace of at most `bound` steps violating the property of the system.
+pub fn check(system: &TransitionSystem, bound: usize, config: CdclConfig) -> BmcResult {
+    let transformation = Transformation::PlaistedGreenbaum;
+    let mut builder = CnfBuilder::new(0);
+    let mut added = 0;
+    // The literals of the state and inputs at the previous step
+    let mut previous: Option<(Vec<Literal>, Vec<Literal>)> = None;
+
+    check_steps(bound, config, |solver| {
+        let state: Vec<Literal> = system.state.iter().map(|_| builder.new_literal()).collect();
+        let inputs: Vec<Literal> = system
+            .inputs
+            .iter()
+            .map(|_| builder.new_literal())
+            .collect();
+        let current = literals_by_name(system, &state, &inputs);
+
+        let constraint = match &previous {
+            None => system
+                .init
+                .encode(&mut builder, transformation, &|name| current[name]),
+            Some((previous_state, previous_inputs)) => {
+                let mut names = literals_by_name(system, previous_state, previous_inputs);
+                for (name, &literal) in system.state.iter().zip(&state) {
+                    names.insert(format!("{}'", name), literal);
+                }
+                system
+                    .trans
+                    .encode(&mut builder, transformation, &|name| names[name])
+            }
+        };
+        let bad =
+            (!system.property.clone()).encode(&mut builder, transformation, &|name| current[name]);
+        add_new(solver, builder.formula(), &mut added);
+
+        let frame = Frame {
+            state: state
+                .iter()
+                .map(|&literal| Signal::Literal(literal))
+                .collect(),
+            inputs: inputs
+                .iter()
+                .map(|&literal| Signal::Literal(literal))
+                .collect(),
+            constraints: vec![Signal::Literal(constraint)],
+            bad: Signal::Literal(bad),
+        };
+        previous = Some((state, inputs));
+        frame
+    })
+}
+
+fn literals_by_name(
+    system: &TransitionSystem,
+    state: &[Literal],
+    inputs: &[Literal],
+) -> HashMap<String, Literal> {
+    system
+        .state
+        .iter()
+        .zip(state)
+        .chain(system.inputs.iter().zip(inputs))
+        .map(|(name, &literal)| (name.clone(), literal))
+        .collect()
+}
+
+/// Searches for a trace of at most `bound` steps reaching a state where `bad` is true
+/// while the invariant constraints hold, starting with the latches in their initial state.
+pub fn check_aig(aig: &Aig, bad: AigLiteral, bound: usize, config: CdclConfig) -> BmcResult {
+    // Gates are shared between steps by structural hashing
+    let mut encoder = AigEncoder::new(CnfBuilder::new(0));
+    let mut added = 0;
+    let mut latches: Vec<Signal> = aig
+        .latches()
+        .iter()
+        .map(|latch| encoder.initial_value(latch))
+        .collect();
+
+    check_steps(bound, config, |solver| {
+        let inputs: Vec<Signal> = aig.inputs().iter().map(|_| encoder.input()).collect();
+        let signals = encoder.encode(aig, &inputs, &latches);
+        add_new(solver, encoder.formula(), &mut added);
+
+        let next = aig
+            .latches()
+            .iter()
+            .map(|latch| aig.signal(&signals, latch.next))
+            .collect();
+        Frame {
+            state: std::mem::replace(&mut latches, next),
+            inputs,
+            constraints: aig
+                .constraints()
+                .iter()
+                .map(|&constraint| aig.signal(&signals, constraint))
+                .collect(),
+            bad: aig.signal(&signals, bad),
+        }
+    })
+}
+
+/// Adds the variables of `formula` missing in the solver,
+/// and its clauses after the first `added` ones.
+fn add_new(solver: &mut CdclSolver, formula: &Cnf, added: &mut usize) {
+    while solver.num_variables() < formula.num_variables() {
+        solver.new_variable();
+    }
+    for clause in &formula.clauses()[*added..] {
+        solver.add_clause(clause.clone());
+    }
+    *added = formula.clauses().len();
+}
+
+/// Checks the steps in order with assumptions on a single solver,
+/// where `unroll` adds the clauses of the next step to the solver and returns its frame.
+fn check_steps(
+    bound: usize,
+    config: CdclConfig,
+    mut unroll: impl FnMut(&mut CdclSolver) -> Frame,
+) -> BmcResult {
+    let mut solver = CdclSolver::with_config(Cnf::new(0), config);
+    let mut frames = Vec::new();
+    let mut assumptions = Vec::new();
+
+    for step in 0..=bound {
+        frames.push(unroll(&mut solver));
+        let frame = &frames[step];
+        for &constraint in &frame.constraints {
+            match constraint {
+                Signal::True => (),
+                // No trace reaches this step or any later one
+                Signal::False => return BmcResult::NoCounterexample { bound },
+                Signal::Literal(literal) => assumptions.push(literal),
+            }
+        }
+
+        let mut query = assumptions.clone();
+        match frame.bad {
+            Signal::False => continue,
+            Signal::True => (),
+            Signal::Literal(literal) => query.push(literal),
+        }
+
+        debug!("Checking step {}", step);
+        match solver.solve_with_assumptions(&query) {
+            SolveResult::Sat(model) => {
+                let assignments = model.assignment();
+                let values = |signals: &[Signal]| -> Vec<bool> {
+                    signals
+                        .iter()
+                        .map(|signal| signal.value(assignments))
+                        .collect()
+                };
+                let trace = frames[..=step]
+                    .iter()
+                    .map(|frame| Step {
+                        state: values(&frame.state),
+                        inputs: values(&frame.inputs),
+                    })
+                    .collect();
+                return BmcResult::Counterexample(trace);
+            }
+            SolveResult::Unsat => (),
+            SolveResult::Unknown => return BmcResult::Unknown { step },
+        }
+    }
+
+    BmcResult::NoCounterexample { bound }
+}
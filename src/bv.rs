@@ -0,0 +1,874 @@
+/*!
+Fixed-width bit-vectors and their translation to CNF by bit-blasting.
+
+Every bit-vector term is translated into one circuit signal per bit, least significant first,
+and every operation into gates of an and-inverter graph with constant propagation and
+structural hashing, so operations on constants and repeated subterms cost nothing.
+
+* Bitwise operations, shifts, concatenation and extraction only rearrange or combine bits.
+* Addition is a ripple-carry adder, subtraction adds the two's complement,
+  and multiplication adds shifted partial products, all modulo `2^width`.
+* Shifts by a term are barrel shifters; shifting by the width or more gives zeros,
+  or copies of the sign bit for arithmetic right shifts.
+* Unsigned comparisons take the borrow of a subtraction,
+  and signed ones flip the sign bits first.
+
+Widths are between 1 and 64, so that values fit in `u64`.
+*/
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    ops::{Add, BitAnd, BitOr, BitXor, Mul, Neg, Not, Shl, Sub},
+};
+
+use crate::aig::AigEncoder;
+use crate::encode::{CnfBuilder, Signal};
+use crate::formula::{Cnf, Literal, Model};
+
+const MAX_WIDTH: usize = 64;
+
+/// Binary operations on bit-vectors of the same width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BvOperator {
+    And,
+    Or,
+    Xor,
+    Add,
+    Sub,
+    Mul,
+    /// Shift left
+    Shl,
+    /// Logical shift right
+    Lshr,
+    /// Arithmetic shift right
+    Ashr,
+}
+
+impl BvOperator {
+    pub fn name(self) -> &'static str {
+        match self {
+            BvOperator::And => "bvand",
+            BvOperator::Or => "bvor",
+            BvOperator::Xor => "bvxor",
+            BvOperator::Add => "bvadd",
+            BvOperator::Sub => "bvsub",
+            BvOperator::Mul => "bvmul",
+            BvOperator::Shl => "bvshl",
+            BvOperator::Lshr => "bvlshr",
+            BvOperator::Ashr => "bvashr",
+        }
+    }
+
+    fn apply(self, a: u64, b: u64, width: usize) -> u64 {
+        let shift = |shifted: fn(u64, u32) -> u64, fill: u64| {
+            if b >= width as u64 {
+                fill
+            } else {
+                shifted(a, b as u32)
+            }
+        };
+        let value = match self {
+            BvOperator::And => a & b,
+            BvOperator::Or => a | b,
+            BvOperator::Xor => a ^ b,
+            BvOperator::Add => a.wrapping_add(b),
+            BvOperator::Sub => a.wrapping_sub(b),
+            BvOperator::Mul => a.wrapping_mul(b),
+            BvOperator::Shl => shift(|a, b| a << b, 0),
+            BvOperator::Lshr => shift(|a, b| a >> b, 0),
+            BvOperator::Ashr => {
+                // The sign is extended to 64 bits, so shifting by 63 fills the width
+                ((sign_extend(a, width) as i64) >> b.min(63)) as u64
+            }
+        };
+        value & mask(width)
+    }
+}
+
+/// Comparisons of bit-vectors of the same width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    /// Unsigned less than
+    Ult,
+    /// Unsigned less than or equal
+    Ule,
+    /// Signed less than
+    Slt,
+    /// Signed less than or equal
+    Sle,
+}
+
+impl Comparison {
+    pub fn name(self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Ult => "bvult",
+            Comparison::Ule => "bvule",
+            Comparison::Slt => "bvslt",
+            Comparison::Sle => "bvsle",
+        }
+    }
+
+    fn apply(self, a: u64, b: u64, width: usize) -> bool {
+        let (signed_a, signed_b) = (sign_extend(a, width) as i64, sign_extend(b, width) as i64);
+        match self {
+            Comparison::Eq => a == b,
+            Comparison::Ult => a < b,
+            Comparison::Ule => a <= b,
+            Comparison::Slt => signed_a < signed_b,
+            Comparison::Sle => signed_a <= signed_b,
+        }
+    }
+}
+
+/// A bit-vector term.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitVector {
+    Const {
+        value: u64,
+        width: usize,
+    },
+    Var {
+        name: String,
+        width: usize,
+    },
+    Not(Box<BitVector>),
+    Neg(Box<BitVector>),
+    Binary(BvOperator, Box<BitVector>, Box<BitVector>),
+    /// The first operand is the most significant part
+    Concat(Box<BitVector>, Box<BitVector>),
+    /// Bits `low..=high`
+    Extract {
+        high: usize,
+        low: usize,
+        operand: Box<BitVector>,
+    },
+    /// If-then-else
+    Ite(Box<Bool>, Box<BitVector>, Box<BitVector>),
+}
+
+impl BitVector {
+    /// # Panics
+    ///
+    /// Panics when the width is not between 1 and 64, or the value does not fit.
+    pub fn constant(value: u64, width: usize) -> Self {
+        assert_width(width);
+        assert!(value & !mask(width) == 0);
+        BitVector::Const { value, width }
+    }
+
+    /// # Panics
+    ///
+    /// Panics when the width is not between 1 and 64.
+    pub fn var(name: impl Into<String>, width: usize) -> Self {
+        assert_width(width);
+        BitVector::Var {
+            name: name.into(),
+            width,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics when the widths are different.
+    pub fn binary(operator: BvOperator, a: BitVector, b: BitVector) -> Self {
+        assert_eq!(a.width(), b.width());
+        BitVector::Binary(operator, Box::new(a), Box::new(b))
+    }
+
+    pub fn lshr(a: BitVector, b: BitVector) -> Self {
+        BitVector::binary(BvOperator::Lshr, a, b)
+    }
+
+    pub fn ashr(a: BitVector, b: BitVector) -> Self {
+        BitVector::binary(BvOperator::Ashr, a, b)
+    }
+
+    /// # Panics
+    ///
+    /// Panics when the total width exceeds 64.
+    pub fn concat(high: BitVector, low: BitVector) -> Self {
+        assert_width(high.width() + low.width());
+        BitVector::Concat(Box::new(high), Box::new(low))
+    }
+
+    /// # Panics
+    ///
+    /// Panics unless `low <= high < width`.
+    pub fn extract(high: usize, low: usize, operand: BitVector) -> Self {
+        assert!(low <= high && high < operand.width());
+        BitVector::Extract {
+            high,
+            low,
+            operand: Box::new(operand),
+        }
+    }
+
+    /// Adds `extra` zeros as the most significant bits.
+    pub fn zero_extend(extra: usize, operand: BitVector) -> Self {
+        if extra == 0 {
+            return operand;
+        }
+        BitVector::concat(BitVector::constant(0, extra), operand)
+    }
+
+    /// Adds `extra` copies of the sign bit as the most significant bits.
+    pub fn sign_extend(extra: usize, operand: BitVector) -> Self {
+        let sign = operand.width() - 1;
+        (0..extra).fold(operand.clone(), |extended, _| {
+            BitVector::concat(BitVector::extract(sign, sign, operand.clone()), extended)
+        })
+    }
+
+    /// # Panics
+    ///
+    /// Panics when the widths of the branches are different.
+    pub fn ite(condition: Bool, then: BitVector, otherwise: BitVector) -> Self {
+        assert_eq!(then.width(), otherwise.width());
+        BitVector::Ite(Box::new(condition), Box::new(then), Box::new(otherwise))
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            BitVector::Const { width, .. } | BitVector::Var { width, .. } => *width,
+            BitVector::Not(a) | BitVector::Neg(a) | BitVector::Binary(_, a, _) => a.width(),
+            BitVector::Concat(a, b) => a.width() + b.width(),
+            BitVector::Extract { high, low, .. } => high - low + 1,
+            BitVector::Ite(_, a, _) => a.width(),
+        }
+    }
+
+    /// Evaluates the term with the values of the variables given by `value_of`,
+    /// where Boolean variables are 0 or 1.
+    pub fn value(&self, value_of: &impl Fn(&str) -> u64) -> u64 {
+        let width = self.width();
+        let value = match self {
+            BitVector::Const { value, .. } => *value,
+            BitVector::Var { name, .. } => value_of(name),
+            BitVector::Not(a) => !a.value(value_of),
+            BitVector::Neg(a) => a.value(value_of).wrapping_neg(),
+            BitVector::Binary(operator, a, b) => {
+                operator.apply(a.value(value_of), b.value(value_of), width)
+            }
+            BitVector::Concat(a, b) => {
+                let low_width = b.width();
+                // Shifting by 64 overflows, while the result is the low part then
+                let high = a.value(value_of).checked_shl(low_width as u32).unwrap_or(0);
+                high | b.value(value_of)
+            }
+            BitVector::Extract { low, operand, .. } => operand.value(value_of) >> low,
+            BitVector::Ite(condition, then, otherwise) => {
+                if condition.value(value_of) {
+                    then.value(value_of)
+                } else {
+                    otherwise.value(value_of)
+                }
+            }
+        };
+        value & mask(width)
+    }
+}
+
+impl Not for BitVector {
+    type Output = BitVector;
+
+    fn not(self) -> Self::Output {
+        BitVector::Not(Box::new(self))
+    }
+}
+
+impl Neg for BitVector {
+    type Output = BitVector;
+
+    fn neg(self) -> Self::Output {
+        BitVector::Neg(Box::new(self))
+    }
+}
+
+macro_rules! impl_binary_operator {
+    ($trait:ident, $method:ident, $operator:ident) => {
+        impl $trait for BitVector {
+            type Output = BitVector;
+
+            fn $method(self, rhs: Self) -> Self::Output {
+                BitVector::binary(BvOperator::$operator, self, rhs)
+            }
+        }
+    };
+}
+
+impl_binary_operator!(BitAnd, bitand, And);
+impl_binary_operator!(BitOr, bitor, Or);
+impl_binary_operator!(BitXor, bitxor, Xor);
+impl_binary_operator!(Add, add, Add);
+impl_binary_operator!(Sub, sub, Sub);
+impl_binary_operator!(Mul, mul, Mul);
+impl_binary_operator!(Shl, shl, Shl);
+
+impl Display for BitVector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BitVector::Const { value, width } => write!(f, "(_ bv{} {})", value, width),
+            BitVector::Var { name, .. } => write!(f, "{}", name),
+            BitVector::Not(a) => write!(f, "(bvnot {})", a),
+            BitVector::Neg(a) => write!(f, "(bvneg {})", a),
+            BitVector::Binary(operator, a, b) => write!(f, "({} {} {})", operator.name(), a, b),
+            BitVector::Concat(a, b) => write!(f, "(concat {} {})", a, b),
+            BitVector::Extract { high, low, operand } => {
+                write!(f, "((_ extract {} {}) {})", high, low, operand)
+            }
+            BitVector::Ite(condition, then, otherwise) => {
+                write!(f, "(ite {} {} {})", condition, then, otherwise)
+            }
+        }
+    }
+}
+
+/// A Boolean term over Boolean variables and comparisons of bit-vectors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bool {
+    Const(bool),
+    Var(String),
+    Not(Box<Bool>),
+    /// Conjunction, true if empty
+    And(Vec<Bool>),
+    /// Disjunction, false if empty
+    Or(Vec<Bool>),
+    Xor(Box<Bool>, Box<Bool>),
+    Implies(Box<Bool>, Box<Bool>),
+    Iff(Box<Bool>, Box<Bool>),
+    /// If-then-else
+    Ite(Box<Bool>, Box<Bool>, Box<Bool>),
+    Compare(Comparison, BitVector, BitVector),
+}
+
+impl Bool {
+    pub fn var(name: impl Into<String>) -> Self {
+        Bool::Var(name.into())
+    }
+
+    pub fn and(operands: Vec<Bool>) -> Self {
+        Bool::And(operands)
+    }
+
+    pub fn or(operands: Vec<Bool>) -> Self {
+        Bool::Or(operands)
+    }
+
+    pub fn implies(a: Bool, b: Bool) -> Self {
+        Bool::Implies(Box::new(a), Box::new(b))
+    }
+
+    pub fn iff(a: Bool, b: Bool) -> Self {
+        Bool::Iff(Box::new(a), Box::new(b))
+    }
+
+    pub fn ite(condition: Bool, then: Bool, otherwise: Bool) -> Self {
+        Bool::Ite(Box::new(condition), Box::new(then), Box::new(otherwise))
+    }
+
+    /// # Panics
+    ///
+    /// Panics when the widths are different.
+    pub fn compare(comparison: Comparison, a: BitVector, b: BitVector) -> Self {
+        assert_eq!(a.width(), b.width());
+        Bool::Compare(comparison, a, b)
+    }
+
+    pub fn equal(a: BitVector, b: BitVector) -> Self {
+        Bool::compare(Comparison::Eq, a, b)
+    }
+
+    pub fn ult(a: BitVector, b: BitVector) -> Self {
+        Bool::compare(Comparison::Ult, a, b)
+    }
+
+    pub fn ule(a: BitVector, b: BitVector) -> Self {
+        Bool::compare(Comparison::Ule, a, b)
+    }
+
+    pub fn ugt(a: BitVector, b: BitVector) -> Self {
+        Bool::compare(Comparison::Ult, b, a)
+    }
+
+    pub fn uge(a: BitVector, b: BitVector) -> Self {
+        Bool::compare(Comparison::Ule, b, a)
+    }
+
+    pub fn slt(a: BitVector, b: BitVector) -> Self {
+        Bool::compare(Comparison::Slt, a, b)
+    }
+
+    pub fn sle(a: BitVector, b: BitVector) -> Self {
+        Bool::compare(Comparison::Sle, a, b)
+    }
+
+    pub fn sgt(a: BitVector, b: BitVector) -> Self {
+        Bool::compare(Comparison::Slt, b, a)
+    }
+
+    pub fn sge(a: BitVector, b: BitVector) -> Self {
+        Bool::compare(Comparison::Sle, b, a)
+    }
+
+    /// Evaluates the term with the values of the variables given by `value_of`,
+    /// where Boolean variables are 0 or 1.
+    pub fn value(&self, value_of: &impl Fn(&str) -> u64) -> bool {
+        match self {
+            Bool::Const(value) => *value,
+            Bool::Var(name) => value_of(name) != 0,
+            Bool::Not(a) => !a.value(value_of),
+            Bool::And(operands) => operands.iter().all(|a| a.value(value_of)),
+            Bool::Or(operands) => operands.iter().any(|a| a.value(value_of)),
+            Bool::Xor(a, b) => a.value(value_of) != b.value(value_of),
+            Bool::Implies(a, b) => !a.value(value_of) || b.value(value_of),
+            Bool::Iff(a, b) => a.value(value_of) == b.value(value_of),
+            Bool::Ite(condition, then, otherwise) => {
+                if condition.value(value_of) {
+                    then.value(value_of)
+                } else {
+                    otherwise.value(value_of)
+                }
+            }
+            Bool::Compare(comparison, a, b) => {
+                comparison.apply(a.value(value_of), b.value(value_of), a.width())
+            }
+        }
+    }
+
+    /// Bit-blasts the term into an equisatisfiable CNF asserting it.
+    pub fn to_cnf(&self) -> (Cnf, BvVariables) {
+        let mut blaster = BitBlaster::new();
+        blaster.assert(self);
+        blaster.build()
+    }
+}
+
+impl Not for Bool {
+    type Output = Bool;
+
+    fn not(self) -> Self::Output {
+        Bool::Not(Box::new(self))
+    }
+}
+
+impl BitAnd for Bool {
+    type Output = Bool;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Bool::And(vec![self, rhs])
+    }
+}
+
+impl BitOr for Bool {
+    type Output = Bool;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Bool::Or(vec![self, rhs])
+    }
+}
+
+impl BitXor for Bool {
+    type Output = Bool;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        Bool::Xor(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl Display for Bool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let write_all = |f: &mut std::fmt::Formatter<'_>, name: &str, operands: &[Bool]| {
+            write!(f, "({}", name)?;
+            for a in operands {
+                write!(f, " {}", a)?;
+            }
+            write!(f, ")")
+        };
+
+        match self {
+            Bool::Const(value) => write!(f, "{}", value),
+            Bool::Var(name) => write!(f, "{}", name),
+            Bool::Not(a) => write!(f, "(not {})", a),
+            Bool::And(operands) => write_all(f, "and", operands),
+            Bool::Or(operands) => write_all(f, "or", operands),
+            Bool::Xor(a, b) => write!(f, "(xor {} {})", a, b),
+            Bool::Implies(a, b) => write!(f, "(=> {} {})", a, b),
+            Bool::Iff(a, b) => write!(f, "(= {} {})", a, b),
+            Bool::Ite(condition, then, otherwise) => {
+                write!(f, "(ite {} {} {})", condition, then, otherwise)
+            }
+            Bool::Compare(comparison, a, b) => write!(f, "({} {} {})", comparison.name(), a, b),
+        }
+    }
+}
+
+/// Maps the variables of bit-blasted terms to their literals.
+#[derive(Debug, Clone, Default)]
+pub struct BvVariables {
+    /// The bits of each bit-vector, least significant first
+    bit_vectors: HashMap<String, Vec<Literal>>,
+    booleans: HashMap<String, Literal>,
+}
+
+impl BvVariables {
+    /// Returns the width of the bit-vector variable.
+    pub fn width(&self, name: &str) -> Option<usize> {
+        self.bit_vectors.get(name).map(|bits| bits.len())
+    }
+
+    /// Returns the value of the bit-vector variable in the model.
+    pub fn value(&self, model: &Model, name: &str) -> Option<u64> {
+        self.bit_vectors.get(name).map(|bits| {
+            bits.iter()
+                .enumerate()
+                .filter(|(_, bit)| bit.value(model.assignment()))
+                .fold(0, |value, (index, _)| value | 1 << index)
+        })
+    }
+
+    /// Returns the value of the Boolean variable in the model.
+    pub fn bool_value(&self, model: &Model, name: &str) -> Option<bool> {
+        self.booleans
+            .get(name)
+            .map(|literal| literal.value(model.assignment()))
+    }
+}
+
+/// Bit-blasts terms into a formula, sharing variables and gates between assertions.
+pub struct BitBlaster {
+    encoder: AigEncoder,
+    variables: BvVariables,
+}
+
+impl Default for BitBlaster {
+    fn default() -> Self {
+        BitBlaster::new()
+    }
+}
+
+impl BitBlaster {
+    pub fn new() -> Self {
+        BitBlaster {
+            encoder: AigEncoder::new(CnfBuilder::new(0)),
+            variables: BvVariables::default(),
+        }
+    }
+
+    /// Declares a bit-vector variable, so that it has a value even if it is not used.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the variable is already used with another width.
+    pub fn declare(&mut self, name: &str, width: usize) {
+        self.bit_vector_variable(name, width);
+    }
+
+    /// Declares a Boolean variable, so that it has a value even if it is not used.
+    pub fn declare_bool(&mut self, name: &str) {
+        self.bool_variable(name);
+    }
+
+    /// Adds clauses asserting the term.
+    ///
+    /// # Panics
+    ///
+    /// Panics when a bit-vector variable is used with different widths.
+    pub fn assert(&mut self, term: &Bool) {
+        let signal = self.blast_bool(term);
+        self.encoder.assert(signal);
+    }
+
+    pub fn build(self) -> (Cnf, BvVariables) {
+        (self.encoder.build(), self.variables)
+    }
+
+    fn bit_vector_variable(&mut self, name: &str, width: usize) -> Vec<Signal> {
+        let encoder = &mut self.encoder;
+        let bits = self
+            .variables
+            .bit_vectors
+            .entry(name.to_owned())
+            .or_insert_with(|| {
+                (0..width)
+                    .map(|_| match encoder.input() {
+                        Signal::Literal(literal) => literal,
+                        _ => unreachable!(),
+                    })
+                    .collect()
+            });
+        assert_eq!(
+            bits.len(),
+            width,
+            "'{}' is used with different widths",
+            name
+        );
+        bits.iter()
+            .map(|&literal| Signal::Literal(literal))
+            .collect()
+    }
+
+    fn bool_variable(&mut self, name: &str) -> Signal {
+        let encoder = &mut self.encoder;
+        let literal = *self
+            .variables
+            .booleans
+            .entry(name.to_owned())
+            .or_insert_with(|| match encoder.input() {
+                Signal::Literal(literal) => literal,
+                _ => unreachable!(),
+            });
+        Signal::Literal(literal)
+    }
+
+    fn blast_bool(&mut self, term: &Bool) -> Signal {
+        match term {
+            Bool::Const(false) => Signal::False,
+            Bool::Const(true) => Signal::True,
+            Bool::Var(name) => self.bool_variable(name),
+            Bool::Not(a) => self.blast_bool(a).negate(),
+            Bool::And(operands) => operands.iter().fold(Signal::True, |result, a| {
+                let a = self.blast_bool(a);
+                self.and(result, a)
+            }),
+            Bool::Or(operands) => operands.iter().fold(Signal::False, |result, a| {
+                let a = self.blast_bool(a);
+                self.or(result, a)
+            }),
+            Bool::Xor(a, b) => {
+                let (a, b) = (self.blast_bool(a), self.blast_bool(b));
+                self.xor(a, b)
+            }
+            Bool::Implies(a, b) => {
+                let (a, b) = (self.blast_bool(a), self.blast_bool(b));
+                self.or(a.negate(), b)
+            }
+            Bool::Iff(a, b) => {
+                let (a, b) = (self.blast_bool(a), self.blast_bool(b));
+                self.xor(a, b).negate()
+            }
+            Bool::Ite(condition, then, otherwise) => {
+                let condition = self.blast_bool(condition);
+                let then = self.blast_bool(then);
+                let otherwise = self.blast_bool(otherwise);
+                self.ite(condition, then, otherwise)
+            }
+            Bool::Compare(comparison, a, b) => {
+                let a = self.blast(a);
+                let b = self.blast(b);
+                match comparison {
+                    Comparison::Eq => self.equal(&a, &b),
+                    Comparison::Ult => self.less_than(&a, &b),
+                    Comparison::Ule => self.less_than(&b, &a).negate(),
+                    Comparison::Slt => {
+                        let (a, b) = (flip_sign(&a), flip_sign(&b));
+                        self.less_than(&a, &b)
+                    }
+                    Comparison::Sle => {
+                        let (a, b) = (flip_sign(&a), flip_sign(&b));
+                        self.less_than(&b, &a).negate()
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the signals of the bits, least significant first.
+    fn blast(&mut self, term: &BitVector) -> Vec<Signal> {
+        match term {
+            BitVector::Const { value, width } => (0..*width)
+                .map(|index| constant(value >> index & 1 == 1))
+                .collect(),
+            BitVector::Var { name, width } => self.bit_vector_variable(name, *width),
+            BitVector::Not(a) => self.blast(a).iter().map(|bit| bit.negate()).collect(),
+            BitVector::Neg(a) => {
+                let a = self.blast(a);
+                let zeros = vec![Signal::False; a.len()];
+                self.subtract(&zeros, &a)
+            }
+            BitVector::Binary(operator, a, b) => {
+                let a = self.blast(a);
+                let b = self.blast(b);
+                match operator {
+                    BvOperator::And => self.bitwise(&a, &b, Self::and),
+                    BvOperator::Or => self.bitwise(&a, &b, Self::or),
+                    BvOperator::Xor => self.bitwise(&a, &b, Self::xor),
+                    BvOperator::Add => self.add(&a, &b, Signal::False).0,
+                    BvOperator::Sub => self.subtract(&a, &b),
+                    BvOperator::Mul => self.multiply(&a, &b),
+                    BvOperator::Shl => self.shift(&a, &b, Signal::False, |bits, amount| {
+                        let mut shifted = vec![Signal::False; amount];
+                        shifted.extend_from_slice(&bits[..bits.len() - amount]);
+                        shifted
+                    }),
+                    BvOperator::Lshr => self.shift(&a, &b, Signal::False, |bits, amount| {
+                        let mut shifted = bits[amount..].to_vec();
+                        shifted.resize(bits.len(), Signal::False);
+                        shifted
+                    }),
+                    BvOperator::Ashr => {
+                        let sign = *a.last().unwrap();
+                        self.shift(&a, &b, sign, |bits, amount| {
+                            let mut shifted = bits[amount..].to_vec();
+                            shifted.resize(bits.len(), *bits.last().unwrap());
+                            shifted
+                        })
+                    }
+                }
+            }
+            BitVector::Concat(a, b) => {
+                let mut bits = self.blast(b);
+                bits.extend(self.blast(a));
+                bits
+            }
+            BitVector::Extract { high, low, operand } => self.blast(operand)[*low..=*high].to_vec(),
+            BitVector::Ite(condition, then, otherwise) => {
+                let condition = self.blast_bool(condition);
+                let then = self.blast(then);
+                let otherwise = self.blast(otherwise);
+                then.iter()
+                    .zip(&otherwise)
+                    .map(|(&then, &otherwise)| self.ite(condition, then, otherwise))
+                    .collect()
+            }
+        }
+    }
+
+    fn and(&mut self, a: Signal, b: Signal) -> Signal {
+        self.encoder.and(a, b)
+    }
+
+    fn or(&mut self, a: Signal, b: Signal) -> Signal {
+        self.and(a.negate(), b.negate()).negate()
+    }
+
+    fn xor(&mut self, a: Signal, b: Signal) -> Signal {
+        let a_only = self.and(a, b.negate());
+        let b_only = self.and(a.negate(), b);
+        self.or(a_only, b_only)
+    }
+
+    fn ite(&mut self, condition: Signal, then: Signal, otherwise: Signal) -> Signal {
+        let then = self.and(condition, then);
+        let otherwise = self.and(condition.negate(), otherwise);
+        self.or(then, otherwise)
+    }
+
+    fn bitwise(
+        &mut self,
+        a: &[Signal],
+        b: &[Signal],
+        gate: fn(&mut Self, Signal, Signal) -> Signal,
+    ) -> Vec<Signal> {
+        a.iter().zip(b).map(|(&a, &b)| gate(self, a, b)).collect()
+    }
+
+    /// Returns the sum modulo `2^width` and the carry out.
+    fn add(&mut self, a: &[Signal], b: &[Signal], carry: Signal) -> (Vec<Signal>, Signal) {
+        let mut carry = carry;
+        let mut sum = Vec::with_capacity(a.len());
+        for (&a, &b) in a.iter().zip(b) {
+            let half = self.xor(a, b);
+            sum.push(self.xor(half, carry));
+            let generate = self.and(a, b);
+            let propagate = self.and(half, carry);
+            carry = self.or(generate, propagate);
+        }
+        (sum, carry)
+    }
+
+    /// `a - b = a + ¬b + 1`
+    fn subtract(&mut self, a: &[Signal], b: &[Signal]) -> Vec<Signal> {
+        let negated: Vec<Signal> = b.iter().map(|bit| bit.negate()).collect();
+        self.add(a, &negated, Signal::True).0
+    }
+
+    fn multiply(&mut self, a: &[Signal], b: &[Signal]) -> Vec<Signal> {
+        let width = a.len();
+        let mut product = vec![Signal::False; width];
+        for (shift, &b) in b.iter().enumerate() {
+            // a * b[shift] shifted left, truncated to the width
+            let mut partial = vec![Signal::False; shift];
+            for &a in &a[..width - shift] {
+                partial.push(self.and(a, b));
+            }
+            product = self.add(&product, &partial, Signal::False).0;
+        }
+        product
+    }
+
+    /// The borrow of `a - b`, i.e., no carry out of `a + ¬b + 1`.
+    fn less_than(&mut self, a: &[Signal], b: &[Signal]) -> Signal {
+        let negated: Vec<Signal> = b.iter().map(|bit| bit.negate()).collect();
+        self.add(a, &negated, Signal::True).1.negate()
+    }
+
+    fn equal(&mut self, a: &[Signal], b: &[Signal]) -> Signal {
+        a.iter().zip(b).fold(Signal::True, |result, (&a, &b)| {
+            let same = self.xor(a, b).negate();
+            self.and(result, same)
+        })
+    }
+
+    /// A barrel shifter, where `shifted(bits, amount)` shifts by a constant amount.
+    /// Shifting by the width or more fills every bit with `fill`.
+    fn shift(
+        &mut self,
+        a: &[Signal],
+        b: &[Signal],
+        fill: Signal,
+        shifted: impl Fn(&[Signal], usize) -> Vec<Signal>,
+    ) -> Vec<Signal> {
+        let width = a.len();
+        let mut bits = a.to_vec();
+        let mut overflow = Signal::False;
+        for (stage, &select) in b.iter().enumerate() {
+            let amount = 1usize.checked_shl(stage as u32).unwrap_or(usize::MAX);
+            if amount >= width {
+                overflow = self.or(overflow, select);
+                continue;
+            }
+            let moved = shifted(&bits, amount);
+            bits = bits
+                .iter()
+                .zip(&moved)
+                .map(|(&bit, &moved)| self.ite(select, moved, bit))
+                .collect();
+        }
+        bits.iter()
+            .map(|&bit| self.ite(overflow, fill, bit))
+            .collect()
+    }
+}
+
+fn constant(value: bool) -> Signal {
+    if value {
+        Signal::True
+    } else {
+        Signal::False
+    }
+}
+
+/// Signed comparison is unsigned comparison with the sign bits flipped.
+fn flip_sign(bits: &[Signal]) -> Vec<Signal> {
+    let mut flipped = bits.to_vec();
+    let sign = flipped.len() - 1;
+    flipped[sign] = flipped[sign].negate();
+    flipped
+}
+
+fn assert_width(width: usize) {
+    assert!((1..=MAX_WIDTH).contains(&width), "invalid width {}", width);
+}
+
+fn mask(width: usize) -> u64 {
+    u64::MAX >> (MAX_WIDTH - width)
+}
+
+/// Interprets the value of the width in two's complement, extended to 64 bits.
+fn sign_extend(value: u64, width: usize) -> u64 {
+    let unused = (MAX_WIDTH - width) as u32;
+    (((value << unused) as i64) >> unused) as u64
+}
@@ -0,0 +1,211 @@
+/*!
+Encodings of common constraints into clauses.
+
+`CnfBuilder` collects clauses into a `Cnf` and allocates the fresh variables
+the encodings need, numbered after the variables of the formula.
+
+* At most one: pairwise, sequential counter, commander, product, and bimander.
+* At most k: totalizer, sequential counter, and cardinality networks.
+* Exactly one, and at least k on top of them.
+* Pseudo-Boolean constraints: BDD, adder, and sorting network.
+*/
+
+use crate::formula::{Clause, Cnf, Literal, PbConstraint, Variable};
+use crate::prelude::*;
+
+pub use self::amk::AmkEncoding;
+pub use self::amo::AmoEncoding;
+pub use self::pb::PbEncoding;
+
+mod amk;
+mod amo;
+mod pb;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Unknown encoding '{}'", name))]
+    UnknownEncoding { name: String },
+}
+
+/// Builds a formula clause by clause, allocating fresh variables on demand.
+#[derive(Debug, Clone)]
+pub struct CnfBuilder {
+    formula: Cnf,
+}
+
+impl CnfBuilder {
+    pub fn new(num_variables: usize) -> Self {
+        CnfBuilder {
+            formula: Cnf::new(num_variables),
+        }
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.formula.num_variables()
+    }
+
+    /// Adds a fresh variable numbered after the existing variables.
+    pub fn new_variable(&mut self) -> Variable {
+        self.formula.new_variable()
+    }
+
+    /// Adds a fresh variable and returns its positive literal.
+    pub fn new_literal(&mut self) -> Literal {
+        Literal::new(self.new_variable(), true)
+    }
+
+    /// Adds a clause to the formula.
+    /// Unlike `Cnf::add_clause`, an empty clause makes the formula unsatisfiable.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `clause` contains invalid literals.
+    pub fn add_clause(&mut self, clause: Clause) {
+        if clause.is_empty() {
+            // The empty clause is only counted by `Cnf`, so assert `y ∧ ¬y` instead
+            let literal = self.new_literal();
+            self.formula.add_clause(Clause::new(vec![literal]));
+            self.formula.add_clause(Clause::new(vec![!literal]));
+        } else {
+            self.formula.add_clause(clause);
+        }
+    }
+
+    /// Adds clauses asserting that at least one of the literals is true.
+    pub fn at_least_one(&mut self, literals: &[Literal]) {
+        self.add_clause(Clause::new(literals.to_vec()));
+    }
+
+    /// Adds clauses asserting that at most one of the literals is true.
+    pub fn at_most_one(&mut self, literals: &[Literal], encoding: AmoEncoding) {
+        encoding.encode(literals, self);
+    }
+
+    /// Adds clauses asserting that exactly one of the literals is true.
+    pub fn exactly_one(&mut self, literals: &[Literal], encoding: AmoEncoding) {
+        self.at_least_one(literals);
+        self.at_most_one(literals, encoding);
+    }
+
+    /// Adds clauses asserting that at most `k` of the literals are true.
+    pub fn at_most_k(&mut self, literals: &[Literal], k: usize, encoding: AmkEncoding) {
+        encoding.encode(literals, k, self);
+    }
+
+    /// Adds clauses asserting that at least `k` of the literals are true,
+    /// i.e., at most `n - k` of their negations are true.
+    pub fn at_least_k(&mut self, literals: &[Literal], k: usize, encoding: AmkEncoding) {
+        if k > literals.len() {
+            self.add_clause(Clause::new(Vec::new()));
+            return;
+        }
+        let negated: Vec<Literal> = literals.iter().map(|&literal| !literal).collect();
+        self.at_most_k(&negated, literals.len() - k, encoding);
+    }
+
+    /// Adds clauses asserting a pseudo-Boolean constraint.
+    pub fn add_pb(&mut self, pb: &PbConstraint, encoding: PbEncoding) {
+        encoding.encode(pb, self);
+    }
+
+    /// Returns the formula built so far.
+    pub fn formula(&self) -> &Cnf {
+        &self.formula
+    }
+
+    pub fn build(self) -> Cnf {
+        self.formula
+    }
+}
+
+impl From<Cnf> for CnfBuilder {
+    /// Continues building on top of `formula`.
+    fn from(formula: Cnf) -> Self {
+        CnfBuilder { formula }
+    }
+}
+
+/// A signal in a circuit, which may be a constant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Signal {
+    False,
+    True,
+    Literal(Literal),
+}
+
+impl Signal {
+    pub(crate) fn new(builder: &mut CnfBuilder) -> Signal {
+        Signal::Literal(builder.new_literal())
+    }
+
+    pub(crate) fn value(self, assignments: &[bool]) -> bool {
+        match self {
+            Signal::False => false,
+            Signal::True => true,
+            Signal::Literal(literal) => literal.value(assignments),
+        }
+    }
+
+    pub(crate) fn negate(self) -> Signal {
+        match self {
+            Signal::False => Signal::True,
+            Signal::True => Signal::False,
+            Signal::Literal(literal) => Signal::Literal(!literal),
+        }
+    }
+
+    /// Adds the clause with the constants removed,
+    /// unless it is satisfied by a constant.
+    pub(crate) fn add_clause(builder: &mut CnfBuilder, signals: &[Signal]) {
+        let mut literals = Vec::new();
+        for signal in signals {
+            match signal {
+                Signal::True => return,
+                Signal::False => (),
+                Signal::Literal(literal) => literals.push(*literal),
+            }
+        }
+        builder.add_clause(Clause::new(literals));
+    }
+}
+
+/// The direction of the bound a sorting network is used for,
+/// which decides the implications its comparators need.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Bound {
+    /// Outputs are implied by the inputs, so that false outputs bound the true inputs
+    AtMost,
+    /// Outputs imply the inputs, so that true outputs bound the true inputs
+    AtLeast,
+}
+
+/// Sorts two signals in descending order, returning the max `a ∨ b`
+/// followed by the min `a ∧ b`, with the implications `bound` needs.
+pub(crate) fn comparator(
+    a: Signal,
+    b: Signal,
+    bound: Bound,
+    builder: &mut CnfBuilder,
+) -> (Signal, Signal) {
+    match (a, b) {
+        (Signal::False, other) | (other, Signal::False) => (other, Signal::False),
+        (Signal::True, other) | (other, Signal::True) => (Signal::True, other),
+        _ => {
+            let max = Signal::new(builder);
+            let min = Signal::new(builder);
+            match bound {
+                Bound::AtMost => {
+                    Signal::add_clause(builder, &[a.negate(), max]);
+                    Signal::add_clause(builder, &[b.negate(), max]);
+                    Signal::add_clause(builder, &[a.negate(), b.negate(), min]);
+                }
+                Bound::AtLeast => {
+                    Signal::add_clause(builder, &[max.negate(), a, b]);
+                    Signal::add_clause(builder, &[min.negate(), a]);
+                    Signal::add_clause(builder, &[min.negate(), b]);
+                }
+            }
+            (max, min)
+        }
+    }
+}
@@ -0,0 +1,255 @@
+/*!
+At-most-k encodings.
+
+* Totalizer (Bailleux and Boufkhad, 2003): a binary tree of unary adders,
+  with outputs beyond `k + 1` dropped.
+* Sequential counter (Sinz, 2005): registers count the true literals
+  among the prefixes in unary.
+* Cardinality networks (Asín et al., 2011): the literals are sorted in blocks,
+  which are combined with simplified merge networks keeping only the largest outputs.
+
+Counting signals are only implied by the literals, which is all at-most constraints need.
+*/
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::formula::{Clause, Literal};
+use crate::prelude::*;
+
+use super::{comparator, Bound, CnfBuilder, Error, Signal, UnknownEncoding};
+
+/// Selects how at-most-k constraints are translated into clauses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmkEncoding {
+    /// Tree of unary adders
+    #[default]
+    Totalizer,
+    /// Unary registers over prefixes
+    SequentialCounter,
+    /// Sorting and simplified merge networks
+    CardinalityNetwork,
+}
+
+impl AmkEncoding {
+    pub const ALL: [AmkEncoding; 3] = [
+        AmkEncoding::Totalizer,
+        AmkEncoding::SequentialCounter,
+        AmkEncoding::CardinalityNetwork,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AmkEncoding::Totalizer => "totalizer",
+            AmkEncoding::SequentialCounter => "sequential",
+            AmkEncoding::CardinalityNetwork => "network",
+        }
+    }
+
+    pub(super) fn encode(self, literals: &[Literal], k: usize, builder: &mut CnfBuilder) {
+        if k >= literals.len() {
+            return;
+        }
+        if k == 0 {
+            for &literal in literals {
+                builder.add_clause(Clause::new(vec![!literal]));
+            }
+            return;
+        }
+
+        match self {
+            AmkEncoding::Totalizer => encode_totalizer(literals, k, builder),
+            AmkEncoding::SequentialCounter => encode_sequential_counter(literals, k, builder),
+            AmkEncoding::CardinalityNetwork => encode_cardinality_network(literals, k, builder),
+        }
+    }
+}
+
+impl FromStr for AmkEncoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AmkEncoding::ALL
+            .iter()
+            .copied()
+            .find(|encoding| encoding.name() == s)
+            .context(UnknownEncoding { name: s })
+    }
+}
+
+impl Display for AmkEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+fn encode_totalizer(literals: &[Literal], k: usize, builder: &mut CnfBuilder) {
+    let outputs = totalizer_node(literals, k + 1, builder);
+    builder.add_clause(Clause::new(vec![!outputs[k]]));
+}
+
+/// Returns unary outputs where `outputs[j]` is implied when more than `j` literals are true,
+/// counting up to `limit`.
+fn totalizer_node(literals: &[Literal], limit: usize, builder: &mut CnfBuilder) -> Vec<Literal> {
+    if literals.len() == 1 {
+        return literals.to_vec();
+    }
+
+    let (left, right) = literals.split_at(literals.len() / 2);
+    let left = totalizer_node(left, limit, builder);
+    let right = totalizer_node(right, limit, builder);
+    let outputs: Vec<Literal> = (0..literals.len().min(limit))
+        .map(|_| builder.new_literal())
+        .collect();
+
+    // i true on the left and j true on the right imply i + j true in total
+    for i in 0..=left.len() {
+        for j in 0..=right.len() {
+            let sum = i + j;
+            if sum == 0 || sum > outputs.len() {
+                continue;
+            }
+            let mut clause = vec![outputs[sum - 1]];
+            if i > 0 {
+                clause.push(!left[i - 1]);
+            }
+            if j > 0 {
+                clause.push(!right[j - 1]);
+            }
+            builder.add_clause(Clause::new(clause));
+        }
+    }
+
+    outputs
+}
+
+/// Requires `0 < k < literals.len()`.
+pub(super) fn encode_sequential_counter(literals: &[Literal], k: usize, builder: &mut CnfBuilder) {
+    // counters[i][j] is implied when more than j of literals[..=i] are true
+    let mut counters: Vec<Vec<Literal>> = Vec::new();
+    for (i, &literal) in literals.iter().enumerate() {
+        if i > 0 {
+            // Overflow
+            builder.add_clause(Clause::new(vec![!literal, !counters[i - 1][k - 1]]));
+        }
+        if i + 1 == literals.len() {
+            break;
+        }
+
+        let counter: Vec<Literal> = (0..k).map(|_| builder.new_literal()).collect();
+        builder.add_clause(Clause::new(vec![!literal, counter[0]]));
+        if i == 0 {
+            for &register in &counter[1..] {
+                builder.add_clause(Clause::new(vec![!register]));
+            }
+        } else {
+            let previous = &counters[i - 1];
+            for j in 0..k {
+                builder.add_clause(Clause::new(vec![!previous[j], counter[j]]));
+                if j > 0 {
+                    builder.add_clause(Clause::new(vec![!literal, !previous[j - 1], counter[j]]));
+                }
+            }
+        }
+        counters.push(counter);
+    }
+}
+
+fn encode_cardinality_network(literals: &[Literal], k: usize, builder: &mut CnfBuilder) {
+    // Blocks of a power of two large enough to count k + 1
+    let block_size = (k + 1).next_power_of_two();
+    let mut inputs: Vec<Signal> = literals
+        .iter()
+        .map(|&literal| Signal::Literal(literal))
+        .collect();
+    inputs.resize(inputs.len().next_multiple_of(block_size), Signal::False);
+
+    let outputs = cardinality_network(&inputs, block_size, builder);
+    Signal::add_clause(builder, &[outputs[k].negate()]);
+}
+
+/// Returns the `block_size` largest of the sorted inputs.
+fn cardinality_network(
+    inputs: &[Signal],
+    block_size: usize,
+    builder: &mut CnfBuilder,
+) -> Vec<Signal> {
+    if inputs.len() == block_size {
+        return sort(inputs, builder);
+    }
+
+    let (first, rest) = inputs.split_at(block_size);
+    let first = cardinality_network(first, block_size, builder);
+    let rest = cardinality_network(rest, block_size, builder);
+    let mut outputs = simplified_merge(&first, &rest, builder);
+    outputs.truncate(block_size);
+    outputs
+}
+
+/// Sorts a power-of-two number of inputs in descending order.
+fn sort(inputs: &[Signal], builder: &mut CnfBuilder) -> Vec<Signal> {
+    if inputs.len() == 1 {
+        return inputs.to_vec();
+    }
+
+    let (first, second) = inputs.split_at(inputs.len() / 2);
+    let first = sort(first, builder);
+    let second = sort(second, builder);
+    merge(&first, &second, builder)
+}
+
+/// Merges two sorted sequences of the same power-of-two length.
+fn merge(a: &[Signal], b: &[Signal], builder: &mut CnfBuilder) -> Vec<Signal> {
+    if a.len() == 1 {
+        let (high, low) = comparator(a[0], b[0], Bound::AtMost, builder);
+        return vec![high, low];
+    }
+
+    let (odd, even) = merge_halves(a, b, merge, builder);
+    let mut outputs = vec![odd[0]];
+    for i in 1..odd.len() {
+        let (high, low) = comparator(odd[i], even[i - 1], Bound::AtMost, builder);
+        outputs.push(high);
+        outputs.push(low);
+    }
+    outputs.push(*even.last().unwrap());
+    outputs
+}
+
+/// Merges two sorted sequences of the same power-of-two length `n`,
+/// returning only the `n + 1` largest outputs.
+fn simplified_merge(a: &[Signal], b: &[Signal], builder: &mut CnfBuilder) -> Vec<Signal> {
+    if a.len() == 1 {
+        let (high, low) = comparator(a[0], b[0], Bound::AtMost, builder);
+        return vec![high, low];
+    }
+
+    let (odd, even) = merge_halves(a, b, simplified_merge, builder);
+    let mut outputs = vec![odd[0]];
+    for i in 1..=a.len() / 2 {
+        let (high, low) = comparator(odd[i], even[i - 1], Bound::AtMost, builder);
+        outputs.push(high);
+        outputs.push(low);
+    }
+    outputs
+}
+
+/// Merges the elements at even and odd positions separately.
+fn merge_halves(
+    a: &[Signal],
+    b: &[Signal],
+    merge: fn(&[Signal], &[Signal], &mut CnfBuilder) -> Vec<Signal>,
+    builder: &mut CnfBuilder,
+) -> (Vec<Signal>, Vec<Signal>) {
+    let odd = |signals: &[Signal]| signals.iter().step_by(2).copied().collect::<Vec<_>>();
+    let even = |signals: &[Signal]| {
+        signals
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .copied()
+            .collect::<Vec<_>>()
+    };
+    let odd = merge(&odd(a), &odd(b), builder);
+    let even = merge(&even(a), &even(b), builder);
+    (odd, even)
+}
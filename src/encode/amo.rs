@@ -0,0 +1,163 @@
+/*!
+At-most-one encodings.
+
+* Pairwise: a binary clause for every pair, without fresh variables.
+* Sequential counter (Sinz, 2005): a register remembers that a literal was true.
+* Commander (Klieber and Kwon, 2007): literals are split into groups of three,
+  each with a commander implied by its members, and the commanders are constrained recursively.
+* Product (Chen, 2010): literals are arranged in a grid,
+  and at most one row and one column may be selected.
+* Bimander (Nguyen and Mai, 2015): literals are split into pairs,
+  and the index of the pair with a true literal is encoded in binary.
+*/
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::formula::{Clause, Literal};
+use crate::prelude::*;
+
+use super::{amk, CnfBuilder, Error, UnknownEncoding};
+
+/// Selects how at-most-one constraints are translated into clauses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmoEncoding {
+    /// Binary clauses for all pairs
+    Pairwise,
+    /// Sequential counter with one register per literal
+    #[default]
+    Sequential,
+    /// Recursive commander variables
+    Commander,
+    /// Two-dimensional grid
+    Product,
+    /// Pairs with binary indices
+    Bimander,
+}
+
+/// Groups of at most this size are encoded pairwise.
+const PAIRWISE_LIMIT: usize = 4;
+
+impl AmoEncoding {
+    pub const ALL: [AmoEncoding; 5] = [
+        AmoEncoding::Pairwise,
+        AmoEncoding::Sequential,
+        AmoEncoding::Commander,
+        AmoEncoding::Product,
+        AmoEncoding::Bimander,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AmoEncoding::Pairwise => "pairwise",
+            AmoEncoding::Sequential => "sequential",
+            AmoEncoding::Commander => "commander",
+            AmoEncoding::Product => "product",
+            AmoEncoding::Bimander => "bimander",
+        }
+    }
+
+    pub(super) fn encode(self, literals: &[Literal], builder: &mut CnfBuilder) {
+        if literals.len() <= 1 {
+            return;
+        }
+
+        match self {
+            AmoEncoding::Pairwise => encode_pairwise(literals, builder),
+            AmoEncoding::Sequential => amk::encode_sequential_counter(literals, 1, builder),
+            AmoEncoding::Commander => encode_commander(literals, builder),
+            AmoEncoding::Product => encode_product(literals, builder),
+            AmoEncoding::Bimander => encode_bimander(literals, builder),
+        }
+    }
+}
+
+impl FromStr for AmoEncoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AmoEncoding::ALL
+            .iter()
+            .copied()
+            .find(|encoding| encoding.name() == s)
+            .context(UnknownEncoding { name: s })
+    }
+}
+
+impl Display for AmoEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+fn encode_pairwise(literals: &[Literal], builder: &mut CnfBuilder) {
+    for (i, &a) in literals.iter().enumerate() {
+        for &b in &literals[i + 1..] {
+            builder.add_clause(Clause::new(vec![!a, !b]));
+        }
+    }
+}
+
+fn encode_commander(literals: &[Literal], builder: &mut CnfBuilder) {
+    const GROUP_SIZE: usize = 3;
+
+    if literals.len() <= PAIRWISE_LIMIT {
+        encode_pairwise(literals, builder);
+        return;
+    }
+
+    let mut commanders = Vec::new();
+    for group in literals.chunks(GROUP_SIZE) {
+        // The commander is true iff a member is true, and then the only one
+        let commander = builder.new_literal();
+        encode_pairwise(group, builder);
+        for &literal in group {
+            builder.add_clause(Clause::new(vec![!literal, commander]));
+        }
+        let mut clause = vec![!commander];
+        clause.extend_from_slice(group);
+        builder.add_clause(Clause::new(clause));
+        commanders.push(commander);
+    }
+    encode_commander(&commanders, builder);
+}
+
+fn encode_product(literals: &[Literal], builder: &mut CnfBuilder) {
+    if literals.len() <= PAIRWISE_LIMIT {
+        encode_pairwise(literals, builder);
+        return;
+    }
+
+    // literals[i] is at row i / num_columns and column i % num_columns
+    let num_columns = (literals.len() as f64).sqrt().ceil() as usize;
+    let num_rows = literals.len().div_ceil(num_columns);
+    let rows: Vec<Literal> = (0..num_rows).map(|_| builder.new_literal()).collect();
+    let columns: Vec<Literal> = (0..num_columns).map(|_| builder.new_literal()).collect();
+    for (i, &literal) in literals.iter().enumerate() {
+        builder.add_clause(Clause::new(vec![!literal, rows[i / num_columns]]));
+        builder.add_clause(Clause::new(vec![!literal, columns[i % num_columns]]));
+    }
+    encode_product(&rows, builder);
+    encode_product(&columns, builder);
+}
+
+fn encode_bimander(literals: &[Literal], builder: &mut CnfBuilder) {
+    const GROUP_SIZE: usize = 2;
+
+    let num_groups = literals.len().div_ceil(GROUP_SIZE);
+    let num_bits = (usize::BITS - (num_groups - 1).leading_zeros()) as usize;
+    let bits: Vec<Literal> = (0..num_bits).map(|_| builder.new_literal()).collect();
+    for (index, group) in literals.chunks(GROUP_SIZE).enumerate() {
+        encode_pairwise(group, builder);
+        // A true member selects the group index
+        for &literal in group {
+            for (bit, &bit_literal) in bits.iter().enumerate() {
+                let selected = if index >> bit & 1 == 1 {
+                    bit_literal
+                } else {
+                    !bit_literal
+                };
+                builder.add_clause(Clause::new(vec![!literal, selected]));
+            }
+        }
+    }
+}
@@ -0,0 +1,300 @@
+/*!
+Encodings of pseudo-Boolean constraints into clauses.
+
+Every encoding introduces fresh variables and asserts that the constraint holds.
+Auxiliary variables are only constrained in the direction needed for soundness,
+except for the adder, whose sums must be exact.
+
+* BDD (Eén and Sörensson, 2006): a decision diagram over the terms,
+  with nodes shared by the remaining bound.
+* Adder: a network of full adders computes the sum in binary,
+  which is compared with the bound.
+* Sorting network: each literal is repeated as many times as its coefficient,
+  and an odd-even merge sorting network counts the true literals in unary.
+  It suits constraints with small coefficients, and constraints whose coefficients
+  sum to more than `MAX_SORTER_WIRES` are encoded with the adder instead.
+*/
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    str::FromStr,
+};
+
+use crate::formula::{Clause, Literal, PbConstraint};
+use crate::prelude::*;
+
+use super::{comparator, Bound, CnfBuilder, Error, Signal, UnknownEncoding};
+
+/// The largest sum of coefficients encoded with a sorting network.
+const MAX_SORTER_WIRES: u64 = 1 << 12;
+
+/// Selects how pseudo-Boolean constraints are translated into clauses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PbEncoding {
+    /// Binary decision diagram
+    #[default]
+    Bdd,
+    /// Binary adder network and comparator
+    Adder,
+    /// Unary sorting network
+    SortingNetwork,
+}
+
+impl PbEncoding {
+    pub const ALL: [PbEncoding; 3] = [
+        PbEncoding::Bdd,
+        PbEncoding::Adder,
+        PbEncoding::SortingNetwork,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PbEncoding::Bdd => "bdd",
+            PbEncoding::Adder => "adder",
+            PbEncoding::SortingNetwork => "sorter",
+        }
+    }
+
+    /// Adds clauses asserting the constraint to the formula.
+    pub(super) fn encode(self, pb: &PbConstraint, builder: &mut CnfBuilder) {
+        if pb.bound() == 0 {
+            return;
+        }
+        let sum = pb.terms().iter().fold(0u64, |sum, (coefficient, _)| {
+            sum.saturating_add(*coefficient)
+        });
+        if sum < pb.bound() {
+            builder.add_clause(Clause::new(Vec::new()));
+            return;
+        }
+
+        match self {
+            PbEncoding::Bdd => encode_bdd(pb, builder),
+            PbEncoding::Adder => encode_adder(pb, builder),
+            PbEncoding::SortingNetwork if sum > MAX_SORTER_WIRES => {
+                debug!(
+                    "Encode {} with an adder, as its sorting network is too large",
+                    pb
+                );
+                encode_adder(pb, builder)
+            }
+            PbEncoding::SortingNetwork => encode_sorting_network(pb, builder),
+        }
+    }
+}
+
+impl FromStr for PbEncoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PbEncoding::ALL
+            .iter()
+            .copied()
+            .find(|encoding| encoding.name() == s)
+            .context(UnknownEncoding { name: s })
+    }
+}
+
+impl Display for PbEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+fn encode_bdd(pb: &PbConstraint, builder: &mut CnfBuilder) {
+    let terms = pb.terms();
+
+    // suffix_sums[i] is the largest sum of terms[i..]
+    let mut suffix_sums = vec![0u64; terms.len() + 1];
+    for i in (0..terms.len()).rev() {
+        suffix_sums[i] = suffix_sums[i + 1].saturating_add(terms[i].0);
+    }
+
+    let mut memo = HashMap::new();
+    let root = bdd_node(terms, &suffix_sums, 0, pb.bound(), &mut memo, builder);
+    Signal::add_clause(builder, &[root]);
+}
+
+/// Returns a signal implying that `terms[index..]` sum to at least `bound`.
+fn bdd_node(
+    terms: &[(u64, Literal)],
+    suffix_sums: &[u64],
+    index: usize,
+    bound: u64,
+    memo: &mut HashMap<(usize, u64), Signal>,
+    builder: &mut CnfBuilder,
+) -> Signal {
+    if bound == 0 {
+        return Signal::True;
+    }
+    if suffix_sums[index] < bound {
+        return Signal::False;
+    }
+    if let Some(&node) = memo.get(&(index, bound)) {
+        return node;
+    }
+
+    let (coefficient, literal) = terms[index];
+    let high = bdd_node(
+        terms,
+        suffix_sums,
+        index + 1,
+        bound.saturating_sub(coefficient),
+        memo,
+        builder,
+    );
+    let low = bdd_node(terms, suffix_sums, index + 1, bound, memo, builder);
+
+    let node = if high == low {
+        high
+    } else {
+        // node ∧ literal → high, node ∧ ¬literal → low; `low` implies `high`
+        let node = Signal::new(builder);
+        Signal::add_clause(builder, &[node.negate(), high]);
+        Signal::add_clause(builder, &[node.negate(), Signal::Literal(literal), low]);
+        node
+    };
+    memo.insert((index, bound), node);
+    node
+}
+
+fn encode_adder(pb: &PbConstraint, builder: &mut CnfBuilder) {
+    // buckets[j] holds signals of weight 2^j
+    let mut buckets: Vec<VecDeque<Signal>> = Vec::new();
+    for &(coefficient, literal) in pb.terms() {
+        for bit in 0..u64::BITS as usize - coefficient.leading_zeros() as usize {
+            if coefficient >> bit & 1 == 1 {
+                if buckets.len() <= bit {
+                    buckets.resize(bit + 1, VecDeque::new());
+                }
+                buckets[bit].push_back(Signal::Literal(literal));
+            }
+        }
+    }
+
+    // Reduce each bucket to a single bit, carrying into the next bucket
+    let mut sum_bits = Vec::new();
+    let mut bit = 0;
+    while bit < buckets.len() {
+        while buckets[bit].len() >= 2 {
+            let a = buckets[bit].pop_front().unwrap();
+            let b = buckets[bit].pop_front().unwrap();
+            let (sum, carry) = if buckets[bit].is_empty() {
+                half_adder(builder, a, b)
+            } else {
+                let c = buckets[bit].pop_front().unwrap();
+                full_adder(builder, a, b, c)
+            };
+            buckets[bit].push_back(sum);
+            if buckets.len() <= bit + 1 {
+                buckets.push(VecDeque::new());
+            }
+            buckets[bit + 1].push_back(carry);
+        }
+        sum_bits.push(buckets[bit].pop_front().unwrap_or(Signal::False));
+        bit += 1;
+    }
+
+    // sum ≥ bound iff for every 1-bit of the bound, the sum has the bit
+    // or a higher bit that is 0 in the bound
+    let bound = pb.bound();
+    let num_bits = (u64::BITS - bound.leading_zeros()) as usize;
+    let sum_bit = |bit: usize| sum_bits.get(bit).copied().unwrap_or(Signal::False);
+    for bit in 0..num_bits.max(sum_bits.len()) {
+        if bit < 64 && bound >> bit & 1 == 1 {
+            let mut clause = vec![sum_bit(bit)];
+            for higher in bit + 1..num_bits.max(sum_bits.len()) {
+                if higher >= 64 || bound >> higher & 1 == 0 {
+                    clause.push(sum_bit(higher));
+                }
+            }
+            Signal::add_clause(builder, &clause);
+        }
+    }
+}
+
+/// Returns `(a ⊕ b, a ∧ b)`.
+fn half_adder(builder: &mut CnfBuilder, a: Signal, b: Signal) -> (Signal, Signal) {
+    let sum = Signal::new(builder);
+    let carry = Signal::new(builder);
+    let (n_a, n_b, n_sum, n_carry) = (a.negate(), b.negate(), sum.negate(), carry.negate());
+
+    Signal::add_clause(builder, &[n_a, n_b, n_sum]);
+    Signal::add_clause(builder, &[a, b, n_sum]);
+    Signal::add_clause(builder, &[n_a, b, sum]);
+    Signal::add_clause(builder, &[a, n_b, sum]);
+
+    Signal::add_clause(builder, &[n_a, n_b, carry]);
+    Signal::add_clause(builder, &[a, n_carry]);
+    Signal::add_clause(builder, &[b, n_carry]);
+
+    (sum, carry)
+}
+
+/// Returns `(a ⊕ b ⊕ c, majority(a, b, c))`.
+fn full_adder(builder: &mut CnfBuilder, a: Signal, b: Signal, c: Signal) -> (Signal, Signal) {
+    let sum = Signal::new(builder);
+    let carry = Signal::new(builder);
+    let inputs = [a, b, c];
+
+    // The sum is true iff an odd number of inputs are true
+    for assignment in 0..8usize {
+        let odd = assignment.count_ones() % 2 == 1;
+        let mut clause: Vec<Signal> = (0..3)
+            .map(|i| {
+                if assignment >> i & 1 == 1 {
+                    inputs[i].negate()
+                } else {
+                    inputs[i]
+                }
+            })
+            .collect();
+        clause.push(if odd { sum } else { sum.negate() });
+        Signal::add_clause(builder, &clause);
+    }
+
+    // The carry is true iff at least two inputs are true
+    for (i, j) in [(0, 1), (0, 2), (1, 2)] {
+        Signal::add_clause(builder, &[inputs[i].negate(), inputs[j].negate(), carry]);
+        Signal::add_clause(builder, &[inputs[i], inputs[j], carry.negate()]);
+    }
+
+    (sum, carry)
+}
+
+fn encode_sorting_network(pb: &PbConstraint, builder: &mut CnfBuilder) {
+    let mut wires = Vec::new();
+    for &(coefficient, literal) in pb.terms() {
+        for _ in 0..coefficient {
+            wires.push(Signal::Literal(literal));
+        }
+    }
+    wires.resize(wires.len().next_power_of_two(), Signal::False);
+
+    // Batcher's odd-even merge sort, in descending order
+    let size = wires.len();
+    let mut p = 1;
+    while p < size {
+        let mut k = p;
+        while k >= 1 {
+            let mut j = k % p;
+            while j + k < size {
+                for i in 0..k.min(size - j - k) {
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                        let (high, low) =
+                            comparator(wires[i + j], wires[i + j + k], Bound::AtLeast, builder);
+                        wires[i + j] = high;
+                        wires[i + j + k] = low;
+                    }
+                }
+                j += 2 * k;
+            }
+            k /= 2;
+        }
+        p *= 2;
+    }
+
+    // The `bound`-th largest input is true
+    Signal::add_clause(builder, &[wires[pb.bound() as usize - 1]]);
+}
@@ -0,0 +1,449 @@
+/*!
+Boolean expressions over named variables and their conversion to CNF.
+
+Each compound subexpression is named by a fresh variable, and clauses relate it
+to its operands, so the formula grows linearly instead of exponentially.
+
+* Tseitin: the fresh variable is equivalent to its subexpression.
+* Plaisted–Greenbaum: only the implications required by the polarity of the subexpression
+  are encoded, e.g., `t → a ∧ b` for a subexpression that occurs only positively.
+  The result is equisatisfiable with about half the clauses.
+
+Negations, and conjunctions or disjunctions of a single operand, need no fresh variable.
+*/
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    ops::{BitAnd, BitOr, BitXor, Not},
+    str::FromStr,
+};
+
+use crate::encode::CnfBuilder;
+use crate::formula::{Clause, Cnf, Literal, Model, Variable};
+use crate::prelude::*;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Unknown CNF transformation '{}'", name))]
+    UnknownTransformation { name: String },
+}
+
+/// Boolean expression over named variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Formula {
+    Const(bool),
+    Var(String),
+    Not(Box<Formula>),
+    /// Conjunction, true if empty
+    And(Vec<Formula>),
+    /// Disjunction, false if empty
+    Or(Vec<Formula>),
+    Xor(Box<Formula>, Box<Formula>),
+    Implies(Box<Formula>, Box<Formula>),
+    Iff(Box<Formula>, Box<Formula>),
+    /// If-then-else
+    Ite(Box<Formula>, Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    pub fn var(name: impl Into<String>) -> Self {
+        Formula::Var(name.into())
+    }
+
+    pub fn and(operands: Vec<Formula>) -> Self {
+        Formula::And(operands)
+    }
+
+    pub fn or(operands: Vec<Formula>) -> Self {
+        Formula::Or(operands)
+    }
+
+    pub fn xor(a: Formula, b: Formula) -> Self {
+        Formula::Xor(Box::new(a), Box::new(b))
+    }
+
+    pub fn implies(a: Formula, b: Formula) -> Self {
+        Formula::Implies(Box::new(a), Box::new(b))
+    }
+
+    pub fn iff(a: Formula, b: Formula) -> Self {
+        Formula::Iff(Box::new(a), Box::new(b))
+    }
+
+    pub fn ite(condition: Formula, then: Formula, otherwise: Formula) -> Self {
+        Formula::Ite(Box::new(condition), Box::new(then), Box::new(otherwise))
+    }
+
+    /// Evaluates the formula with the values of the variables given by `value_of`.
+    pub fn value(&self, value_of: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            Formula::Const(value) => *value,
+            Formula::Var(name) => value_of(name),
+            Formula::Not(a) => !a.value(value_of),
+            Formula::And(operands) => operands.iter().all(|a| a.value(value_of)),
+            Formula::Or(operands) => operands.iter().any(|a| a.value(value_of)),
+            Formula::Xor(a, b) => a.value(value_of) != b.value(value_of),
+            Formula::Implies(a, b) => !a.value(value_of) || b.value(value_of),
+            Formula::Iff(a, b) => a.value(value_of) == b.value(value_of),
+            Formula::Ite(condition, then, otherwise) => {
+                if condition.value(value_of) {
+                    then.value(value_of)
+                } else {
+                    otherwise.value(value_of)
+                }
+            }
+        }
+    }
+
+    /// Converts the formula into an equisatisfiable CNF.
+    /// The named variables come first, in the order of their first occurrence,
+    /// followed by the fresh variables of the transformation.
+    pub fn to_cnf(&self, transformation: Transformation) -> (Cnf, VariableMap) {
+        let mut variables = VariableMap::default();
+        self.collect_names(&mut variables);
+
+        let mut builder = CnfBuilder::new(variables.len());
+        let root = self.encode(&mut builder, transformation, &|name| {
+            Literal::new(variables.get(name).unwrap(), true)
+        });
+        builder.add_clause(Clause::new(vec![root]));
+
+        (builder.build(), variables)
+    }
+
+    /// Returns the names of the variables in the order of their first occurrence.
+    pub fn names(&self) -> Vec<String> {
+        let mut variables = VariableMap::default();
+        self.collect_names(&mut variables);
+        variables.names
+    }
+
+    /// Adds the clauses of the transformation to `builder`, returning a literal
+    /// that implies the formula, where `literal_of` gives the literals of the names.
+    pub(crate) fn encode(
+        &self,
+        builder: &mut CnfBuilder,
+        transformation: Transformation,
+        literal_of: &dyn Fn(&str) -> Literal,
+    ) -> Literal {
+        let mut encoder = Encoder {
+            builder,
+            literal_of,
+            transformation,
+        };
+        encoder.encode(self, Polarity::Positive)
+    }
+
+    fn collect_names(&self, variables: &mut VariableMap) {
+        match self {
+            Formula::Const(_) => (),
+            Formula::Var(name) => variables.insert(name),
+            Formula::Not(a) => a.collect_names(variables),
+            Formula::And(operands) | Formula::Or(operands) => {
+                for a in operands {
+                    a.collect_names(variables);
+                }
+            }
+            Formula::Xor(a, b) | Formula::Implies(a, b) | Formula::Iff(a, b) => {
+                a.collect_names(variables);
+                b.collect_names(variables);
+            }
+            Formula::Ite(condition, then, otherwise) => {
+                condition.collect_names(variables);
+                then.collect_names(variables);
+                otherwise.collect_names(variables);
+            }
+        }
+    }
+}
+
+impl Not for Formula {
+    type Output = Formula;
+
+    fn not(self) -> Self::Output {
+        Formula::Not(Box::new(self))
+    }
+}
+
+impl BitAnd for Formula {
+    type Output = Formula;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Formula::And(vec![self, rhs])
+    }
+}
+
+impl BitOr for Formula {
+    type Output = Formula;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Formula::Or(vec![self, rhs])
+    }
+}
+
+impl BitXor for Formula {
+    type Output = Formula;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        Formula::xor(self, rhs)
+    }
+}
+
+impl Display for Formula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let write_operands = |f: &mut std::fmt::Formatter<'_>, operands: &[Formula], operator| {
+            write!(f, "(")?;
+            for (i, a) in operands.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", operator)?;
+                }
+                write!(f, "{}", a)?;
+            }
+            write!(f, ")")
+        };
+
+        match self {
+            Formula::Const(true) => write!(f, "⊤"),
+            Formula::Const(false) => write!(f, "⊥"),
+            Formula::Var(name) => write!(f, "{}", name),
+            Formula::Not(a) => write!(f, "¬{}", a),
+            Formula::And(operands) if operands.is_empty() => write!(f, "⊤"),
+            Formula::Or(operands) if operands.is_empty() => write!(f, "⊥"),
+            Formula::And(operands) => write_operands(f, operands, "∧"),
+            Formula::Or(operands) => write_operands(f, operands, "∨"),
+            Formula::Xor(a, b) => write!(f, "({} ⊕ {})", a, b),
+            Formula::Implies(a, b) => write!(f, "({} → {})", a, b),
+            Formula::Iff(a, b) => write!(f, "({} ↔ {})", a, b),
+            Formula::Ite(condition, then, otherwise) => {
+                write!(f, "ite({}, {}, {})", condition, then, otherwise)
+            }
+        }
+    }
+}
+
+/// Selects how subexpressions are related to their fresh variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transformation {
+    /// Equivalences
+    Tseitin,
+    /// Implications in the direction of the polarity
+    #[default]
+    PlaistedGreenbaum,
+}
+
+impl Transformation {
+    pub const ALL: [Transformation; 2] =
+        [Transformation::Tseitin, Transformation::PlaistedGreenbaum];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Transformation::Tseitin => "tseitin",
+            Transformation::PlaistedGreenbaum => "pg",
+        }
+    }
+}
+
+impl FromStr for Transformation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Transformation::ALL
+            .iter()
+            .copied()
+            .find(|transformation| transformation.name() == s)
+            .context(UnknownTransformation { name: s })
+    }
+}
+
+impl Display for Transformation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Maps the names in a formula to the variables of its CNF.
+#[derive(Debug, Clone, Default)]
+pub struct VariableMap {
+    variables: HashMap<String, Variable>,
+    names: Vec<String>,
+}
+
+impl VariableMap {
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Variable> {
+        self.variables.get(name).copied()
+    }
+
+    /// Returns the name of the variable, or `None` for a fresh variable.
+    pub fn name(&self, variable: Variable) -> Option<&str> {
+        self.names.get(variable.index()).map(|name| name.as_str())
+    }
+
+    /// Returns the value of the named variable in the model.
+    pub fn value(&self, model: &Model, name: &str) -> Option<bool> {
+        self.get(name)
+            .map(|variable| variable.value(model.assignment()))
+    }
+
+    fn insert(&mut self, name: &str) {
+        if !self.variables.contains_key(name) {
+            let variable = Variable::from_index(self.names.len()).unwrap();
+            self.variables.insert(name.to_owned(), variable);
+            self.names.push(name.to_owned());
+        }
+    }
+}
+
+/// The directions in which a subexpression must be encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Polarity {
+    Positive,
+    Negative,
+    Both,
+}
+
+impl Polarity {
+    fn flip(self) -> Polarity {
+        match self {
+            Polarity::Positive => Polarity::Negative,
+            Polarity::Negative => Polarity::Positive,
+            Polarity::Both => Polarity::Both,
+        }
+    }
+
+    /// Whether the fresh variable must imply its subexpression.
+    fn positive(self) -> bool {
+        self != Polarity::Negative
+    }
+
+    /// Whether the subexpression must imply its fresh variable.
+    fn negative(self) -> bool {
+        self != Polarity::Positive
+    }
+}
+
+struct Encoder<'a> {
+    builder: &'a mut CnfBuilder,
+    literal_of: &'a dyn Fn(&str) -> Literal,
+    transformation: Transformation,
+}
+
+impl Encoder<'_> {
+    /// Returns a literal standing for `formula` in the given polarity.
+    fn encode(&mut self, formula: &Formula, polarity: Polarity) -> Literal {
+        let polarity = match self.transformation {
+            Transformation::Tseitin => Polarity::Both,
+            Transformation::PlaistedGreenbaum => polarity,
+        };
+
+        match formula {
+            Formula::Const(value) => {
+                let literal = self.builder.new_literal();
+                self.builder
+                    .add_clause(Clause::new(vec![if *value { literal } else { !literal }]));
+                literal
+            }
+            Formula::Var(name) => (self.literal_of)(name),
+            Formula::Not(a) => !self.encode(a, polarity.flip()),
+            Formula::And(operands) => {
+                let literals = self.encode_all(operands, polarity);
+                // a ∧ b = ¬(¬a ∨ ¬b)
+                let negated: Vec<Literal> = literals.into_iter().map(|literal| !literal).collect();
+                !self.or_gate(negated, polarity.flip())
+            }
+            Formula::Or(operands) => {
+                let literals = self.encode_all(operands, polarity);
+                self.or_gate(literals, polarity)
+            }
+            Formula::Implies(a, b) => {
+                let a = self.encode(a, polarity.flip());
+                let b = self.encode(b, polarity);
+                self.or_gate(vec![!a, b], polarity)
+            }
+            Formula::Xor(a, b) => {
+                let a = self.encode(a, Polarity::Both);
+                let b = self.encode(b, Polarity::Both);
+                self.xor_gate(a, b, polarity)
+            }
+            Formula::Iff(a, b) => {
+                let a = self.encode(a, Polarity::Both);
+                let b = self.encode(b, Polarity::Both);
+                !self.xor_gate(a, b, polarity.flip())
+            }
+            Formula::Ite(condition, then, otherwise) => {
+                let condition = self.encode(condition, Polarity::Both);
+                let then = self.encode(then, polarity);
+                let otherwise = self.encode(otherwise, polarity);
+                self.ite_gate(condition, then, otherwise, polarity)
+            }
+        }
+    }
+
+    fn encode_all(&mut self, operands: &[Formula], polarity: Polarity) -> Vec<Literal> {
+        operands.iter().map(|a| self.encode(a, polarity)).collect()
+    }
+
+    /// Adds the clause `premises → conclusion` where `premises` is a conjunction.
+    fn add_implication(&mut self, premises: &[Literal], conclusion: &[Literal]) {
+        let mut literals: Vec<Literal> = premises.iter().map(|&literal| !literal).collect();
+        literals.extend_from_slice(conclusion);
+        self.builder.add_clause(Clause::new(literals));
+    }
+
+    fn or_gate(&mut self, literals: Vec<Literal>, polarity: Polarity) -> Literal {
+        if literals.len() == 1 {
+            return literals[0];
+        }
+
+        let t = self.builder.new_literal();
+        if polarity.positive() {
+            self.add_implication(&[t], &literals);
+        }
+        if polarity.negative() {
+            for &literal in &literals {
+                self.add_implication(&[literal], &[t]);
+            }
+        }
+        t
+    }
+
+    fn xor_gate(&mut self, a: Literal, b: Literal, polarity: Polarity) -> Literal {
+        let t = self.builder.new_literal();
+        if polarity.positive() {
+            self.add_implication(&[t], &[a, b]);
+            self.add_implication(&[t, a], &[!b]);
+        }
+        if polarity.negative() {
+            self.add_implication(&[a, !b], &[t]);
+            self.add_implication(&[!a, b], &[t]);
+        }
+        t
+    }
+
+    fn ite_gate(
+        &mut self,
+        condition: Literal,
+        then: Literal,
+        otherwise: Literal,
+        polarity: Polarity,
+    ) -> Literal {
+        let t = self.builder.new_literal();
+        if polarity.positive() {
+            self.add_implication(&[t, condition], &[then]);
+            self.add_implication(&[t, !condition], &[otherwise]);
+        }
+        if polarity.negative() {
+            self.add_implication(&[condition, then], &[t]);
+            self.add_implication(&[!condition, otherwise], &[t]);
+        }
+        t
+    }
+}
@@ -4,6 +4,7 @@ A module to represent conjunctive normal form formula.
 
 use std::{convert::TryInto, fmt::Display, str::FromStr};
 
+use crate::encode::{AmkEncoding, CnfBuilder, PbEncoding};
 use crate::prelude::*;
 
 #[derive(Debug, Snafu)]
@@ -20,11 +21,11 @@ pub enum VariableParseError {
 
 /// Newtype wrapper for variable ID.
 /// Internally uses 0-based index, but uses 1-based index for printing
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Variable(u32);
 
 impl Variable {
-    pub const MAX_VARIABLE_INDEX: usize = std::u32::MAX as usize;
+    pub const MAX_VARIABLE_INDEX: usize = u32::MAX as usize;
 }
 
 impl Variable {
@@ -66,7 +67,7 @@ impl Display for Variable {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Literal {
     variable: Variable,
     positive: bool,
@@ -98,14 +99,24 @@ impl Literal {
             .partial_value(assignments)
             .map(|val| val ^ !self.positive)
     }
+
+    /// Returns the DIMACS representation of the literal (1-based, negative if negated).
+    pub fn dimacs(self) -> i64 {
+        let number = self.index() as i64 + 1;
+        if self.positive {
+            number
+        } else {
+            -number
+        }
+    }
 }
 
 impl FromStr for Literal {
     type Err = VariableParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (positive, variable) = if s.starts_with('-') {
-            (false, s[1..].parse()?)
+        let (positive, variable) = if let Some(stripped) = s.strip_prefix('-') {
+            (false, stripped.parse()?)
         } else {
             (true, s.parse()?)
         };
@@ -151,6 +162,10 @@ impl Clause {
         self.literals.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.literals.is_empty()
+    }
+
     pub fn as_slice(&self) -> &[Literal] {
         &self.literals
     }
@@ -178,11 +193,314 @@ impl Display for Clause {
     }
 }
 
+/// Parity constraint `x1 ⊕ x2 ⊕ ... ⊕ xn = parity`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XorClause {
+    variables: Vec<Variable>,
+    parity: bool,
+}
+
+impl XorClause {
+    /// Creates a constraint that the XOR of the literals is true.
+    /// Negated literals flip the parity, and duplicated variables cancel out.
+    pub fn new(literals: Vec<Literal>) -> Self {
+        let mut parity = true;
+        let mut variables = Vec::new();
+        for literal in literals {
+            parity ^= !literal.positive();
+            variables.push(literal.variable());
+        }
+
+        XorClause::with_parity(variables, parity)
+    }
+
+    /// Creates a constraint that the XOR of the variables equals `parity`.
+    /// Duplicated variables cancel out.
+    pub fn with_parity(mut variables: Vec<Variable>, parity: bool) -> Self {
+        variables.sort_unstable();
+        let mut deduplicated: Vec<Variable> = Vec::new();
+        for variable in variables {
+            if deduplicated.last() == Some(&variable) {
+                deduplicated.pop();
+            } else {
+                deduplicated.push(variable);
+            }
+        }
+
+        XorClause {
+            variables: deduplicated,
+            parity,
+        }
+    }
+
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    pub fn parity(&self) -> bool {
+        self.parity
+    }
+
+    pub fn value(&self, assignments: &[bool]) -> bool {
+        let sum = self
+            .variables
+            .iter()
+            .fold(false, |sum, variable| sum ^ variable.value(assignments));
+        sum == self.parity
+    }
+}
+
+impl Display for XorClause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+
+        let mut iter = self.variables.iter();
+        if let Some(first) = iter.next() {
+            write!(f, "{}", first)?;
+        }
+        for variable in iter {
+            write!(f, " ⊕ {}", variable)?;
+        }
+
+        write!(f, " = {})", self.parity as u8)?;
+
+        Ok(())
+    }
+}
+
+/// Cardinality constraint `l1 + l2 + ... + ln ≥ bound`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cardinality {
+    literals: Vec<Literal>,
+    bound: usize,
+}
+
+impl Cardinality {
+    /// Creates a constraint that at least `bound` of the literals are true.
+    pub fn at_least(literals: Vec<Literal>, bound: usize) -> Self {
+        Cardinality { literals, bound }
+    }
+
+    /// Creates a constraint that at most `bound` of the literals are true,
+    /// represented as at least `n - bound` of their negations being true.
+    pub fn at_most(literals: Vec<Literal>, bound: usize) -> Self {
+        let bound = literals.len().saturating_sub(bound);
+        let literals = literals.into_iter().map(|literal| !literal).collect();
+        Cardinality { literals, bound }
+    }
+
+    pub fn literals(&self) -> &[Literal] {
+        &self.literals
+    }
+
+    pub fn bound(&self) -> usize {
+        self.bound
+    }
+
+    pub fn value(&self, assignments: &[bool]) -> bool {
+        let count = self
+            .literals
+            .iter()
+            .filter(|literal| literal.value(assignments))
+            .count();
+        count >= self.bound
+    }
+}
+
+impl Display for Cardinality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+
+        let mut iter = self.literals.iter();
+        if let Some(first) = iter.next() {
+            write!(f, "{}", first)?;
+        } else {
+            write!(f, "0")?;
+        }
+        for literal in iter {
+            write!(f, " + {}", literal)?;
+        }
+
+        write!(f, " ≥ {})", self.bound)?;
+
+        Ok(())
+    }
+}
+
+/// Linear pseudo-Boolean constraint `a1 l1 + a2 l2 + ... + an ln ≥ bound`,
+/// normalized to positive coefficients sorted in descending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PbConstraint {
+    terms: Vec<(u64, Literal)>,
+    bound: u64,
+}
+
+impl PbConstraint {
+    /// Creates a constraint that the weighted sum of the literals is at least `bound`.
+    /// Negative coefficients and repeated variables are allowed.
+    pub fn at_least(terms: Vec<(i64, Literal)>, bound: i64) -> Self {
+        let terms = terms
+            .into_iter()
+            .map(|(coefficient, literal)| (coefficient as i128, literal));
+        PbConstraint::normalize(terms, bound as i128)
+    }
+
+    /// Creates a constraint that the weighted sum of the literals is at most `bound`.
+    pub fn at_most(terms: Vec<(i64, Literal)>, bound: i64) -> Self {
+        // Negating in i128 cannot overflow, unlike -i64::MIN
+        let terms = terms
+            .into_iter()
+            .map(|(coefficient, literal)| (-(coefficient as i128), literal));
+        PbConstraint::normalize(terms, -(bound as i128))
+    }
+
+    fn normalize(terms: impl Iterator<Item = (i128, Literal)>, mut bound: i128) -> Self {
+        // Collect coefficients of positive literals, using ¬x = 1 - x
+        let mut coefficients: Vec<(Variable, i128)> = Vec::new();
+        for (mut coefficient, literal) in terms {
+            if !literal.positive() {
+                bound -= coefficient;
+                coefficient = -coefficient;
+            }
+            coefficients.push((literal.variable(), coefficient));
+        }
+        coefficients.sort_unstable_by_key(|&(variable, _)| variable);
+
+        let mut merged: Vec<(i128, Literal)> = Vec::new();
+        for (variable, coefficient) in coefficients {
+            match merged.last_mut() {
+                Some((sum, literal)) if literal.variable() == variable => *sum += coefficient,
+                _ => merged.push((coefficient, Literal::new(variable, true))),
+            }
+        }
+
+        // Negative terms become positive terms of the negated literals, using x = 1 - ¬x
+        let mut normalized = Vec::new();
+        for (coefficient, literal) in merged {
+            if coefficient > 0 {
+                normalized.push((coefficient, literal));
+            } else if coefficient < 0 {
+                bound -= coefficient;
+                normalized.push((-coefficient, !literal));
+            }
+        }
+
+        let bound = bound.max(0);
+        let terms = if bound == 0 {
+            Vec::new()
+        } else {
+            // A coefficient larger than the bound satisfies the constraint alone
+            let mut terms: Vec<(u64, Literal)> = normalized
+                .into_iter()
+                .map(|(coefficient, literal)| (coefficient.min(bound) as u64, literal))
+                .collect();
+            terms.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+            terms
+        };
+
+        PbConstraint {
+            terms,
+            bound: bound.min(u64::MAX as i128) as u64,
+        }
+    }
+
+    pub fn terms(&self) -> &[(u64, Literal)] {
+        &self.terms
+    }
+
+    pub fn bound(&self) -> u64 {
+        self.bound
+    }
+
+    pub fn value(&self, assignments: &[bool]) -> bool {
+        let sum = self
+            .terms
+            .iter()
+            .filter(|(_, literal)| literal.value(assignments))
+            .fold(0u64, |sum, (coefficient, _)| {
+                sum.saturating_add(*coefficient)
+            });
+        sum >= self.bound
+    }
+}
+
+impl From<&Cardinality> for PbConstraint {
+    fn from(cardinality: &Cardinality) -> Self {
+        let terms = cardinality
+            .literals()
+            .iter()
+            .map(|&literal| (1, literal))
+            .collect();
+        PbConstraint::at_least(terms, cardinality.bound() as i64)
+    }
+}
+
+impl Display for PbConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+
+        let mut iter = self.terms.iter();
+        if let Some((coefficient, literal)) = iter.next() {
+            write!(f, "{} {}", coefficient, literal)?;
+        } else {
+            write!(f, "0")?;
+        }
+        for (coefficient, literal) in iter {
+            write!(f, " + {} {}", coefficient, literal)?;
+        }
+
+        write!(f, " ≥ {})", self.bound)?;
+
+        Ok(())
+    }
+}
+
+/// Linear objective function `c1 l1 + c2 l2 + ... + cn ln` to minimize.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Objective {
+    terms: Vec<(i64, Literal)>,
+}
+
+impl Objective {
+    pub fn new(terms: Vec<(i64, Literal)>) -> Self {
+        Objective { terms }
+    }
+
+    pub fn terms(&self) -> &[(i64, Literal)] {
+        &self.terms
+    }
+
+    pub fn value(&self, assignments: &[bool]) -> i64 {
+        self.terms
+            .iter()
+            .filter(|(_, literal)| literal.value(assignments))
+            .map(|(coefficient, _)| coefficient)
+            .sum()
+    }
+}
+
+impl Display for Objective {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "min:")?;
+        for (coefficient, literal) in &self.terms {
+            write!(f, " {:+} {}", coefficient, literal)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Formula representation in Conjunctive Normal Form
 #[derive(Debug, Clone)]
 pub struct Cnf {
     num_variables: usize,
     clauses: Vec<Clause>,
+    /// Parity constraints, conjoined with the clauses.
+    xors: Vec<XorClause>,
+    /// Cardinality constraints, conjoined with the clauses.
+    cardinalities: Vec<Cardinality>,
+    /// Pseudo-Boolean constraints, conjoined with the clauses.
+    pbs: Vec<PbConstraint>,
     empty_clause_count: usize,
 }
 
@@ -193,6 +511,9 @@ impl Cnf {
         Cnf {
             num_variables,
             clauses: Vec::new(),
+            xors: Vec::new(),
+            cardinalities: Vec::new(),
+            pbs: Vec::new(),
             empty_clause_count: 0,
         }
     }
@@ -205,6 +526,18 @@ impl Cnf {
         &self.clauses
     }
 
+    pub fn xors(&self) -> &[XorClause] {
+        &self.xors
+    }
+
+    pub fn cardinalities(&self) -> &[Cardinality] {
+        &self.cardinalities
+    }
+
+    pub fn pbs(&self) -> &[PbConstraint] {
+        &self.pbs
+    }
+
     pub fn empty_clause_count(&self) -> usize {
         self.empty_clause_count
     }
@@ -215,7 +548,7 @@ impl Cnf {
     ///
     /// Panics when `clause` contains invalid literals.
     pub fn add_clause(&mut self, clause: Clause) {
-        if clause.len() == 0 {
+        if clause.is_empty() {
             self.empty_clause_count += 1;
             return;
         }
@@ -227,18 +560,169 @@ impl Cnf {
 
         self.clauses.push(clause);
     }
+
+    /// Adds a parity constraint to the current formula.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `xor` contains invalid variables.
+    pub fn add_xor(&mut self, xor: XorClause) {
+        assert!(xor
+            .variables()
+            .iter()
+            .all(|variable| variable.index() < self.num_variables));
+
+        self.xors.push(xor);
+    }
+
+    /// Adds a cardinality constraint to the current formula.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `cardinality` contains invalid literals.
+    pub fn add_cardinality(&mut self, cardinality: Cardinality) {
+        assert!(cardinality
+            .literals()
+            .iter()
+            .all(|literal| literal.index() < self.num_variables));
+
+        self.cardinalities.push(cardinality);
+    }
+
+    /// Adds a pseudo-Boolean constraint to the current formula.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `pb` contains invalid literals.
+    pub fn add_pb(&mut self, pb: PbConstraint) {
+        assert!(pb
+            .terms()
+            .iter()
+            .all(|(_, literal)| literal.index() < self.num_variables));
+
+        self.pbs.push(pb);
+    }
+
+    /// Adds a fresh variable numbered after the existing variables.
+    pub(crate) fn new_variable(&mut self) -> Variable {
+        self.num_variables += 1;
+        Variable::from_index(self.num_variables - 1).unwrap()
+    }
+
+    /// Returns an equivalent formula consisting of clauses only.
+    pub fn encode_constraints(&self) -> Cnf {
+        self.encode_xors()
+            .encode_cardinalities()
+            .encode_pbs(PbEncoding::default())
+    }
+
+    /// Returns an equivalent formula without pseudo-Boolean constraints,
+    /// encoding them with fresh variables numbered after the variables of `self`.
+    pub fn encode_pbs(&self, encoding: PbEncoding) -> Cnf {
+        let mut builder = CnfBuilder::from(Cnf {
+            pbs: Vec::new(),
+            ..self.clone()
+        });
+        for pb in &self.pbs {
+            builder.add_pb(pb, encoding);
+        }
+        builder.build()
+    }
+
+    /// Returns an equivalent formula without parity constraints.
+    /// Each parity constraint is split into chunks linked by fresh variables,
+    /// which are numbered after the variables of `self`, and each chunk is encoded
+    /// with clauses forbidding every assignment of the wrong parity.
+    pub fn encode_xors(&self) -> Cnf {
+        /// Longer chunks need exponentially many clauses.
+        const MAX_CHUNK_LEN: usize = 4;
+
+        let mut encoded = Cnf {
+            xors: Vec::new(),
+            ..self.clone()
+        };
+        let mut fresh_variable = || {
+            encoded.num_variables += 1;
+            Variable::from_index(encoded.num_variables - 1).unwrap()
+        };
+
+        let mut chunks = Vec::new();
+        for xor in &self.xors {
+            let mut variables = xor.variables().to_vec();
+            if variables.is_empty() {
+                // Unsatisfiable `0 = 1` becomes `y ∧ ¬y`
+                variables.push(fresh_variable());
+                variables.push(variables[0]);
+            }
+            while variables.len() > MAX_CHUNK_LEN {
+                // a ⊕ b ⊕ c ⊕ rest = p  <=>  t = a ⊕ b ⊕ c, t ⊕ rest = p
+                let link = fresh_variable();
+                let mut chunk: Vec<Variable> = variables.drain(..MAX_CHUNK_LEN - 1).collect();
+                chunk.push(link);
+                chunks.push((chunk, false));
+                variables.insert(0, link);
+            }
+            chunks.push((variables, xor.parity()));
+        }
+
+        for (variables, parity) in chunks {
+            // A clause forbids the assignment falsifying all of its literals
+            for forbidden in 0..1usize << variables.len() {
+                if (forbidden.count_ones() % 2 == 1) == parity {
+                    continue;
+                }
+                encoded.add_clause(Clause::new(
+                    variables
+                        .iter()
+                        .enumerate()
+                        .map(|(bit, &variable)| Literal::new(variable, forbidden >> bit & 1 == 0))
+                        .collect(),
+                ));
+            }
+        }
+
+        encoded
+    }
+
+    /// Returns an equivalent formula without cardinality constraints.
+    /// Each constraint is encoded with a sequential counter (Sinz, 2005)
+    /// on fresh variables numbered after the variables of `self`.
+    pub fn encode_cardinalities(&self) -> Cnf {
+        let mut builder = CnfBuilder::from(Cnf {
+            cardinalities: Vec::new(),
+            ..self.clone()
+        });
+        for cardinality in &self.cardinalities {
+            builder.at_least_k(
+                cardinality.literals(),
+                cardinality.bound(),
+                AmkEncoding::SequentialCounter,
+            );
+        }
+        builder.build()
+    }
 }
 
 impl Display for Cnf {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "CNF with {} variables (", self.num_variables)?;
 
-        let mut iter = self.clauses.iter();
+        let mut iter = self
+            .clauses
+            .iter()
+            .map(|clause| clause.to_string())
+            .chain(self.xors.iter().map(|xor| xor.to_string()))
+            .chain(
+                self.cardinalities
+                    .iter()
+                    .map(|cardinality| cardinality.to_string()),
+            )
+            .chain(self.pbs.iter().map(|pb| pb.to_string()));
         if let Some(first) = iter.next() {
             write!(f, "{}", first)?;
         }
-        for variable in iter {
-            write!(f, " ∧ {}", variable)?;
+        for constraint in iter {
+            write!(f, " ∧ {}", constraint)?;
         }
 
         write!(f, ")")?;
@@ -267,6 +751,15 @@ impl Model {
         for clause in &formula.clauses {
             assert!(clause.iter().any(|literal| literal.value(&assignment)));
         }
+        for xor in &formula.xors {
+            assert!(xor.value(&assignment));
+        }
+        for cardinality in &formula.cardinalities {
+            assert!(cardinality.value(&assignment));
+        }
+        for pb in &formula.pbs {
+            assert!(pb.value(&assignment));
+        }
 
         Model {
             formula,
@@ -274,6 +767,17 @@ impl Model {
         }
     }
 
+    /// Creates a model from an assignment of an extended formula
+    /// (e.g., `formula.encode_constraints()`), ignoring the additional variables.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the restricted assignment is invalid.
+    pub fn from_extended(formula: Cnf, assignment: &[bool]) -> Self {
+        let num_variables = formula.num_variables();
+        Model::new(formula, assignment[..num_variables].to_vec())
+    }
+
     pub fn formula(&self) -> &Cnf {
         &self.formula
     }
@@ -1,11 +1,19 @@
 #[macro_use]
 extern crate log;
 
+pub mod aig;
+pub mod bmc;
+pub mod bv;
+pub mod encode;
+pub mod expr;
 pub mod formula;
 pub mod parser;
 pub mod prelude;
+pub mod qbf;
 pub mod report;
+pub mod smt;
 pub mod solver;
+pub mod symmetry;
 
 #[cfg(test)]
 mod tests;
@@ -1,22 +1,82 @@
-use std::{env::args, path::Path};
+use std::{
+    collections::HashMap,
+    env::args,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+    str::FromStr,
+};
 
 use pretty_env_logger::formatted_builder;
 use satire::{
-    formula::Model,
-    parser::{self, parse_file},
+    aig::{Aig, AigLiteral},
+    bmc::{self, BmcResult},
+    encode::PbEncoding,
+    formula::{Cnf, Model},
+    parser::{
+        self, parse_aiger_file, parse_file, parse_icnf_file, parse_opb_file, parse_qdimacs_file,
+    },
     prelude::*,
+    qbf::QbfResult,
     report::Report,
-    solver::{CdclSolver, DpllSolver, Solver},
+    smt::{self, SmtSession},
+    solver::{
+        self, backbone, cube, minimal_hitting_sets, minimize, write_icnf, BackboneResult,
+        CdclConfig, CdclSolver, CubeAndConquerSolver, CubeConfig, DpllSolver, LocalSearchAlgorithm,
+        LocalSearchConfig, LocalSearchSolver, McsEnumerator, OptimizeResult, PortfolioConfig,
+        PortfolioSolver, SolveResult, Solver, Statistics,
+    },
+    symmetry::{break_symmetries, find_symmetries},
 };
 
 fn usage_string() -> String {
     format!(
-        "Usage: {} <solver_name> <command>
+        "Usage: {} <solver_name> <command> [options]
 
-solver_name: dpll, cdcl
+solver_name: dpll, cdcl, walksat, probsat, portfolio, cnc
 
 command:
-    check <file_name> - test the solver with given file",
+    check <file_name> - test the solver with given file
+        (DIMACS CNF, OPB if it ends with .opb, AIGER if it ends with .aag or .aig,
+        or QDIMACS if it ends with .qdimacs)
+    cube <file_name> - split the formula into cubes and print them in iCNF (cnc)
+    conquer <file_name> - solve the cubes in the given iCNF file (cnc)
+    smt [file_name] - answer SMT-LIB 2 commands over Bool and BitVec from the file or stdin
+    bmc <file_name> - search for a trace reaching a bad state of an AIGER file (cdcl)
+    backbone <file_name> - print the literals true in every model of a DIMACS CNF (cdcl)
+    mcs <file_name> - print the minimal correction sets of the clauses of a DIMACS CNF,
+        then its minimal unsatisfiable subsets (cdcl)
+
+options:
+    --heuristic=<name> - branching heuristic of the solver
+        dpll: first (default), dlis, dlcs, moms, jw, bohm
+        cdcl: vsids (default), vmtf, chb, lrb
+    --restart=<name> - restart policy (cdcl): luby (default), geometric
+    --local-search[=<true|false>] - local search on rephasing (cdcl, default: true)
+    --xor[=<true|false>] - detect XORs encoded in clauses (cdcl, default: true)
+    --pb-encoding=<name> - pseudo-Boolean constraint handling (cdcl):
+        native (default), bdd, adder, sorter
+    --chrono=<number> - backtrack one level instead of backjumping over more levels than this
+        (cdcl, default: 100)
+    --modes[=<true|false>] - alternate focused (vmtf, frequent restarts) and stable
+        (vsids, rare restarts, target phases) modes instead of --heuristic and --restart
+        (cdcl, default: false)
+    --seed=<number> - random seed (cdcl, walksat, probsat)
+    --max-flips=<number> - flips per try (walksat, probsat)
+    --max-tries=<number> - the number of tries (walksat, probsat)
+    --threads=<number> - the number of threads (portfolio, cnc, default: all cores)
+    --dpll[=<true|false>] - run a DPLL solver in one thread (portfolio, default: false)
+    --sharing[=<true|false>] - share learned clauses (portfolio, default: true)
+    --depth=<number> - the maximum number of decisions in a cube (cnc, default: 8)
+    --output=<number> - the AIGER output to satisfy, counting bad state properties
+        after the outputs (default: 0); bmc accepts outputs only if there are no bad
+        state properties
+    --bound=<number> - the maximum number of steps of a trace (bmc, default: 20)
+    --symmetry[=<true|false>] - add symmetry-breaking clauses to a DIMACS CNF before checking it
+        (default: false)
+    --stats[=<true|false>] - print statistics of the search as comment lines before the result
+        (dpll, cdcl, default: false); not for OPB files with an objective or QDIMACS files,
+        which take several searches",
         args().next().unwrap()
     )
 }
@@ -31,24 +91,407 @@ pub enum Error {
     ParserError { source: parser::Error },
     #[snafu(display("Required argument does not exist\n\n{}", usage_string()))]
     MissingArgument,
+    #[snafu(display("Unknown option '{}'\n\n{}", name, usage_string()))]
+    UnknownOption { name: String },
+    #[snafu(display("Invalid value '{}' for option '{}'", value, name))]
+    InvalidOptionValue { name: String, value: String },
+    #[snafu(display("Invalid solver option"))]
+    SolverOptionError { source: solver::Error },
+    #[snafu(display("The AIG has no output {}", index))]
+    MissingAigerOutput { index: usize },
+    #[snafu(display(
+        "Output {} is not a bad state property, which a witness cannot refer to",
+        index
+    ))]
+    NotBadProperty { index: usize },
+    #[snafu(display(
+        "Statistics are not available for {}, which take several searches",
+        input
+    ))]
+    UnsupportedStatistics { input: String },
+    #[snafu(display("Failed to read '{}'", path))]
+    InputError { path: String, source: io::Error },
+    #[snafu(display("SMT-LIB session failed"))]
+    SmtError { source: smt::Error },
+    #[snafu(display("Failed to write the output"))]
+    OutputError { source: io::Error },
+}
+
+/// Options given as `--name=value` arguments.
+#[derive(Default)]
+struct Options {
+    values: HashMap<String, String>,
+}
+
+impl Options {
+    /// Separates options from positional arguments.
+    fn parse(args: Vec<String>) -> Result<(Options, Vec<String>), Error> {
+        let mut options = Options::default();
+        let mut positional = Vec::new();
+
+        for arg in args {
+            if let Some(option) = arg.strip_prefix("--") {
+                let (name, value) = match option.find('=') {
+                    Some(pos) => (&option[..pos], Some(&option[pos + 1..])),
+                    None => (option, None),
+                };
+                match (name, value) {
+                    ("heuristic", Some(value))
+                    | ("restart", Some(value))
+                    | ("local-search", Some(value))
+                    | ("xor", Some(value))
+                    | ("pb-encoding", Some(value))
+                    | ("chrono", Some(value))
+                    | ("modes", Some(value))
+                    | ("seed", Some(value))
+                    | ("max-flips", Some(value))
+                    | ("max-tries", Some(value))
+                    | ("threads", Some(value))
+                    | ("dpll", Some(value))
+                    | ("sharing", Some(value))
+                    | ("depth", Some(value))
+                    | ("output", Some(value))
+                    | ("bound", Some(value))
+                    | ("symmetry", Some(value))
+                    | ("stats", Some(value)) => {
+                        options.values.insert(name.to_owned(), value.to_owned());
+                    }
+                    // Boolean options given without a value are enabled
+                    ("local-search", None)
+                    | ("xor", None)
+                    | ("modes", None)
+                    | ("dpll", None)
+                    | ("sharing", None)
+                    | ("symmetry", None)
+                    | ("stats", None) => {
+                        options.values.insert(name.to_owned(), true.to_string());
+                    }
+                    _ => UnknownOption { name: arg.clone() }.fail()?,
+                }
+            } else {
+                positional.push(arg);
+            }
+        }
+
+        Ok((options, positional))
+    }
+
+    /// Parses a solver specific option, or returns the default if not given.
+    fn solver_option<T>(&self, name: &str) -> Result<T, Error>
+    where
+        T: FromStr<Err = solver::Error> + Default,
+    {
+        match self.values.get(name) {
+            Some(name) => name.parse().context(SolverOptionError),
+            None => Ok(T::default()),
+        }
+    }
+
+    /// Parses the option value, or returns `default` if not given.
+    fn value_or<T: FromStr>(&self, name: &str, default: T) -> Result<T, Error> {
+        match self.values.get(name) {
+            Some(value) => value.parse().ok().context(InvalidOptionValue {
+                name,
+                value: value.as_str(),
+            }),
+            None => Ok(default),
+        }
+    }
+
+    fn cdcl_config(&self) -> Result<CdclConfig, Error> {
+        let default = CdclConfig::default();
+        Ok(CdclConfig {
+            heuristic: self.solver_option("heuristic")?,
+            restart: self.solver_option("restart")?,
+            local_search: self.value_or("local-search", default.local_search)?,
+            seed: self.value_or("seed", default.seed)?,
+            xor_detection: self.value_or("xor", default.xor_detection)?,
+            pb_encoding: self.pb_encoding()?,
+            chrono_threshold: self.value_or("chrono", default.chrono_threshold)?,
+            mode_switching: self.value_or("modes", default.mode_switching)?,
+            phase_timings: self.value_or("stats", default.phase_timings)?,
+        })
+    }
+
+    /// Parses the PB encoding, where `native` means no encoding.
+    fn pb_encoding(&self) -> Result<Option<PbEncoding>, Error> {
+        match self.values.get("pb-encoding").map(|value| value.as_str()) {
+            None | Some("native") => Ok(None),
+            Some(value) => value.parse().ok().map(Some).context(InvalidOptionValue {
+                name: "pb-encoding",
+                value,
+            }),
+        }
+    }
+
+    fn portfolio_config(&self) -> Result<PortfolioConfig, Error> {
+        let default = PortfolioConfig::default();
+        let mut config = PortfolioConfig::diversified(
+            self.value_or("threads", default.workers.len())?,
+            self.value_or("dpll", false)?,
+        );
+        if !self.value_or("sharing", true)? {
+            config.sharing = None;
+        }
+        Ok(config)
+    }
+
+    fn cube_config(&self) -> Result<CubeConfig, Error> {
+        let default = CubeConfig::default();
+        Ok(CubeConfig {
+            max_depth: self.value_or("depth", default.max_depth)?,
+            threads: self.value_or("threads", default.threads)?,
+            cdcl: self.cdcl_config()?,
+            ..default
+        })
+    }
+
+    fn local_search_config(
+        &self,
+        algorithm: LocalSearchAlgorithm,
+    ) -> Result<LocalSearchConfig, Error> {
+        let default = LocalSearchConfig::default();
+        Ok(LocalSearchConfig {
+            algorithm,
+            seed: self.value_or("seed", default.seed)?,
+            max_flips: self.value_or("max-flips", default.max_flips)?,
+            max_tries: self.value_or("max-tries", default.max_tries)?,
+        })
+    }
+}
+
+fn print_result(result: SolveResult) {
+    match result {
+        SolveResult::Sat(model) => println!("SAT {}", model),
+        SolveResult::Unsat => println!("UNSAT"),
+        SolveResult::Unknown => println!("UNKNOWN"),
+    }
+}
+
+/// Prints the statistics as DIMACS comment lines.
+fn print_statistics(stats: &Statistics) {
+    println!("c decisions: {}", stats.decisions);
+    println!("c propagations: {}", stats.propagations);
+    println!("c conflicts: {}", stats.conflicts);
+    println!("c restarts: {}", stats.restarts);
+    println!("c mode switches: {}", stats.mode_switches);
+    println!(
+        "c learned clauses: {} (average length {:.2})",
+        stats.learned_clauses,
+        stats.average_learned_len()
+    );
+    if !stats.lbd_histogram.is_empty() {
+        print!("c learned clauses by LBD:");
+        for (lbd, &count) in stats.lbd_histogram.iter().enumerate() {
+            if count > 0 {
+                print!(" {}:{}", lbd, count);
+            }
+        }
+        println!();
+    }
+    println!("c deleted clauses: {}", stats.deleted_clauses);
+    println!("c vivified literals: {}", stats.vivified_literals);
+    println!("c max decision level: {}", stats.max_decision_level);
+    println!(
+        "c memory: {:.2} MiB",
+        stats.memory as f64 / (1 << 20) as f64
+    );
+
+    let timings = &stats.timings;
+    println!("c total time: {:.3}s", timings.total.as_secs_f64());
+    for (name, duration) in [
+        ("propagation", timings.propagation),
+        ("conflict analysis", timings.conflict_analysis),
+        ("vivification", timings.vivification),
+        ("local search", timings.local_search),
+    ] {
+        println!("c   {}: {:.3}s", name, duration.as_secs_f64());
+    }
 }
 
-fn solve_path<T: Solver>(path: &Path) -> Result<Option<Model>, Error> {
-    let formula = parse_file(path).context(ParserError)?;
-    let solver = T::new(formula);
-    Ok(solver.solve())
+fn print_optimize_result(result: OptimizeResult) {
+    match result {
+        OptimizeResult::Optimum { model, cost } => println!("OPTIMUM {} {}", cost, model),
+        OptimizeResult::Feasible { model, cost } => println!("FEASIBLE {} {}", cost, model),
+        OptimizeResult::Unsat => println!("UNSAT"),
+        OptimizeResult::Unknown => println!("UNKNOWN"),
+    }
 }
 
-fn dispatch_command<T: Solver>(args: Vec<String>) -> Result<(), Error> {
-    match args.get(0).map(|s| s.as_str()) {
+/// Prints the result of a QBF with the certificate in DIMACS literals.
+fn print_qbf_result(result: QbfResult) {
+    let (name, certificate) = match result {
+        QbfResult::True(certificate) => ("TRUE", certificate),
+        QbfResult::False(certificate) => ("FALSE", certificate),
+        QbfResult::Unknown => ("UNKNOWN", Vec::new()),
+    };
+    print!("{}", name);
+    for literal in certificate {
+        print!(" {}", literal.dimacs());
+    }
+    println!();
+}
+
+fn print_backbone_result(result: BackboneResult) {
+    match result {
+        BackboneResult::Backbone(literals) => {
+            print!("BACKBONE");
+            for literal in literals {
+                print!(" {}", literal.dimacs());
+            }
+            println!();
+        }
+        BackboneResult::Unsat => println!("UNSAT"),
+        BackboneResult::Unknown => println!("UNKNOWN"),
+    }
+}
+
+/// Prints sets of clauses by their 1-based indices.
+fn print_clause_sets(name: &str, sets: &[Vec<usize>]) {
+    for set in sets {
+        print!("{}", name);
+        for index in set {
+            print!(" {}", index + 1);
+        }
+        println!();
+    }
+}
+
+/// Prints a counterexample as an AIGER witness for the bad state property `property`:
+/// the initial state of the latches followed by the inputs at each step.
+fn print_bmc_result(result: BmcResult, property: usize) {
+    let bits = |values: &[bool]| -> String {
+        values
+            .iter()
+            .map(|&value| if value { '1' } else { '0' })
+            .collect()
+    };
+    match result {
+        BmcResult::Counterexample(trace) => {
+            println!("1\nb{}\n{}", property, bits(&trace[0].state));
+            for step in &trace {
+                println!("{}", bits(&step.inputs));
+            }
+            println!(".");
+        }
+        BmcResult::NoCounterexample { bound } => println!("NO COUNTEREXAMPLE UP TO {}", bound),
+        BmcResult::Unknown { step } => println!("UNKNOWN AT {}", step),
+    }
+}
+
+/// Returns the output selected by the `output` option,
+/// where bad state properties are numbered after the outputs.
+fn aiger_output(options: &Options, aig: &Aig) -> Result<(usize, AigLiteral), Error> {
+    let index = options.value_or("output", 0)?;
+    let output = aig
+        .outputs()
+        .iter()
+        .chain(aig.bad())
+        .nth(index)
+        .context(MissingAigerOutput { index })?;
+    Ok((index, *output))
+}
+
+/// Returns the number of the bad state property at `index` of the outputs selected by
+/// `aiger_output`. Outputs are the properties of AIGs without bad state properties,
+/// following AIGER 1.0.
+fn bad_property(aig: &Aig, index: usize) -> Result<usize, Error> {
+    match index.checked_sub(aig.outputs().len()) {
+        Some(property) => Ok(property),
+        None if aig.bad().is_empty() => Ok(index),
+        None => NotBadProperty { index }.fail(),
+    }
+}
+
+/// Solves the file, minimizing the objective if it is an OPB file with one,
+/// satisfying an output if it is an AIGER file, or deciding it if it is a QDIMACS file.
+fn check_path<T: Solver>(
+    options: &Options,
+    path: &Path,
+    mut build: impl FnMut(Cnf) -> T,
+) -> Result<(), Error> {
+    let stats = options.value_or("stats", false)?;
+    let solve = |solver: T| {
+        if stats {
+            let (result, statistics) = solver.solve_with_statistics();
+            print_statistics(&statistics);
+            result
+        } else {
+            solver.solve()
+        }
+    };
+
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    if extension == Some("opb") {
+        let (formula, objective) = parse_opb_file(path).context(ParserError)?;
+        match objective {
+            Some(objective) => {
+                ensure!(
+                    !stats,
+                    UnsupportedStatistics {
+                        input: "OPB files with an objective"
+                    }
+                );
+                print_optimize_result(minimize(formula, &objective, build));
+            }
+            None => print_result(solve(build(formula))),
+        }
+    } else if extension == Some("aag") || extension == Some("aig") {
+        let aig = parse_aiger_file(path).context(ParserError)?;
+        let (_, output) = aiger_output(options, &aig)?;
+        print_result(solve(build(aig.to_cnf(output))));
+    } else if extension == Some("qdimacs") {
+        ensure!(
+            !stats,
+            UnsupportedStatistics {
+                input: "QDIMACS files"
+            }
+        );
+        let qbf = parse_qdimacs_file(path).context(ParserError)?;
+        print_qbf_result(qbf.solve(build));
+    } else {
+        let formula = parse_file(path).context(ParserError)?;
+        if options.value_or("symmetry", false)? {
+            let symmetries = find_symmetries(&formula);
+            let result = match solve(build(break_symmetries(&formula, &symmetries))) {
+                SolveResult::Sat(model) => {
+                    SolveResult::Sat(Model::from_extended(formula, model.assignment()))
+                }
+                result => result,
+            };
+            print_result(result);
+        } else {
+            print_result(solve(build(formula)));
+        }
+    }
+
+    Ok(())
+}
+
+fn dispatch_command<T: Solver>(
+    options: &Options,
+    args: Vec<String>,
+    build: impl FnMut(Cnf) -> T,
+) -> Result<(), Error> {
+    match args.first().map(|s| s.as_str()) {
         Some("check") => {
             let path = args.get(1).context(MissingArgument)?;
-            let result = solve_path::<T>(path.as_ref())?;
-            if let Some(model) = result {
-                println!("SAT {}", model);
-            } else {
-                println!("UNSAT");
+            check_path(options, path.as_ref(), build)?;
+        }
+        Some("smt") => {
+            let mut session = SmtSession::new(build);
+            let stdout = io::stdout();
+            let mut output = stdout.lock();
+            match args.get(1) {
+                Some(path) => {
+                    let file = File::open(path).context(InputError {
+                        path: path.as_str(),
+                    })?;
+                    session.run(BufReader::new(file), &mut output)
+                }
+                None => session.run(io::stdin().lock(), &mut output),
             }
+            .context(SmtError)?;
         }
         Some(name) => UnknownCommand {
             name: name.to_owned(),
@@ -60,6 +503,66 @@ fn dispatch_command<T: Solver>(args: Vec<String>) -> Result<(), Error> {
     Ok(())
 }
 
+/// Handles the cube-and-conquer specific commands.
+fn dispatch_cnc_command(options: &Options, args: Vec<String>) -> Result<(), Error> {
+    let config = options.cube_config()?;
+    match args.first().map(|s| s.as_str()) {
+        Some("cube") => {
+            let path = args.get(1).context(MissingArgument)?;
+            let formula = parse_file(path).context(ParserError)?;
+            let cubes = cube(&formula, &config);
+            let stdout = io::stdout();
+            write_icnf(&mut BufWriter::new(stdout.lock()), &formula, &cubes)
+                .context(OutputError)?;
+        }
+        Some("conquer") => {
+            let path = args.get(1).context(MissingArgument)?;
+            let (formula, cubes) = parse_icnf_file(path).context(ParserError)?;
+            print_result(CubeAndConquerSolver::with_cubes(formula, cubes, config).solve());
+        }
+        _ => dispatch_command(options, args, |formula| {
+            CubeAndConquerSolver::with_config(formula, config.clone())
+        })?,
+    }
+
+    Ok(())
+}
+
+/// Handles the CDCL specific commands.
+fn dispatch_cdcl_command(options: &Options, args: Vec<String>) -> Result<(), Error> {
+    let config = options.cdcl_config()?;
+    match args.first().map(|s| s.as_str()) {
+        Some("bmc") => {
+            let path = args.get(1).context(MissingArgument)?;
+            let aig = parse_aiger_file(path).context(ParserError)?;
+            let (index, bad) = aiger_output(options, &aig)?;
+            let property = bad_property(&aig, index)?;
+            let bound = options.value_or("bound", 20)?;
+            print_bmc_result(bmc::check_aig(&aig, bad, bound, config), property);
+        }
+        Some("backbone") => {
+            let path = args.get(1).context(MissingArgument)?;
+            let formula = parse_file(path).context(ParserError)?;
+            print_backbone_result(backbone(formula, config));
+        }
+        Some("mcs") => {
+            let path = args.get(1).context(MissingArgument)?;
+            let formula = parse_file(path).context(ParserError)?;
+            // Every clause is soft
+            let hard = Cnf::new(formula.num_variables());
+            let mcses: Vec<Vec<usize>> =
+                McsEnumerator::new(&hard, formula.clauses().to_vec(), config.clone()).collect();
+            print_clause_sets("MCS", &mcses);
+            print_clause_sets("MUS", &minimal_hitting_sets(&mcses, config));
+        }
+        _ => dispatch_command(options, args, |formula| {
+            CdclSolver::with_config(formula, config.clone())
+        })?,
+    }
+
+    Ok(())
+}
+
 fn init_logger() {
     let mut builder = formatted_builder();
 
@@ -86,11 +589,35 @@ fn main() -> Result<(), Report> {
 
     // solver name
     let solver_name = args.next();
-    let remaining: Vec<_> = args.collect();
+    let (options, remaining) = Options::parse(args.collect())?;
 
     match solver_name.as_deref() {
-        Some("dpll") => dispatch_command::<DpllSolver>(remaining)?,
-        Some("cdcl") => dispatch_command::<CdclSolver>(remaining)?,
+        Some("dpll") => {
+            let heuristic = options.solver_option("heuristic")?;
+            dispatch_command(&options, remaining, |formula| {
+                DpllSolver::with_heuristic(formula, heuristic)
+            })?
+        }
+        Some("cdcl") => dispatch_cdcl_command(&options, remaining)?,
+        Some("portfolio") => {
+            let config = options.portfolio_config()?;
+            dispatch_command(&options, remaining, |formula| {
+                PortfolioSolver::with_config(formula, config.clone())
+            })?
+        }
+        Some("walksat") => {
+            let config = options.local_search_config(LocalSearchAlgorithm::WALKSAT)?;
+            dispatch_command(&options, remaining, |formula| {
+                LocalSearchSolver::with_config(formula, config.clone())
+            })?
+        }
+        Some("probsat") => {
+            let config = options.local_search_config(LocalSearchAlgorithm::PROBSAT)?;
+            dispatch_command(&options, remaining, |formula| {
+                LocalSearchSolver::with_config(formula, config.clone())
+            })?
+        }
+        Some("cnc") => dispatch_cnc_command(&options, remaining)?,
         Some(name) => UnknownSolver {
             name: name.to_owned(),
         }
@@ -1,11 +1,17 @@
 use std::{
+    collections::{HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
 };
 
-use crate::formula::{Clause, Cnf, Literal, VariableParseError};
+use crate::aig::{Aig, AigLiteral, AndGate, Latch};
+use crate::formula::{
+    Cardinality, Clause, Cnf, Literal, Objective, PbConstraint, Variable, VariableParseError,
+    XorClause,
+};
 use crate::prelude::*;
+use crate::qbf::{Qbf, Quantifier};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -21,14 +27,38 @@ pub enum Error {
         clause: String,
         source: VariableParseError,
     },
+    #[snafu(display("Failed to parse line '{}' as cardinality constraint", constraint))]
+    MalformedCardinality { constraint: String },
+    #[snafu(display("Failed to parse '{}' as pseudo-Boolean constraint", constraint))]
+    MalformedPbConstraint { constraint: String },
     #[snafu(display("Problem line 'p cnf <num_variables> <num_clauses>' is not found"))]
     MalformedProblemDefinition,
+    #[snafu(display("Problem line 'p inccnf' is not found"))]
+    MalformedIncrementalProblemDefinition,
     #[snafu(display(
         "The number of clauses ({}) does not match the clauses number in the problem definition ({})",
         found,
         expected,
     ))]
     ClauseCountMismatch { expected: usize, found: usize },
+    #[snafu(display("Failed to parse line '{}' as quantifier block", line))]
+    MalformedQuantifierBlock { line: String },
+    #[snafu(display("Quantifier block '{}' must precede the clauses", line))]
+    MisplacedQuantifierBlock { line: String },
+    #[snafu(display("Variable {} is out of range or quantified twice", variable))]
+    InvalidQuantifiedVariable { variable: Variable },
+    #[snafu(display("Header line 'aag|aig M I L O A [B C J F]' is malformed: '{}'", header))]
+    MalformedAigerHeader { header: String },
+    #[snafu(display("Justice and fairness properties are not supported: '{}'", header))]
+    UnsupportedAigerProperty { header: String },
+    #[snafu(display("Failed to parse line '{}' of AIGER file", line))]
+    MalformedAigerLine { line: String },
+    #[snafu(display("Failed to decode AND gate {} of binary AIGER file", index))]
+    MalformedBinaryAnd { index: usize },
+    #[snafu(display("AIGER literal {} is out of range, redefined or undefined", literal))]
+    InvalidAigerLiteral { literal: AigLiteral },
+    #[snafu(display("AND gates of AIGER file form a cycle through literal {}", literal))]
+    CyclicAiger { literal: AigLiteral },
 }
 
 /// Parse a line to a clause
@@ -53,6 +83,36 @@ fn parse_line(line: &str) -> Result<Clause, Error> {
     Ok(Clause::new(variables))
 }
 
+/// Returns whether the line has a relation in the place of the `<=` of `1 -2 3 <= 2`.
+fn is_cardinality_line(line: &str) -> bool {
+    matches!(line.split_whitespace().rev().nth(1), Some("<=" | ">="))
+}
+
+/// Parse a line to a cardinality constraint in MiniCard format, e.g., `1 -2 3 <= 2`
+fn parse_cardinality_line(line: &str) -> Result<Cardinality, Error> {
+    let splitted = line.split_whitespace().collect::<Vec<_>>();
+    let malformed = || MalformedCardinality {
+        constraint: line.to_owned(),
+    };
+
+    ensure!(splitted.len() >= 2, malformed());
+    let (literals, relation) = splitted.split_at(splitted.len() - 2);
+    let bound = relation[1].parse::<usize>().ok().with_context(malformed)?;
+
+    let mut parsed = Vec::new();
+    for s in literals {
+        parsed.push(s.parse::<Literal>().with_context(|| MalformedVariable {
+            clause: line.to_owned(),
+        })?);
+    }
+
+    match relation[0] {
+        ">=" => Ok(Cardinality::at_least(parsed, bound)),
+        "<=" => Ok(Cardinality::at_most(parsed, bound)),
+        _ => malformed().fail(),
+    }
+}
+
 /// Parses CNF formula from a file
 pub fn parse_file(path: impl AsRef<Path>) -> Result<Cnf, Error> {
     let path = path.as_ref();
@@ -70,13 +130,14 @@ pub fn parse_file(path: impl AsRef<Path>) -> Result<Cnf, Error> {
         .next()
         .ok_or_else(|| MalformedProblemDefinition.build())?;
 
-    let splitted = prob_line.trim().split(" ").collect::<Vec<_>>();
+    let splitted = prob_line.split_whitespace().collect::<Vec<_>>();
 
-    // We only support CNF DIMACS format
+    // We only support CNF DIMACS format, and its `cnf+` extension with cardinality constraints
     ensure!(
-        splitted.len() == 4 || splitted[0] == "p" || splitted[1] == "cnf",
+        splitted.len() == 4 && splitted[0] == "p" && matches!(splitted[1], "cnf" | "cnf+"),
         MalformedProblemDefinition
     );
+    let with_cardinalities = splitted[1] == "cnf+";
 
     let (num_variables, num_clauses) =
         match (splitted[2].parse::<usize>(), splitted[3].parse::<usize>()) {
@@ -92,16 +153,553 @@ pub fn parse_file(path: impl AsRef<Path>) -> Result<Cnf, Error> {
             // empty line, comment
             continue;
         }
-        cnf.add_clause(parse_line(&trimmed)?);
+
+        if let Some(xor) = trimmed.strip_prefix('x') {
+            // XOR clause in CryptoMiniSat format, e.g., `x1 -2 3 0`
+            let literals = parse_line(xor.trim_start())?;
+            cnf.add_xor(XorClause::new(literals.iter().collect()));
+        } else if with_cardinalities && is_cardinality_line(trimmed) {
+            cnf.add_cardinality(parse_cardinality_line(trimmed)?);
+        } else {
+            cnf.add_clause(parse_line(trimmed)?);
+        }
     }
 
+    let found = cnf.clauses().len()
+        + cnf.xors().len()
+        + cnf.cardinalities().len()
+        + cnf.empty_clause_count();
     ensure!(
-        cnf.clauses().len() + cnf.empty_clause_count() == num_clauses,
+        found == num_clauses,
         ClauseCountMismatch {
-            found: cnf.clauses().len(),
+            found,
             expected: num_clauses,
         }
     );
 
     Ok(cnf)
 }
+
+/// Parses a QDIMACS file, a DIMACS CNF whose header is followed by quantifier blocks,
+/// each an `a <variables> 0` (universal) or `e <variables> 0` (existential) line.
+pub fn parse_qdimacs_file(path: impl AsRef<Path>) -> Result<Qbf, Error> {
+    let path = path.as_ref();
+    let file = BufReader::new(File::open(path).context(IoError {
+        path: path.to_owned(),
+    })?);
+
+    let mut lines = file
+        .lines()
+        .map(|line| line.unwrap())
+        .skip_while(|line| !line.starts_with('p'));
+
+    let prob_line = lines
+        .next()
+        .ok_or_else(|| MalformedProblemDefinition.build())?;
+    let splitted = prob_line.split_whitespace().collect::<Vec<_>>();
+    ensure!(
+        splitted.len() == 4 && splitted[0] == "p" && splitted[1] == "cnf",
+        MalformedProblemDefinition
+    );
+    let (num_variables, num_clauses) =
+        match (splitted[2].parse::<usize>(), splitted[3].parse::<usize>()) {
+            (Ok(num_variables), Ok(num_clauses)) => (num_variables, num_clauses),
+            _ => return MalformedProblemDefinition.fail(),
+        };
+
+    let mut prefix = Vec::new();
+    let mut cnf = Cnf::new(num_variables);
+    let mut seen = HashSet::new();
+
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('c') {
+            // empty line, comment
+            continue;
+        }
+
+        let quantifier = match trimmed.chars().next() {
+            Some('a') => Quantifier::Forall,
+            Some('e') => Quantifier::Exists,
+            _ => {
+                let clause = parse_line(trimmed)?;
+                for literal in clause.iter() {
+                    ensure!(
+                        literal.index() < num_variables,
+                        MalformedClause { clause: trimmed }
+                    );
+                }
+                cnf.add_clause(clause);
+                continue;
+            }
+        };
+
+        ensure!(
+            cnf.clauses().is_empty() && cnf.empty_clause_count() == 0,
+            MisplacedQuantifierBlock { line: trimmed }
+        );
+        let block = parse_line(trimmed[1..].trim_start())
+            .ok()
+            .context(MalformedQuantifierBlock { line: trimmed })?;
+        let mut variables = Vec::new();
+        for literal in block.iter() {
+            ensure!(
+                literal.positive(),
+                MalformedQuantifierBlock { line: trimmed }
+            );
+            let variable = literal.variable();
+            ensure!(
+                variable.index() < num_variables && seen.insert(variable),
+                InvalidQuantifiedVariable { variable }
+            );
+            variables.push(variable);
+        }
+        prefix.push((quantifier, variables));
+    }
+
+    let found = cnf.clauses().len() + cnf.empty_clause_count();
+    ensure!(
+        found == num_clauses,
+        ClauseCountMismatch {
+            found,
+            expected: num_clauses,
+        }
+    );
+
+    let mut qbf = Qbf::new(cnf);
+    for (quantifier, variables) in prefix {
+        qbf.add_block(quantifier, variables);
+    }
+    Ok(qbf)
+}
+
+/// Parses an incremental CNF (iCNF) file, a formula followed by cubes.
+/// Each cube is an assumption line `a <literals> 0`.
+pub fn parse_icnf_file(path: impl AsRef<Path>) -> Result<(Cnf, Vec<Vec<Literal>>), Error> {
+    let path = path.as_ref();
+    let file = BufReader::new(File::open(path).context(IoError {
+        path: path.to_owned(),
+    })?);
+
+    let mut lines = file
+        .lines()
+        .map(|line| line.unwrap())
+        .skip_while(|line| !line.starts_with('p'));
+
+    let prob_line = lines
+        .next()
+        .ok_or_else(|| MalformedIncrementalProblemDefinition.build())?;
+    ensure!(
+        prob_line
+            .split_whitespace()
+            .eq(["p", "inccnf"].iter().copied()),
+        MalformedIncrementalProblemDefinition
+    );
+
+    let mut clauses = Vec::new();
+    let mut cubes = Vec::new();
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('c') {
+            // empty line, comment
+            continue;
+        }
+
+        if let Some(cube) = trimmed.strip_prefix("a ") {
+            cubes.push(parse_line(cube.trim())?.iter().collect());
+        } else {
+            clauses.push(parse_line(trimmed)?);
+        }
+    }
+
+    // iCNF has no variable count, so take the largest variable in use
+    let num_variables = clauses
+        .iter()
+        .flat_map(|clause: &Clause| clause.iter())
+        .chain(cubes.iter().flatten().copied())
+        .map(|literal| literal.index() + 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut cnf = Cnf::new(num_variables);
+    for clause in clauses {
+        cnf.add_clause(clause);
+    }
+
+    Ok((cnf, cubes))
+}
+
+/// Parses a term list of an OPB statement, e.g., `+2 x1 -1 ~x3`
+fn parse_opb_terms(tokens: &[&str], statement: &str) -> Result<Vec<(i64, Literal)>, Error> {
+    let malformed = || MalformedPbConstraint {
+        constraint: statement.to_owned(),
+    };
+
+    // Products of literals (non-linear terms) are not supported
+    ensure!(tokens.len().is_multiple_of(2), malformed());
+
+    let mut terms = Vec::new();
+    for pair in tokens.chunks(2) {
+        let coefficient = pair[0].parse::<i64>().ok().with_context(malformed)?;
+        let (positive, variable) = match pair[1].strip_prefix('~') {
+            Some(variable) => (false, variable),
+            None => (true, pair[1]),
+        };
+        let variable = variable.strip_prefix('x').with_context(malformed)?;
+        let variable = variable.parse().with_context(|| MalformedVariable {
+            clause: statement.to_owned(),
+        })?;
+        terms.push((coefficient, Literal::new(variable, positive)));
+    }
+    Ok(terms)
+}
+
+/// Parses a pseudo-Boolean problem in the OPB format of the PB competitions,
+/// returning the constraints and the objective to minimize if any.
+///
+/// ```text
+/// * #variable= 3 #constraint= 2
+/// min: +1 x1 +2 x2 +3 x3 ;
+/// +1 x1 +1 x2 +1 ~x3 >= 2 ;
+/// +2 x1 -1 x3 = 1 ;
+/// ```
+pub fn parse_opb_file(path: impl AsRef<Path>) -> Result<(Cnf, Option<Objective>), Error> {
+    let path = path.as_ref();
+    let file = BufReader::new(File::open(path).context(IoError {
+        path: path.to_owned(),
+    })?);
+
+    let mut num_variables = 0;
+    let mut text = String::new();
+    for line in file.lines() {
+        let line = line.context(IoError {
+            path: path.to_owned(),
+        })?;
+        if let Some(comment) = line.trim_start().strip_prefix('*') {
+            // The first line declares the number of variables
+            let mut tokens = comment.split_whitespace();
+            while let Some(token) = tokens.next() {
+                if token == "#variable=" {
+                    if let Some(count) = tokens.next().and_then(|count| count.parse().ok()) {
+                        num_variables = count;
+                    }
+                }
+            }
+        } else {
+            text.push_str(&line);
+            text.push('\n');
+        }
+    }
+
+    let mut objective = None;
+    let mut constraints = Vec::new();
+    for statement in text.split(';').map(str::trim) {
+        if statement.is_empty() {
+            continue;
+        }
+        let tokens = statement.split_whitespace().collect::<Vec<_>>();
+
+        if tokens[0] == "min:" {
+            objective = Some(Objective::new(parse_opb_terms(&tokens[1..], statement)?));
+            continue;
+        }
+
+        let malformed = || MalformedPbConstraint {
+            constraint: statement.to_owned(),
+        };
+        ensure!(tokens.len() >= 2, malformed());
+        let (terms, relation) = tokens.split_at(tokens.len() - 2);
+        let terms = parse_opb_terms(terms, statement)?;
+        let bound = relation[1].parse::<i64>().ok().with_context(malformed)?;
+
+        match relation[0] {
+            ">=" => constraints.push(PbConstraint::at_least(terms, bound)),
+            "<=" => constraints.push(PbConstraint::at_most(terms, bound)),
+            "=" => {
+                constraints.push(PbConstraint::at_least(terms.clone(), bound));
+                constraints.push(PbConstraint::at_most(terms, bound));
+            }
+            _ => malformed().fail()?,
+        }
+    }
+
+    // The declaration may be missing, so also count the variables in use
+    let max_variable = constraints
+        .iter()
+        .flat_map(|constraint| constraint.terms().iter().map(|(_, literal)| *literal))
+        .chain(
+            objective
+                .iter()
+                .flat_map(|objective: &Objective| objective.terms().iter().map(|(_, l)| *l)),
+        )
+        .map(|literal| literal.index() + 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut cnf = Cnf::new(num_variables.max(max_variable));
+    for constraint in constraints {
+        cnf.add_pb(constraint);
+    }
+
+    Ok((cnf, objective))
+}
+
+/// Reads the lines and binary sections of an AIGER file.
+struct AigerReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl AigerReader<'_> {
+    fn line(&mut self) -> Option<String> {
+        if self.position >= self.bytes.len() {
+            return None;
+        }
+        let rest = &self.bytes[self.position..];
+        let length = rest
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .unwrap_or(rest.len());
+        self.position += length + 1;
+        Some(String::from_utf8_lossy(&rest[..length]).trim().to_owned())
+    }
+
+    /// Parses a line of literals, checking their range.
+    fn literals(&mut self, max_variable: usize) -> Result<Vec<AigLiteral>, Error> {
+        let line = self.line().unwrap_or_default();
+        let mut literals = Vec::new();
+        for token in line.split_whitespace() {
+            let literal = token
+                .parse()
+                .ok()
+                .map(AigLiteral::new)
+                .with_context(|| MalformedAigerLine { line: line.clone() })?;
+            ensure!(
+                literal.variable() <= max_variable,
+                InvalidAigerLiteral { literal }
+            );
+            literals.push(literal);
+        }
+        Ok(literals)
+    }
+
+    /// Decodes a delta of the binary format, 7 bits per byte from the least significant.
+    fn delta(&mut self) -> Option<u32> {
+        let mut delta = 0u32;
+        for shift in (0..32).step_by(7) {
+            let byte = *self.bytes.get(self.position)?;
+            self.position += 1;
+            delta |= u32::from(byte & 0x7f).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                return Some(delta);
+            }
+        }
+        None
+    }
+}
+
+/// Parses an and-inverter graph in the ASCII (`aag`) or binary (`aig`) AIGER format.
+/// Symbol tables and comments are ignored.
+pub fn parse_aiger_file(path: impl AsRef<Path>) -> Result<Aig, Error> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).context(IoError {
+        path: path.to_owned(),
+    })?;
+    let mut reader = AigerReader {
+        bytes: &bytes,
+        position: 0,
+    };
+
+    let header = reader.line().unwrap_or_default();
+    let tokens = header.split_whitespace().collect::<Vec<_>>();
+    let malformed = || MalformedAigerHeader {
+        header: header.clone(),
+    };
+    ensure!(
+        (6..=10).contains(&tokens.len()) && (tokens[0] == "aag" || tokens[0] == "aig"),
+        malformed()
+    );
+    let binary = tokens[0] == "aig";
+    let mut counts = Vec::new();
+    for token in &tokens[1..] {
+        counts.push(token.parse::<usize>().ok().with_context(malformed)?);
+    }
+    counts.resize(9, 0);
+    let [max_variable, num_inputs, num_latches, num_outputs, num_ands, num_bad, num_constraints, num_justice, num_fairness] =
+        counts[..]
+    else {
+        unreachable!()
+    };
+    ensure!(
+        num_justice == 0 && num_fairness == 0,
+        UnsupportedAigerProperty {
+            header: header.clone()
+        }
+    );
+    // Binary files number inputs, latches and gates consecutively
+    ensure!(
+        !binary || max_variable == num_inputs + num_latches + num_ands,
+        malformed()
+    );
+
+    let mut aig = Aig::new(max_variable);
+    let define = |aig: &Aig, literal: AigLiteral| {
+        ensure!(
+            !literal.is_negated() && aig.is_undefined(literal),
+            InvalidAigerLiteral { literal }
+        );
+        Ok(literal)
+    };
+
+    for index in 0..num_inputs {
+        let literal = if binary {
+            AigLiteral::new(2 * (index as u32 + 1))
+        } else {
+            single_literal(reader.literals(max_variable)?)?
+        };
+        aig.add_input(define(&aig, literal)?);
+    }
+
+    for index in 0..num_latches {
+        let mut literals = reader.literals(max_variable)?;
+        if binary {
+            literals.insert(0, AigLiteral::new(2 * ((num_inputs + index) as u32 + 1)));
+        }
+        let malformed = || MalformedAigerLine {
+            line: format!("latch {}", index),
+        };
+        ensure!(literals.len() == 2 || literals.len() == 3, malformed());
+        let literal = define(&aig, literals[0])?;
+        // The reset value is 0 by default, and the latch itself if uninitialized
+        let reset = match literals.get(2) {
+            None => Some(false),
+            Some(&AigLiteral::FALSE) => Some(false),
+            Some(&AigLiteral::TRUE) => Some(true),
+            Some(&reset) if reset == literal => None,
+            Some(_) => malformed().fail()?,
+        };
+        aig.add_latch(Latch {
+            literal,
+            next: literals[1],
+            reset,
+        });
+    }
+
+    let mut properties = |count: usize| -> Result<Vec<AigLiteral>, Error> {
+        (0..count)
+            .map(|_| single_literal(reader.literals(max_variable)?))
+            .collect()
+    };
+    let outputs = properties(num_outputs)?;
+    let bad = properties(num_bad)?;
+    let constraints = properties(num_constraints)?;
+
+    let mut gates = Vec::new();
+    for index in 0..num_ands {
+        let gate = if binary {
+            let output = AigLiteral::new(2 * ((num_inputs + num_latches + index) as u32 + 1));
+            let decoded = (|| {
+                let first = output.number().checked_sub(reader.delta()?)?;
+                let second = first.checked_sub(reader.delta()?)?;
+                (first < output.number()).then_some(())?;
+                Some([AigLiteral::new(first), AigLiteral::new(second)])
+            })();
+            let inputs = decoded.context(MalformedBinaryAnd { index })?;
+            AndGate { output, inputs }
+        } else {
+            let literals = reader.literals(max_variable)?;
+            ensure!(
+                literals.len() == 3,
+                MalformedAigerLine {
+                    line: format!("and {}", index),
+                }
+            );
+            AndGate {
+                output: literals[0],
+                inputs: [literals[1], literals[2]],
+            }
+        };
+        define(&aig, gate.output)?;
+        gates.push(gate);
+    }
+    add_ands_in_order(&mut aig, gates)?;
+
+    for literal in outputs {
+        aig.add_output(literal);
+    }
+    for literal in bad {
+        aig.add_bad(literal);
+    }
+    for literal in constraints {
+        aig.add_constraint(literal);
+    }
+    if !aig.is_defined() {
+        let literal = aig
+            .latches()
+            .iter()
+            .map(|latch| latch.next)
+            .chain(aig.outputs().iter().copied())
+            .chain(aig.bad().iter().copied())
+            .chain(aig.constraints().iter().copied())
+            .find(|&literal| aig.is_undefined(literal))
+            .unwrap();
+        return InvalidAigerLiteral { literal }.fail();
+    }
+
+    Ok(aig)
+}
+
+fn single_literal(literals: Vec<AigLiteral>) -> Result<AigLiteral, Error> {
+    ensure!(
+        literals.len() == 1,
+        MalformedAigerLine {
+            line: format!("{:?}", literals),
+        }
+    );
+    Ok(literals[0])
+}
+
+/// Adds the gates after the gates of their inputs, as ASCII files may define them in any order.
+fn add_ands_in_order(aig: &mut Aig, gates: Vec<AndGate>) -> Result<(), Error> {
+    let order: Vec<usize> = gates.iter().map(|gate| gate.output.variable()).collect();
+    let mut pending: HashMap<usize, AndGate> = gates
+        .into_iter()
+        .map(|gate| (gate.output.variable(), gate))
+        .collect();
+    let mut visiting = HashSet::new();
+
+    for root in order {
+        let mut stack = vec![(root, false)];
+        while let Some((variable, expanded)) = stack.pop() {
+            if expanded {
+                let gate = pending.remove(&variable).unwrap();
+                for input in gate.inputs {
+                    ensure!(
+                        !aig.is_undefined(input),
+                        InvalidAigerLiteral { literal: input }
+                    );
+                }
+                aig.add_and(gate);
+                continue;
+            }
+
+            let Some(gate) = pending.get(&variable) else {
+                continue;
+            };
+            ensure!(
+                visiting.insert(variable),
+                CyclicAiger {
+                    literal: gate.output
+                }
+            );
+            stack.push((variable, true));
+            for input in gate.inputs {
+                if pending.contains_key(&input.variable()) {
+                    stack.push((input.variable(), false));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,373 @@
+/*!
+Quantified Boolean formulas in prenex CNF.
+
+The solver is expansion based, in the style of RAReQS: a QBF is a game where each block
+is a move of its quantifier, and the outermost player searches for a winning move by
+counterexample-guided abstraction refinement. A candidate move is found in an abstraction
+of the game, then the rest of the game is solved recursively under the move. When the
+opponent wins, its counter-move is used to expand the game partially, with fresh copies of
+the inner variables, and the expansion is conjoined (or disjoined for a universal player)
+into the abstraction. Games with a single block are solved by a SAT solver.
+
+For 2QBF (`∃∀` or `∀∃`), this is the classic loop between SAT calls for candidate moves
+and for counter-moves, where each counter-move is added to the candidate formula.
+*/
+
+use std::collections::HashMap;
+
+use crate::encode::{CnfBuilder, Signal};
+use crate::formula::{Cnf, Literal, Variable};
+use crate::solver::{SolveResult, Solver};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quantifier {
+    Exists,
+    Forall,
+}
+
+/// A QBF with a prefix of quantifier blocks, outermost first, and a CNF matrix.
+#[derive(Debug, Clone)]
+pub struct Qbf {
+    prefix: Vec<(Quantifier, Vec<Variable>)>,
+    matrix: Cnf,
+}
+
+/// The result of solving a QBF.
+///
+/// A certificate assigns the outermost block when its player wins,
+/// and is empty when the player of the outermost block loses.
+/// Variables not in the prefix are existential in the outermost block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QbfResult {
+    True(Vec<Literal>),
+    False(Vec<Literal>),
+    Unknown,
+}
+
+impl Qbf {
+    /// Creates a QBF without quantifier blocks.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `matrix` has constraints other than clauses.
+    pub fn new(matrix: Cnf) -> Self {
+        assert!(
+            matrix.xors().is_empty()
+                && matrix.cardinalities().is_empty()
+                && matrix.pbs().is_empty()
+        );
+        Qbf {
+            prefix: Vec::new(),
+            matrix,
+        }
+    }
+
+    /// Appends an innermost quantifier block,
+    /// merging it into the previous block if the quantifiers are the same.
+    ///
+    /// # Panics
+    ///
+    /// Panics when a variable is out of range or already quantified.
+    pub fn add_block(&mut self, quantifier: Quantifier, variables: Vec<Variable>) {
+        for (index, &variable) in variables.iter().enumerate() {
+            assert!(variable.index() < self.matrix.num_variables());
+            assert!(!variables[..index].contains(&variable) && !self.is_quantified(variable));
+        }
+
+        match self.prefix.last_mut() {
+            Some((last, block)) if *last == quantifier => block.extend(variables),
+            _ => self.prefix.push((quantifier, variables)),
+        }
+    }
+
+    pub fn is_quantified(&self, variable: Variable) -> bool {
+        self.prefix
+            .iter()
+            .any(|(_, block)| block.contains(&variable))
+    }
+
+    pub fn prefix(&self) -> &[(Quantifier, Vec<Variable>)] {
+        &self.prefix
+    }
+
+    pub fn matrix(&self) -> &Cnf {
+        &self.matrix
+    }
+
+    /// Solves the QBF, building a solver with `build` for each propositional subproblem.
+    pub fn solve<S: Solver>(&self, mut build: impl FnMut(Cnf) -> S) -> QbfResult {
+        let free: Vec<Variable> = (0..self.matrix.num_variables())
+            .map(|index| Variable::from_index(index).unwrap())
+            .filter(|&variable| !self.is_quantified(variable))
+            .collect();
+        let mut prefix = self.prefix.clone();
+        match prefix.first_mut() {
+            Some((Quantifier::Exists, block)) => {
+                block.splice(0..0, free);
+            }
+            // Without free variables, a universal outermost block stays outermost
+            Some((Quantifier::Forall, _)) if free.is_empty() => (),
+            _ => prefix.insert(0, (Quantifier::Exists, free)),
+        }
+
+        let matrix = if self.matrix.empty_clause_count() > 0 {
+            Matrix::Const(false)
+        } else {
+            Matrix::and(
+                self.matrix
+                    .clauses()
+                    .iter()
+                    .map(|clause| Matrix::or(clause.iter().map(Matrix::Literal).collect()))
+                    .collect(),
+            )
+        };
+
+        let quantifier = prefix[0].0;
+        let mut solver = Rareqs {
+            build: &mut build,
+            num_variables: self.matrix.num_variables(),
+        };
+        let result = solver.solve(&Game { prefix, matrix });
+        match (quantifier, result) {
+            (_, Err(GaveUp)) => QbfResult::Unknown,
+            (Quantifier::Exists, Ok(Some(certificate))) => QbfResult::True(certificate),
+            (Quantifier::Exists, Ok(None)) => QbfResult::False(Vec::new()),
+            (Quantifier::Forall, Ok(Some(certificate))) => QbfResult::False(certificate),
+            (Quantifier::Forall, Ok(None)) => QbfResult::True(Vec::new()),
+        }
+    }
+}
+
+/// A matrix in negation normal form, where constants only appear at the root.
+#[derive(Debug, Clone)]
+enum Matrix {
+    Const(bool),
+    Literal(Literal),
+    And(Vec<Matrix>),
+    Or(Vec<Matrix>),
+}
+
+impl Matrix {
+    fn and(operands: Vec<Matrix>) -> Matrix {
+        Matrix::junction(true, operands)
+    }
+
+    fn or(operands: Vec<Matrix>) -> Matrix {
+        Matrix::junction(false, operands)
+    }
+
+    /// Builds a conjunction if `conjunctive`, or a disjunction otherwise,
+    /// flattening nested operands and propagating constants.
+    fn junction(conjunctive: bool, operands: Vec<Matrix>) -> Matrix {
+        let mut flattened = Vec::new();
+        for operand in operands {
+            match operand {
+                // The neutral element
+                Matrix::Const(value) if value == conjunctive => (),
+                Matrix::Const(value) => return Matrix::Const(value),
+                Matrix::And(nested) if conjunctive => flattened.extend(nested),
+                Matrix::Or(nested) if !conjunctive => flattened.extend(nested),
+                operand => flattened.push(operand),
+            }
+        }
+
+        match flattened.len() {
+            0 => Matrix::Const(conjunctive),
+            1 => flattened.pop().unwrap(),
+            _ if conjunctive => Matrix::And(flattened),
+            _ => Matrix::Or(flattened),
+        }
+    }
+
+    fn negate(&self) -> Matrix {
+        match self {
+            Matrix::Const(value) => Matrix::Const(!value),
+            Matrix::Literal(literal) => Matrix::Literal(!*literal),
+            Matrix::And(operands) => Matrix::Or(operands.iter().map(Matrix::negate).collect()),
+            Matrix::Or(operands) => Matrix::And(operands.iter().map(Matrix::negate).collect()),
+        }
+    }
+
+    /// Replaces each literal with the matrix given by `replace`.
+    fn substitute(&self, replace: &impl Fn(Literal) -> Matrix) -> Matrix {
+        match self {
+            Matrix::Const(value) => Matrix::Const(*value),
+            Matrix::Literal(literal) => replace(*literal),
+            Matrix::And(operands) => Matrix::and(
+                operands
+                    .iter()
+                    .map(|operand| operand.substitute(replace))
+                    .collect(),
+            ),
+            Matrix::Or(operands) => Matrix::or(
+                operands
+                    .iter()
+                    .map(|operand| operand.substitute(replace))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Assigns the variables of the literals in `assignment`.
+    fn assign(&self, assignment: &[Literal]) -> Matrix {
+        let values: HashMap<Variable, bool> = assignment
+            .iter()
+            .map(|literal| (literal.variable(), literal.positive()))
+            .collect();
+        self.substitute(&|literal| match values.get(&literal.variable()) {
+            Some(&value) => Matrix::Const(value == literal.positive()),
+            None => Matrix::Literal(literal),
+        })
+    }
+
+    /// Encodes the matrix by the Plaisted-Greenbaum transformation,
+    /// returning a signal implying the matrix.
+    fn encode(&self, builder: &mut CnfBuilder) -> Signal {
+        match self {
+            Matrix::Const(value) => {
+                if *value {
+                    Signal::True
+                } else {
+                    Signal::False
+                }
+            }
+            Matrix::Literal(literal) => Signal::Literal(*literal),
+            Matrix::And(operands) => {
+                let output = Signal::new(builder);
+                for operand in operands {
+                    let operand = operand.encode(builder);
+                    Signal::add_clause(builder, &[output.negate(), operand]);
+                }
+                output
+            }
+            Matrix::Or(operands) => {
+                let output = Signal::new(builder);
+                let mut clause = vec![output.negate()];
+                for operand in operands {
+                    clause.push(operand.encode(builder));
+                }
+                Signal::add_clause(builder, &clause);
+                output
+            }
+        }
+    }
+}
+
+/// A QBF where blocks alternate between the players.
+#[derive(Debug, Clone)]
+struct Game {
+    prefix: Vec<(Quantifier, Vec<Variable>)>,
+    matrix: Matrix,
+}
+
+/// The SAT solver gave up.
+struct GaveUp;
+
+struct Rareqs<'a, S> {
+    build: &'a mut dyn FnMut(Cnf) -> S,
+    /// Variables are never reused, so the copies made by expansion are fresh
+    num_variables: usize,
+}
+
+impl<'a, S: Solver> Rareqs<'a, S> {
+    /// Searches for a winning move for the player of the outermost block,
+    /// returning the values of the block in order, or `None` if the player loses.
+    fn solve(&mut self, game: &Game) -> Result<Option<Vec<Literal>>, GaveUp> {
+        let (quantifier, block) = &game.prefix[0];
+        if game.prefix.len() == 1 {
+            return self.solve_propositional(*quantifier, block, &game.matrix);
+        }
+
+        let mut abstraction = Game {
+            prefix: vec![(*quantifier, block.clone())],
+            matrix: Matrix::Const(*quantifier == Quantifier::Exists),
+        };
+        loop {
+            let candidate = match self.solve(&abstraction)? {
+                Some(mut candidate) => {
+                    // Drop the copies of the inner variables, which come after the block
+                    candidate.truncate(block.len());
+                    candidate
+                }
+                None => return Ok(None),
+            };
+
+            let rest = Game {
+                prefix: game.prefix[1..].to_vec(),
+                matrix: game.matrix.assign(&candidate),
+            };
+            match self.solve(&rest)? {
+                Some(counter) => self.refine(&mut abstraction, game, &counter),
+                None => return Ok(Some(candidate)),
+            }
+        }
+    }
+
+    /// Solves a game of a single block, where the universal player wins by falsifying the matrix.
+    fn solve_propositional(
+        &mut self,
+        quantifier: Quantifier,
+        block: &[Variable],
+        matrix: &Matrix,
+    ) -> Result<Option<Vec<Literal>>, GaveUp> {
+        let matrix = match quantifier {
+            Quantifier::Exists => matrix.clone(),
+            Quantifier::Forall => matrix.negate(),
+        };
+
+        let mut builder = CnfBuilder::new(self.num_variables);
+        let root = matrix.encode(&mut builder);
+        if root == Signal::False {
+            return Ok(None);
+        }
+        Signal::add_clause(&mut builder, &[root]);
+
+        match (self.build)(builder.build()).solve() {
+            SolveResult::Sat(model) => Ok(Some(
+                block
+                    .iter()
+                    .map(|&variable| Literal::new(variable, variable.value(model.assignment())))
+                    .collect(),
+            )),
+            SolveResult::Unsat => Ok(None),
+            SolveResult::Unknown => Err(GaveUp),
+        }
+    }
+
+    /// Adds the expansion of `game` by the counter-move of the opponent to the abstraction,
+    /// renaming the variables after the counter-move to fresh copies.
+    fn refine(&mut self, abstraction: &mut Game, game: &Game, counter: &[Literal]) {
+        let mut renaming = HashMap::new();
+        for (level, (quantifier, block)) in game.prefix[2..].iter().enumerate() {
+            let copies: Vec<Variable> = block
+                .iter()
+                .map(|&variable| {
+                    let copy = Variable::from_index(self.num_variables).unwrap();
+                    self.num_variables += 1;
+                    renaming.insert(variable, copy);
+                    copy
+                })
+                .collect();
+
+            // Levels of the game after the counter-move align with those of the abstraction
+            match abstraction.prefix.get_mut(level) {
+                Some((_, abstract_block)) => abstract_block.extend(copies),
+                None => abstraction.prefix.push((*quantifier, copies)),
+            }
+        }
+
+        let expansion = game
+            .matrix
+            .assign(counter)
+            .substitute(&|literal| match renaming.get(&literal.variable()) {
+                Some(&copy) => Matrix::Literal(Literal::new(copy, literal.positive())),
+                None => Matrix::Literal(literal),
+            });
+
+        let matrix = std::mem::replace(&mut abstraction.matrix, Matrix::Const(false));
+        abstraction.matrix = match game.prefix[0].0 {
+            Quantifier::Exists => Matrix::and(vec![matrix, expansion]),
+            Quantifier::Forall => Matrix::or(vec![matrix, expansion]),
+        };
+    }
+}
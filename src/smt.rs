@@ -0,0 +1,867 @@
+/*!
+SMT-LIB 2 front-end for propositional and fixed-width bit-vector (QF_BV) problems.
+
+Commands are read one at a time and answered as soon as they are complete,
+so a session can be driven interactively through pipes.
+
+* `declare-const`, `declare-fun` without arguments, and `define-fun` without arguments
+* `assert`, `check-sat`, `get-model`, `get-value`, `push`, `pop`, `reset`, `echo`, and `exit`
+* `set-logic`, `set-option` and `set-info` are accepted and ignored.
+
+Terms are of sort `Bool` or `(_ BitVec n)` with `1 <= n <= 64`, and may use `let`.
+Every `check-sat` bit-blasts the assertions of all levels into a new formula.
+Errors are reported as `(error "...")` and the session continues.
+*/
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt::Display,
+    io::{self, BufRead, Write},
+};
+
+use crate::bv::{BitBlaster, BitVector, Bool, BvOperator};
+use crate::formula::Cnf;
+use crate::prelude::*;
+use crate::solver::{SolveResult, Solver};
+
+const MAX_WIDTH: usize = 64;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("I/O error occurred in SMT-LIB session"))]
+    IoError { source: io::Error },
+    #[snafu(display("Unbalanced parentheses"))]
+    UnbalancedParentheses,
+    #[snafu(display("Unterminated string literal or quoted symbol"))]
+    UnterminatedLiteral,
+    #[snafu(display("Malformed command '{}'", command))]
+    MalformedCommand { command: String },
+    #[snafu(display("Malformed term '{}'", term))]
+    MalformedTerm { term: String },
+    #[snafu(display("Unknown symbol '{}'", name))]
+    UnknownSymbol { name: String },
+    #[snafu(display("Unsupported operator '{}'", name))]
+    UnsupportedOperator { name: String },
+    #[snafu(display("Unsupported sort '{}'", sort))]
+    UnsupportedSort { sort: String },
+    #[snafu(display("'{}' is not of sort {}", term, sort))]
+    SortMismatch { term: String, sort: String },
+    #[snafu(display("'{}' is already declared", name))]
+    AlreadyDeclared { name: String },
+    #[snafu(display("Cannot pop {} levels, only {} pushed", levels, pushed))]
+    PopTooMany { levels: usize, pushed: usize },
+    #[snafu(display("No model is available"))]
+    NoModel,
+}
+
+/// An S-expression, where string literals keep their quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+impl Sexp {
+    fn atom(&self) -> Option<&str> {
+        match self {
+            Sexp::Atom(atom) => Some(atom),
+            Sexp::List(_) => None,
+        }
+    }
+
+    fn list(&self) -> Option<&[Sexp]> {
+        match self {
+            Sexp::Atom(_) => None,
+            Sexp::List(list) => Some(list),
+        }
+    }
+
+    fn numeral(&self) -> Option<usize> {
+        self.atom()
+            .filter(|atom| atom.bytes().all(|byte| byte.is_ascii_digit()))
+            .and_then(|atom| atom.parse().ok())
+    }
+}
+
+impl Display for Sexp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sexp::Atom(atom) => write!(f, "{}", atom),
+            Sexp::List(list) => {
+                write!(f, "(")?;
+                for (i, sexp) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", sexp)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Reads S-expressions, taking input line by line only when needed.
+struct SexpReader<R> {
+    input: R,
+    line: Vec<char>,
+    position: usize,
+}
+
+impl<R: BufRead> SexpReader<R> {
+    fn new(input: R) -> Self {
+        SexpReader {
+            input,
+            line: Vec::new(),
+            position: 0,
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<char>, Error> {
+        while self.position == self.line.len() {
+            let mut line = String::new();
+            if self.input.read_line(&mut line).context(IoError)? == 0 {
+                return Ok(None);
+            }
+            self.line = line.chars().collect();
+            self.position = 0;
+        }
+        Ok(Some(self.line[self.position]))
+    }
+
+    fn next(&mut self) -> Result<Option<char>, Error> {
+        let c = self.peek()?;
+        if c.is_some() {
+            self.position += 1;
+        }
+        Ok(c)
+    }
+
+    /// Skips whitespace and comments, returning the next character.
+    fn skip_blank(&mut self) -> Result<Option<char>, Error> {
+        loop {
+            match self.peek()? {
+                Some(c) if c.is_whitespace() => self.position += 1,
+                // Comments run to the end of the line
+                Some(';') => self.position = self.line.len(),
+                c => return Ok(c),
+            }
+        }
+    }
+
+    /// Returns the next S-expression, or `None` at the end of the input.
+    fn read(&mut self) -> Result<Option<Sexp>, Error> {
+        match self.skip_blank()? {
+            None => Ok(None),
+            Some(_) => self.read_sexp().map(Some),
+        }
+    }
+
+    fn read_sexp(&mut self) -> Result<Sexp, Error> {
+        match self.next()? {
+            Some('(') => {
+                let mut list = Vec::new();
+                loop {
+                    match self.skip_blank()? {
+                        None => return UnbalancedParentheses.fail(),
+                        Some(')') => {
+                            self.position += 1;
+                            return Ok(Sexp::List(list));
+                        }
+                        Some(_) => list.push(self.read_sexp()?),
+                    }
+                }
+            }
+            Some(')') | None => UnbalancedParentheses.fail(),
+            Some('"') => {
+                let mut string = String::from('"');
+                loop {
+                    let c = self.next()?.context(UnterminatedLiteral)?;
+                    string.push(c);
+                    // A doubled quote is an escaped quote
+                    if c == '"' {
+                        if self.peek()? != Some('"') {
+                            return Ok(Sexp::Atom(string));
+                        }
+                        string.push('"');
+                        self.position += 1;
+                    }
+                }
+            }
+            Some('|') => {
+                let mut symbol = String::new();
+                loop {
+                    match self.next()?.context(UnterminatedLiteral)? {
+                        '|' => return Ok(Sexp::Atom(symbol)),
+                        c => symbol.push(c),
+                    }
+                }
+            }
+            Some(c) => {
+                let mut atom = String::from(c);
+                while let Some(c) = self.peek()? {
+                    if c.is_whitespace() || "()\";|".contains(c) {
+                        break;
+                    }
+                    atom.push(c);
+                    self.position += 1;
+                }
+                Ok(Sexp::Atom(atom))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    Bool,
+    BitVec(usize),
+}
+
+impl Display for Sort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sort::Bool => write!(f, "Bool"),
+            Sort::BitVec(width) => write!(f, "(_ BitVec {})", width),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Bool(Bool),
+    BitVector(BitVector),
+}
+
+impl Term {
+    fn sort(&self) -> Sort {
+        match self {
+            Term::Bool(_) => Sort::Bool,
+            Term::BitVector(term) => Sort::BitVec(term.width()),
+        }
+    }
+}
+
+/// The declarations and assertions of an assertion level.
+#[derive(Debug, Default)]
+struct Level {
+    names: Vec<String>,
+    assertions: Vec<Bool>,
+}
+
+enum Response {
+    Silent,
+    Text(String),
+    Exit,
+}
+
+/// An SMT-LIB session solving with the solvers created by `build`.
+pub struct SmtSession<F> {
+    build: F,
+    /// The base level followed by the pushed levels
+    levels: Vec<Level>,
+    sorts: HashMap<String, Sort>,
+    definitions: HashMap<String, Term>,
+    /// The values of the declared constants after `check-sat` found a model
+    model: Option<HashMap<String, u64>>,
+}
+
+impl<S: Solver, F: FnMut(Cnf) -> S> SmtSession<F> {
+    pub fn new(build: F) -> Self {
+        SmtSession {
+            build,
+            levels: vec![Level::default()],
+            sorts: HashMap::new(),
+            definitions: HashMap::new(),
+            model: None,
+        }
+    }
+
+    /// Answers the commands from `input` until the end or `exit`.
+    /// Errors of commands are written to `output`, and only I/O errors are returned.
+    pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) -> Result<(), Error> {
+        let mut reader = SexpReader::new(input);
+        loop {
+            let response = match reader.read() {
+                Ok(None) => break,
+                Ok(Some(command)) => self.execute(&command),
+                Err(error @ Error::IoError { .. }) => return Err(error),
+                // The input cannot be resynchronized after a syntax error
+                Err(error) => {
+                    writeln!(output, "(error \"{}\")", escape(&error)).context(IoError)?;
+                    break;
+                }
+            };
+            match response {
+                Ok(Response::Silent) => (),
+                Ok(Response::Text(text)) => writeln!(output, "{}", text).context(IoError)?,
+                Ok(Response::Exit) => break,
+                Err(error) => {
+                    writeln!(output, "(error \"{}\")", escape(&error)).context(IoError)?
+                }
+            }
+            output.flush().context(IoError)?;
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, command: &Sexp) -> Result<Response, Error> {
+        let malformed = || MalformedCommand {
+            command: command.to_string(),
+        };
+        let list = command.list().with_context(malformed)?;
+        let name = list.first().and_then(Sexp::atom).with_context(malformed)?;
+        let arguments = &list[1..];
+
+        match (name, arguments) {
+            ("set-logic", [_]) | ("set-option", [_, _]) | ("set-info", [_, ..]) => (),
+            ("declare-const", [name, sort]) => self.declare_constant(command, name, sort)?,
+            ("declare-fun", [name, Sexp::List(parameters), sort]) if parameters.is_empty() => {
+                self.declare_constant(command, name, sort)?
+            }
+            ("define-fun", [name, Sexp::List(parameters), sort, body]) if parameters.is_empty() => {
+                let name = name.atom().with_context(malformed)?;
+                let sort = parse_sort(sort)?;
+                let term = self.term(body, &mut Vec::new())?;
+                expect_sort(&term, sort, body)?;
+                self.declare(name)?;
+                self.definitions.insert(name.to_owned(), term);
+            }
+            ("assert", [term]) => {
+                let assertion = self.bool_term(term, &mut Vec::new())?;
+                self.levels.last_mut().unwrap().assertions.push(assertion);
+                self.model = None;
+            }
+            ("check-sat", []) => return Ok(Response::Text(self.check_sat().to_owned())),
+            ("get-model", []) => return self.get_model().map(Response::Text),
+            ("get-value", [Sexp::List(terms)]) => return self.get_value(terms).map(Response::Text),
+            ("push", levels) => {
+                for _ in 0..parse_levels(levels).with_context(malformed)? {
+                    self.levels.push(Level::default());
+                }
+                self.model = None;
+            }
+            ("pop", levels) => {
+                let levels = parse_levels(levels).with_context(malformed)?;
+                let pushed = self.levels.len() - 1;
+                ensure!(levels <= pushed, PopTooMany { levels, pushed });
+                for level in self.levels.split_off(self.levels.len() - levels) {
+                    for name in level.names {
+                        self.sorts.remove(&name);
+                        self.definitions.remove(&name);
+                    }
+                }
+                self.model = None;
+            }
+            ("reset", []) => {
+                self.levels = vec![Level::default()];
+                self.sorts.clear();
+                self.definitions.clear();
+                self.model = None;
+            }
+            ("echo", [Sexp::Atom(string)]) if string.starts_with('"') => {
+                return Ok(Response::Text(string.clone()))
+            }
+            ("exit", []) => return Ok(Response::Exit),
+            _ => return malformed().fail(),
+        }
+        Ok(Response::Silent)
+    }
+
+    fn declare_constant(&mut self, command: &Sexp, name: &Sexp, sort: &Sexp) -> Result<(), Error> {
+        let name = name.atom().with_context(|| MalformedCommand {
+            command: command.to_string(),
+        })?;
+        let sort = parse_sort(sort)?;
+        self.declare(name)?;
+        self.sorts.insert(name.to_owned(), sort);
+        Ok(())
+    }
+
+    fn declare(&mut self, name: &str) -> Result<(), Error> {
+        ensure!(
+            !self.sorts.contains_key(name) && !self.definitions.contains_key(name),
+            AlreadyDeclared { name }
+        );
+        self.levels.last_mut().unwrap().names.push(name.to_owned());
+        self.model = None;
+        Ok(())
+    }
+
+    /// Returns the declared constants in the order of declaration.
+    fn constants(&self) -> impl Iterator<Item = (&String, Sort)> {
+        self.levels
+            .iter()
+            .flat_map(|level| &level.names)
+            .filter_map(move |name| self.sorts.get(name).map(|&sort| (name, sort)))
+    }
+
+    fn check_sat(&mut self) -> &'static str {
+        let mut blaster = BitBlaster::new();
+        for (name, sort) in self.constants() {
+            match sort {
+                Sort::Bool => blaster.declare_bool(name),
+                Sort::BitVec(width) => blaster.declare(name, width),
+            }
+        }
+        for assertion in self.levels.iter().flat_map(|level| &level.assertions) {
+            blaster.assert(assertion);
+        }
+        let (formula, variables) = blaster.build();
+
+        debug!("Bit-blasted into {} variables", formula.num_variables());
+        match (self.build)(formula).solve() {
+            SolveResult::Sat(model) => {
+                let values = self
+                    .constants()
+                    .map(|(name, sort)| {
+                        let value = match sort {
+                            Sort::Bool => variables.bool_value(&model, name).unwrap() as u64,
+                            Sort::BitVec(_) => variables.value(&model, name).unwrap(),
+                        };
+                        (name.clone(), value)
+                    })
+                    .collect();
+                self.model = Some(values);
+                "sat"
+            }
+            SolveResult::Unsat => "unsat",
+            SolveResult::Unknown => "unknown",
+        }
+    }
+
+    fn get_model(&self) -> Result<String, Error> {
+        let values = self.model.as_ref().context(NoModel)?;
+        let mut text = String::from("(");
+        for (name, sort) in self.constants() {
+            text += &format!(
+                "\n  (define-fun {} () {} {})",
+                symbol(name),
+                sort,
+                format_value(values[name], sort)
+            );
+        }
+        text += "\n)";
+        Ok(text)
+    }
+
+    fn get_value(&self, terms: &[Sexp]) -> Result<String, Error> {
+        let values = self.model.as_ref().context(NoModel)?;
+        let value_of = |name: &str| values[name];
+        let mut pairs = Vec::new();
+        for sexp in terms {
+            let term = self.term(sexp, &mut Vec::new())?;
+            let value = match &term {
+                Term::Bool(term) => term.value(&value_of) as u64,
+                Term::BitVector(term) => term.value(&value_of),
+            };
+            pairs.push(format!("({} {})", sexp, format_value(value, term.sort())));
+        }
+        Ok(format!("({})", pairs.join(" ")))
+    }
+
+    fn bool_term(&self, sexp: &Sexp, bindings: &mut Vec<(String, Term)>) -> Result<Bool, Error> {
+        expect_bool(self.term(sexp, bindings)?, sexp)
+    }
+
+    /// Translates a term, where `bindings` are the variables bound by `let` so far.
+    fn term(&self, sexp: &Sexp, bindings: &mut Vec<(String, Term)>) -> Result<Term, Error> {
+        let malformed = || MalformedTerm {
+            term: sexp.to_string(),
+        };
+        let list = match sexp {
+            Sexp::Atom(atom) => return self.atom_term(atom, bindings),
+            Sexp::List(list) => list,
+        };
+
+        match list.as_slice() {
+            [Sexp::Atom(name), Sexp::List(bound), body] if name == "let" => {
+                // The bound terms are translated before any of the new bindings is visible
+                let mut new_bindings = Vec::new();
+                for binding in bound {
+                    match binding.list() {
+                        Some([Sexp::Atom(variable), term]) => {
+                            new_bindings.push((variable.clone(), self.term(term, bindings)?))
+                        }
+                        _ => return malformed().fail(),
+                    }
+                }
+                let depth = bindings.len();
+                bindings.extend(new_bindings);
+                let body = self.term(body, bindings);
+                bindings.truncate(depth);
+                body
+            }
+            [Sexp::Atom(underscore), Sexp::Atom(value), width]
+                if underscore == "_" && value.starts_with("bv") =>
+            {
+                let width = width.numeral().with_context(malformed)?;
+                check_width(width)?;
+                let value: u64 = value[2..].parse().ok().with_context(malformed)?;
+                ensure!(value >> (width - 1) >> 1 == 0, malformed());
+                Ok(Term::BitVector(BitVector::constant(value, width)))
+            }
+            [Sexp::List(indices), operand] => {
+                let operand = expect_bit_vector(self.term(operand, bindings)?, operand)?;
+                apply_indexed(sexp, indices, operand)
+            }
+            [Sexp::Atom(name), arguments @ ..] if !arguments.is_empty() => {
+                let terms = arguments
+                    .iter()
+                    .map(|argument| self.term(argument, bindings))
+                    .collect::<Result<Vec<_>, _>>()?;
+                apply(sexp, name, arguments, terms)
+            }
+            _ => malformed().fail(),
+        }
+    }
+
+    fn atom_term(&self, atom: &str, bindings: &[(String, Term)]) -> Result<Term, Error> {
+        if let Some((_, term)) = bindings.iter().rev().find(|(name, _)| name == atom) {
+            return Ok(term.clone());
+        }
+        if let Some(term) = self.definitions.get(atom) {
+            return Ok(term.clone());
+        }
+        if let Some(&sort) = self.sorts.get(atom) {
+            return Ok(match sort {
+                Sort::Bool => Term::Bool(Bool::var(atom)),
+                Sort::BitVec(width) => Term::BitVector(BitVector::var(atom, width)),
+            });
+        }
+
+        let malformed = || MalformedTerm { term: atom };
+        let (digits, radix) = match atom {
+            "true" => return Ok(Term::Bool(Bool::Const(true))),
+            "false" => return Ok(Term::Bool(Bool::Const(false))),
+            _ if atom.starts_with("#b") => (&atom[2..], 2),
+            _ if atom.starts_with("#x") => (&atom[2..], 16),
+            _ => return UnknownSymbol { name: atom }.fail(),
+        };
+        let width = digits.len() * if radix == 2 { 1 } else { 4 };
+        ensure!(!digits.is_empty(), malformed());
+        check_width(width)?;
+        let value = u64::from_str_radix(digits, radix)
+            .ok()
+            .with_context(malformed)?;
+        Ok(Term::BitVector(BitVector::constant(value, width)))
+    }
+}
+
+/// Applies an operator to the translated arguments.
+fn apply(sexp: &Sexp, name: &str, arguments: &[Sexp], terms: Vec<Term>) -> Result<Term, Error> {
+    let malformed = || MalformedTerm {
+        term: sexp.to_string(),
+    };
+
+    match name {
+        "not" | "and" | "or" | "xor" | "=>" => {
+            let mut terms = expect_bools(terms, arguments)?;
+            let term = match name {
+                "not" => {
+                    ensure!(terms.len() == 1, malformed());
+                    !terms.pop().unwrap()
+                }
+                "and" => Bool::and(terms),
+                "or" => Bool::or(terms),
+                "xor" => {
+                    let mut terms = terms.into_iter();
+                    let first = terms.next().unwrap();
+                    terms.fold(first, |result, term| result ^ term)
+                }
+                // Right associative
+                _ => {
+                    let last = terms.pop().unwrap();
+                    terms
+                        .into_iter()
+                        .rev()
+                        .fold(last, |result, term| Bool::implies(term, result))
+                }
+            };
+            Ok(Term::Bool(term))
+        }
+        "=" | "distinct" => {
+            let pairs: Vec<(usize, usize)> = if name == "=" {
+                (1..terms.len()).map(|j| (j - 1, j)).collect()
+            } else {
+                (0..terms.len())
+                    .flat_map(|i| (i + 1..terms.len()).map(move |j| (i, j)))
+                    .collect()
+            };
+            let equalities: Vec<Bool> = if terms[0].sort() == Sort::Bool {
+                let terms = expect_bools(terms, arguments)?;
+                pairs
+                    .iter()
+                    .map(|&(i, j)| Bool::iff(terms[i].clone(), terms[j].clone()))
+                    .collect()
+            } else {
+                let terms = expect_bit_vectors(terms, arguments, true)?;
+                pairs
+                    .iter()
+                    .map(|&(i, j)| Bool::equal(terms[i].clone(), terms[j].clone()))
+                    .collect()
+            };
+            Ok(Term::Bool(if name == "=" {
+                Bool::and(equalities)
+            } else {
+                Bool::and(equalities.into_iter().map(|equality| !equality).collect())
+            }))
+        }
+        "ite" => {
+            let (condition, then, otherwise) = match <[Term; 3]>::try_from(terms) {
+                Ok([condition, then, otherwise]) => (condition, then, otherwise),
+                Err(_) => return malformed().fail(),
+            };
+            let condition = expect_bool(condition, &arguments[0])?;
+            ensure!(
+                then.sort() == otherwise.sort(),
+                SortMismatch {
+                    term: arguments[2].to_string(),
+                    sort: then.sort().to_string(),
+                }
+            );
+            Ok(match (then, otherwise) {
+                (Term::Bool(then), Term::Bool(otherwise)) => {
+                    Term::Bool(Bool::ite(condition, then, otherwise))
+                }
+                (Term::BitVector(then), Term::BitVector(otherwise)) => {
+                    Term::BitVector(BitVector::ite(condition, then, otherwise))
+                }
+                _ => unreachable!(),
+            })
+        }
+        "concat" => {
+            let terms = expect_bit_vectors(terms, arguments, false)?;
+            check_width(terms.iter().map(BitVector::width).sum())?;
+            let mut terms = terms.into_iter();
+            let first = terms.next().unwrap();
+            Ok(Term::BitVector(terms.fold(first, BitVector::concat)))
+        }
+        "bvnot" | "bvneg" => {
+            let mut terms = expect_bit_vectors(terms, arguments, false)?;
+            ensure!(terms.len() == 1, malformed());
+            let term = terms.pop().unwrap();
+            Ok(Term::BitVector(if name == "bvnot" { !term } else { -term }))
+        }
+        _ => {
+            if let Some(operator) = bv_operator(name) {
+                // Left associative
+                let terms = expect_bit_vectors(terms, arguments, true)?;
+                ensure!(terms.len() >= 2, malformed());
+                let mut terms = terms.into_iter();
+                let first = terms.next().unwrap();
+                let term = terms.fold(first, |result, term| {
+                    BitVector::binary(operator, result, term)
+                });
+                return Ok(Term::BitVector(term));
+            }
+
+            let compare = comparison(name).context(UnsupportedOperator { name })?;
+            match <[BitVector; 2]>::try_from(expect_bit_vectors(terms, arguments, true)?) {
+                Ok([a, b]) => Ok(Term::Bool(compare(a, b))),
+                Err(_) => malformed().fail(),
+            }
+        }
+    }
+}
+
+/// Applies an indexed operator `(_ name index...)`.
+fn apply_indexed(sexp: &Sexp, indices: &[Sexp], operand: BitVector) -> Result<Term, Error> {
+    let malformed = || MalformedTerm {
+        term: sexp.to_string(),
+    };
+    let name = match indices {
+        [Sexp::Atom(underscore), Sexp::Atom(name), ..] if underscore == "_" => name,
+        _ => return malformed().fail(),
+    };
+    let indices: Vec<usize> = indices[2..]
+        .iter()
+        .map(Sexp::numeral)
+        .collect::<Option<_>>()
+        .with_context(malformed)?;
+
+    let width = operand.width();
+    let term = match (name.as_str(), indices.as_slice()) {
+        ("extract", &[high, low]) => {
+            ensure!(low <= high && high < width, malformed());
+            BitVector::extract(high, low, operand)
+        }
+        ("zero_extend", &[extra]) => {
+            check_width(width + extra)?;
+            BitVector::zero_extend(extra, operand)
+        }
+        ("sign_extend", &[extra]) => {
+            check_width(width + extra)?;
+            BitVector::sign_extend(extra, operand)
+        }
+        _ => {
+            return UnsupportedOperator {
+                name: name.as_str(),
+            }
+            .fail()
+        }
+    };
+    Ok(Term::BitVector(term))
+}
+
+fn bv_operator(name: &str) -> Option<BvOperator> {
+    Some(match name {
+        "bvand" => BvOperator::And,
+        "bvor" => BvOperator::Or,
+        "bvxor" => BvOperator::Xor,
+        "bvadd" => BvOperator::Add,
+        "bvsub" => BvOperator::Sub,
+        "bvmul" => BvOperator::Mul,
+        "bvshl" => BvOperator::Shl,
+        "bvlshr" => BvOperator::Lshr,
+        "bvashr" => BvOperator::Ashr,
+        _ => return None,
+    })
+}
+
+fn comparison(name: &str) -> Option<fn(BitVector, BitVector) -> Bool> {
+    Some(match name {
+        "bvult" => Bool::ult,
+        "bvule" => Bool::ule,
+        "bvugt" => Bool::ugt,
+        "bvuge" => Bool::uge,
+        "bvslt" => Bool::slt,
+        "bvsle" => Bool::sle,
+        "bvsgt" => Bool::sgt,
+        "bvsge" => Bool::sge,
+        _ => return None,
+    })
+}
+
+fn expect_bool(term: Term, sexp: &Sexp) -> Result<Bool, Error> {
+    match term {
+        Term::Bool(term) => Ok(term),
+        Term::BitVector(_) => SortMismatch {
+            term: sexp.to_string(),
+            sort: "Bool",
+        }
+        .fail(),
+    }
+}
+
+fn expect_bit_vector(term: Term, sexp: &Sexp) -> Result<BitVector, Error> {
+    match term {
+        Term::BitVector(term) => Ok(term),
+        Term::Bool(_) => SortMismatch {
+            term: sexp.to_string(),
+            sort: "BitVec",
+        }
+        .fail(),
+    }
+}
+
+fn expect_bools(terms: Vec<Term>, arguments: &[Sexp]) -> Result<Vec<Bool>, Error> {
+    terms
+        .into_iter()
+        .zip(arguments)
+        .map(|(term, argument)| expect_bool(term, argument))
+        .collect()
+}
+
+/// Expects bit-vectors, all of the width of the first one if `same_width`.
+fn expect_bit_vectors(
+    terms: Vec<Term>,
+    arguments: &[Sexp],
+    same_width: bool,
+) -> Result<Vec<BitVector>, Error> {
+    let terms = terms
+        .into_iter()
+        .zip(arguments)
+        .map(|(term, argument)| expect_bit_vector(term, argument))
+        .collect::<Result<Vec<_>, _>>()?;
+    if same_width {
+        let sort = Sort::BitVec(terms[0].width());
+        for (term, argument) in terms.iter().zip(arguments) {
+            ensure!(
+                Sort::BitVec(term.width()) == sort,
+                SortMismatch {
+                    term: argument.to_string(),
+                    sort: sort.to_string(),
+                }
+            );
+        }
+    }
+    Ok(terms)
+}
+
+fn parse_sort(sexp: &Sexp) -> Result<Sort, Error> {
+    let unsupported = || UnsupportedSort {
+        sort: sexp.to_string(),
+    };
+    match sexp {
+        Sexp::Atom(name) if name == "Bool" => Ok(Sort::Bool),
+        Sexp::List(list) => match list.as_slice() {
+            [Sexp::Atom(underscore), Sexp::Atom(name), width]
+                if underscore == "_" && name == "BitVec" =>
+            {
+                let width = width.numeral().with_context(unsupported)?;
+                ensure!((1..=MAX_WIDTH).contains(&width), unsupported());
+                Ok(Sort::BitVec(width))
+            }
+            _ => unsupported().fail(),
+        },
+        _ => unsupported().fail(),
+    }
+}
+
+fn expect_sort(term: &Term, sort: Sort, sexp: &Sexp) -> Result<(), Error> {
+    ensure!(
+        term.sort() == sort,
+        SortMismatch {
+            term: sexp.to_string(),
+            sort: sort.to_string(),
+        }
+    );
+    Ok(())
+}
+
+fn check_width(width: usize) -> Result<(), Error> {
+    ensure!(
+        (1..=MAX_WIDTH).contains(&width),
+        UnsupportedSort {
+            sort: Sort::BitVec(width).to_string(),
+        }
+    );
+    Ok(())
+}
+
+/// Parses the optional number of levels of `push` and `pop`.
+fn parse_levels(arguments: &[Sexp]) -> Option<usize> {
+    match arguments {
+        [] => Some(1),
+        [levels] => levels.numeral(),
+        _ => None,
+    }
+}
+
+fn format_value(value: u64, sort: Sort) -> String {
+    match sort {
+        Sort::Bool => (value != 0).to_string(),
+        Sort::BitVec(width) if width % 4 == 0 => format!("#x{:0width$x}", value, width = width / 4),
+        Sort::BitVec(width) => format!("#b{:0width$b}", value, width = width),
+    }
+}
+
+/// Quotes the symbol if it is not a simple symbol.
+fn symbol(name: &str) -> String {
+    let simple = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "~!@$%^&*_-+=<>.?/".contains(c));
+    if simple {
+        name.to_owned()
+    } else {
+        format!("|{}|", name)
+    }
+}
+
+fn escape(error: &Error) -> String {
+    error.to_string().replace('"', "\"\"")
+}
@@ -1,16 +1,126 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
 use crate::formula::{Cnf, Model};
+use crate::prelude::*;
 
+mod backbone;
 mod cdcl;
+mod cube;
 mod dpll;
+mod local_search;
+mod mcs;
+mod optimize;
+mod pool;
+mod portfolio;
+mod sharing;
+mod statistics;
+
+pub use backbone::{backbone, BackboneResult};
+pub use cdcl::{CdclConfig, CdclHeuristic, CdclRestart, CdclSolver};
+pub use cube::{cube, write_icnf, CubeAndConquerSolver, CubeConfig};
+pub use dpll::{DpllHeuristic, DpllSolver};
+pub use local_search::{
+    BreakDistribution, LocalSearchAlgorithm, LocalSearchConfig, LocalSearchSolver,
+};
+pub use mcs::{minimal_hitting_sets, minimum_correction_set, McsEnumerator};
+pub use optimize::{minimize, OptimizeResult};
+pub use portfolio::{PortfolioConfig, PortfolioSolver, PortfolioWorker};
+pub use sharing::{ClauseExchange, ExchangePort, SharingConfig};
+pub use statistics::{Statistics, Timings};
+
+#[cfg(test)]
+pub(crate) use cdcl::luby;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Unknown heuristic '{}'", name))]
+    UnknownHeuristic { name: String },
+    #[snafu(display("Unknown restart policy '{}'", name))]
+    UnknownRestart { name: String },
+}
+
+/// A flag shared between threads to ask a running solver to stop.
+/// An interrupted solver returns `SolveResult::Unknown`.
+#[derive(Debug, Clone, Default)]
+pub struct Interrupt {
+    flag: Arc<AtomicBool>,
+}
+
+impl Interrupt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn interrupt(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+}
+
+/// The result of solving a formula.
+#[derive(Debug)]
+pub enum SolveResult {
+    /// The formula is satisfiable.
+    Sat(Model),
+    /// The formula is unsatisfiable.
+    Unsat,
+    /// The solver gave up without finding an answer.
+    Unknown,
+}
+
+impl SolveResult {
+    pub fn is_sat(&self) -> bool {
+        matches!(self, SolveResult::Sat(_))
+    }
+
+    pub fn is_unsat(&self) -> bool {
+        matches!(self, SolveResult::Unsat)
+    }
 
-pub use cdcl::CdclSolver;
-pub use dpll::DpllSolver;
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, SolveResult::Unknown)
+    }
+
+    /// Returns the model if satisfiable.
+    pub fn model(&self) -> Option<&Model> {
+        match self {
+            SolveResult::Sat(model) => Some(model),
+            _ => None,
+        }
+    }
+
+    /// Converts the result into the model if satisfiable.
+    pub fn into_model(self) -> Option<Model> {
+        match self {
+            SolveResult::Sat(model) => Some(model),
+            _ => None,
+        }
+    }
+}
 
 pub trait Solver {
     /// Creates a new solver instance.
     fn new(formula: Cnf) -> Self;
 
+    /// Makes the solver stop when the interrupt is triggered.
+    /// Solvers that cannot be interrupted ignore it and run to completion.
+    fn set_interrupt(&mut self, _interrupt: Interrupt) {}
+
     /// Solves a CNF SAT problem with the solver.
-    /// Returns `Some(Model)` if satisfiable, `None` otherwise.
-    fn solve(self) -> Option<Model>;
+    fn solve(self) -> SolveResult;
+
+    /// Solves the problem and returns the statistics of the search,
+    /// which are empty for solvers that collect none.
+    fn solve_with_statistics(self) -> (SolveResult, Statistics)
+    where
+        Self: Sized,
+    {
+        (self.solve(), Statistics::default())
+    }
 }
@@ -0,0 +1,84 @@
+/*!
+Backbone computation.
+
+The backbone of a satisfiable formula is the set of literals true in every model.
+Starting from the literals of a model as candidates, a single CDCL solver is asked
+whether a chunk of candidates can be flipped together under assumptions.
+Every model found removes the candidates it falsifies, and when the chunk cannot be flipped,
+the failed assumptions tell which candidates are responsible: a single one is a backbone
+literal, and more than one means the chunk is halved until candidates are checked one by one.
+Backbone literals found so far are assumed in later calls.
+*/
+
+use crate::formula::{Cnf, Literal, Variable};
+
+use super::{CdclConfig, CdclSolver, SolveResult};
+
+/// The number of candidates flipped together at first.
+const CHUNK_SIZE: usize = 16;
+
+/// The result of computing a backbone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackboneResult {
+    /// The literals true in every model, ordered by variable.
+    Backbone(Vec<Literal>),
+    /// The formula is unsatisfiable.
+    Unsat,
+    /// The solver gave up.
+    Unknown,
+}
+
+/// Computes the backbone of the formula.
+pub fn backbone(formula: Cnf, config: CdclConfig) -> BackboneResult {
+    let num_variables = formula.num_variables();
+    let mut solver = CdclSolver::with_config(formula, config);
+    let mut candidates: Vec<Literal> = match solver.solve_with_assumptions(&[]) {
+        SolveResult::Sat(model) => (0..num_variables)
+            .map(|index| {
+                let variable = Variable::from_index(index).unwrap();
+                Literal::new(variable, variable.value(model.assignment()))
+            })
+            .collect(),
+        SolveResult::Unsat => return BackboneResult::Unsat,
+        SolveResult::Unknown => return BackboneResult::Unknown,
+    };
+
+    let mut backbone = Vec::new();
+    let mut chunk_size = CHUNK_SIZE;
+    while !candidates.is_empty() {
+        let chunk = chunk_size.min(candidates.len());
+        let mut assumptions = backbone.clone();
+        assumptions.extend(candidates[..chunk].iter().map(|&literal| !literal));
+
+        match solver.solve_with_assumptions(&assumptions) {
+            SolveResult::Sat(model) => {
+                candidates.retain(|literal| literal.value(model.assignment()));
+                chunk_size = (chunk_size * 2).min(CHUNK_SIZE);
+            }
+            SolveResult::Unsat => {
+                let failed: Vec<Literal> = candidates[..chunk]
+                    .iter()
+                    .copied()
+                    .filter(|&literal| solver.failed_assumptions().contains(&!literal))
+                    .collect();
+                debug!(
+                    "{} of {} candidates failed to flip",
+                    failed.len(),
+                    candidates.len()
+                );
+                match failed[..] {
+                    [literal] => {
+                        backbone.push(literal);
+                        candidates.retain(|&candidate| candidate != literal);
+                    }
+                    [] => unreachable!("The backbone found so far is satisfiable"),
+                    _ => chunk_size = (chunk_size / 2).max(1),
+                }
+            }
+            SolveResult::Unknown => return BackboneResult::Unknown,
+        }
+    }
+
+    backbone.sort();
+    BackboneResult::Backbone(backbone)
+}
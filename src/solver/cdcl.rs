@@ -1,37 +1,155 @@
-use crate::formula::{Clause, Cnf, Literal, Model, Variable};
+use std::{cell::OnceCell, time::Instant};
+
+use crate::encode::PbEncoding;
+use crate::formula::{Clause, Cnf, Literal, Model, PbConstraint, Variable};
 
 use self::{
     conflict::{ConflictAnalyzer, ConflictDataProvider},
+    gauss::{GaussElimination, GaussResult},
+    heuristic::BranchingHeuristic,
+    mode::{Mode, ModeScheduler},
+    pb::{PbPropagator, PbResult},
+    phase::Phases,
+    restart::RestartPolicy,
     tracker::{ClauseIdx, Tracker},
-    vsids::VsidsScoring,
+    vivify::Vivifier,
+    walk::Walker,
 };
 
-use super::Solver;
+use super::{sharing::ExchangePort, Interrupt, SolveResult, Solver, Statistics};
+
+pub use self::{heuristic::CdclHeuristic, restart::CdclRestart};
+
+#[cfg(test)]
+pub(crate) use self::restart::luby;
 
 mod conflict;
+mod gauss;
+mod heuristic;
+mod mode;
+mod pb;
+mod phase;
+mod restart;
 mod tracker;
-mod vsids;
+mod vivify;
+mod walk;
+
+/// Tunable options of the CDCL solver.
+#[derive(Debug, Clone)]
+pub struct CdclConfig {
+    /// Decision heuristic
+    pub heuristic: CdclHeuristic,
+    /// Restart policy
+    pub restart: CdclRestart,
+    /// Whether to run local search on rephasing to find target phases.
+    /// It is skipped for formulas with parity, cardinality or pseudo-Boolean constraints,
+    /// and in focused mode if modes switch.
+    pub local_search: bool,
+    /// Seed of the local search and of the tie-breaking between variables of equal scores
+    pub seed: u64,
+    /// Whether to detect parity constraints encoded in clauses.
+    /// Parity constraints given as XOR clauses are always used.
+    pub xor_detection: bool,
+    /// Encodes pseudo-Boolean constraints into clauses if set,
+    /// instead of propagating them natively
+    pub pb_encoding: Option<PbEncoding>,
+    /// Backtracks only one level after a conflict if the backjump would undo
+    /// more levels than this, keeping the rest of the trail
+    pub chrono_threshold: usize,
+    /// Whether to alternate between a focused mode (VMTF, frequent restarts)
+    /// and a stable mode (VSIDS, rare restarts, target phases)
+    /// instead of using `heuristic` and `restart` throughout
+    pub mode_switching: bool,
+    /// Whether to time propagation and conflict analysis in the statistics,
+    /// which reads the clock around each of them.
+    /// `solve_with_statistics` always does.
+    pub phase_timings: bool,
+}
+
+impl Default for CdclConfig {
+    fn default() -> Self {
+        CdclConfig {
+            heuristic: CdclHeuristic::default(),
+            restart: CdclRestart::default(),
+            local_search: true,
+            seed: 0,
+            xor_detection: true,
+            pb_encoding: None,
+            chrono_threshold: 100,
+            mode_switching: false,
+            phase_timings: false,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 enum DecisionReason {
     Decision,
     UnitPropagation(ClauseIdx),
+    /// Implied by parity constraints, explained by `explanations`
+    XorPropagation,
+    /// Implied by the pseudo-Boolean constraint of the index
+    PbPropagation(usize),
+}
+
+/// A constraint falsified by the current assignment.
+enum Conflict {
+    Clause(ClauseIdx),
+    /// A clause implied by parity or pseudo-Boolean constraints
+    Implied(Clause),
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct Decision {
     decision_level: usize,
+    /// The index in the decision stack
+    position: usize,
     reason: DecisionReason,
 }
 
 struct CdclDataProvider<'solver> {
     tracker: &'solver Tracker,
     decisions: &'solver Vec<Option<Decision>>,
+    explanations: &'solver Vec<OnceCell<Clause>>,
+    pb: Option<&'solver PbPropagator>,
 }
 
 impl<'solver> CdclDataProvider<'solver> {
-    fn new(tracker: &'solver Tracker, decisions: &'solver Vec<Option<Decision>>) -> Self {
-        CdclDataProvider { tracker, decisions }
+    fn new(
+        tracker: &'solver Tracker,
+        decisions: &'solver Vec<Option<Decision>>,
+        explanations: &'solver Vec<OnceCell<Clause>>,
+        pb: Option<&'solver PbPropagator>,
+    ) -> Self {
+        CdclDataProvider {
+            tracker,
+            decisions,
+            explanations,
+            pb,
+        }
+    }
+
+    /// Builds the reason of a literal implied by a pseudo-Boolean constraint:
+    /// the literal or any literal of the constraint that was false before it.
+    fn explain_pb(&self, variable: Variable, constraint: usize) -> Clause {
+        let decision = self.decision_for_variable(variable);
+        let value = self.value(variable);
+        let constraint = self.pb.unwrap().constraint(constraint);
+
+        let mut literals = vec![Literal::new(variable, value)];
+        for &(_, literal) in constraint.terms() {
+            if literal.variable() == variable {
+                continue;
+            }
+            if let Some(other) = &self.decisions[literal.index()] {
+                if other.position < decision.position
+                    && literal.partial_value(self.tracker.assignments()) == Some(false)
+                {
+                    literals.push(literal);
+                }
+            }
+        }
+        Clause::new(literals)
     }
 
     fn decision_for_variable(&self, variable: Variable) -> &Decision {
@@ -51,10 +169,16 @@ impl<'solver> ConflictDataProvider for CdclDataProvider<'solver> {
     fn antecedents(&self, variable: Variable) -> Option<&Clause> {
         let decision = self.decision_for_variable(variable);
 
-        if let DecisionReason::UnitPropagation(clause_index) = &decision.reason {
-            Some(self.tracker.original_clause(*clause_index))
-        } else {
-            None
+        match &decision.reason {
+            DecisionReason::Decision => None,
+            DecisionReason::UnitPropagation(clause_index) => {
+                Some(self.tracker.original_clause(*clause_index))
+            }
+            DecisionReason::XorPropagation => self.explanations[variable.index()].get(),
+            DecisionReason::PbPropagation(constraint) => Some(
+                self.explanations[variable.index()]
+                    .get_or_init(|| self.explain_pb(variable, *constraint)),
+            ),
         }
     }
 }
@@ -74,7 +198,38 @@ pub struct CdclSolver {
     /// A data structure to efficiently track each clause's status.
     tracker: Tracker,
     /// Score tracker
-    score_heuristic: VsidsScoring,
+    score_heuristic: Box<dyn BranchingHeuristic>,
+    /// Restart schedule
+    restart_policy: Box<dyn RestartPolicy>,
+    /// The heuristic and restart policy of the inactive mode, if modes switch
+    modes: Option<ModeScheduler>,
+    /// Clause vivification state
+    vivifier: Vivifier,
+    /// Saved and target phases
+    phases: Phases,
+    /// Local search state, if enabled
+    walker: Option<Walker>,
+    /// Counters of the search so far
+    stats: Statistics,
+    /// Stops the search when triggered
+    interrupt: Interrupt,
+    /// Connection to peers for clause sharing
+    exchange: Option<ExchangePort>,
+    /// Parity constraints, if any
+    gauss: Option<GaussElimination>,
+    /// Cardinality and pseudo-Boolean constraints, if any
+    pb: Option<PbPropagator>,
+    /// Explanations of variables implied by parity or pseudo-Boolean constraints.
+    /// Those of pseudo-Boolean constraints are built on demand.
+    explanations: Vec<OnceCell<Clause>>,
+    /// Assumptions of the last call that are unsatisfiable with the formula
+    failed_assumptions: Vec<Literal>,
+    /// Whether a conflict at the root level proved the formula unsatisfiable
+    unsat: bool,
+    /// Backjumps longer than this backtrack chronologically
+    chrono_threshold: usize,
+    /// Whether propagation and conflict analysis are timed
+    phase_timings: bool,
 }
 
 impl CdclSolver {
@@ -83,39 +238,460 @@ impl CdclSolver {
     }
 
     fn push_decision(&mut self, literal: Literal, reason: DecisionReason) {
-        trace!("Set {}, {:?}", literal, reason);
-        if let DecisionReason::Decision = reason {
-            self.frame.push(self.decision_stack.len())
+        match reason {
+            DecisionReason::Decision => self.frame.push(self.decision_stack.len()),
+            _ => self.stats.propagations += 1,
         }
-        self.decision_stack.push(literal);
+        let level = self.implication_level(literal, reason);
+        self.assign(literal, level, reason);
+    }
+
+    /// Returns the decision level of a literal assigned for the reason.
+    /// An implied literal takes the highest level among the other literals of its reason,
+    /// which is lower than the current level if they are out of order on the trail.
+    fn implication_level(&self, literal: Literal, reason: DecisionReason) -> usize {
+        let level_of = |other: Literal| self.decisions[other.index()].unwrap().decision_level;
+        let variable = literal.variable();
+        match reason {
+            DecisionReason::Decision => self.current_level(),
+            DecisionReason::UnitPropagation(clause_index) => self
+                .tracker
+                .original_clause(clause_index)
+                .iter()
+                .filter(|other| other.variable() != variable)
+                .map(level_of)
+                .max()
+                .unwrap_or(0),
+            DecisionReason::XorPropagation => self.explanations[literal.index()]
+                .get()
+                .unwrap()
+                .iter()
+                .filter(|other| other.variable() != variable)
+                .map(level_of)
+                .max()
+                .unwrap_or(0),
+            DecisionReason::PbPropagation(index) => {
+                let assignments = self.tracker.assignments();
+                self.pb
+                    .as_ref()
+                    .unwrap()
+                    .constraint(index)
+                    .terms()
+                    .iter()
+                    .map(|&(_, other)| other)
+                    .filter(|other| other.partial_value(assignments) == Some(false))
+                    .map(level_of)
+                    .max()
+                    .unwrap_or(0)
+            }
+        }
+    }
+
+    fn assign(&mut self, literal: Literal, decision_level: usize, reason: DecisionReason) {
+        trace!("Set {} on level {}, {:?}", literal, decision_level, reason);
         self.decisions[literal.index()] = Some(Decision {
-            decision_level: self.current_level(),
+            decision_level,
+            position: self.decision_stack.len(),
             reason,
         });
+        self.decision_stack.push(literal);
         self.tracker.set_literal(literal);
-        self.score_heuristic.remove(literal.variable());
+        self.score_heuristic.assign(literal.variable());
+    }
+
+    /// Makes a decision of the search, unlike those of vivification.
+    fn decide(&mut self, literal: Literal) {
+        self.push_decision(literal, DecisionReason::Decision);
+        self.stats.decisions += 1;
+        self.stats.max_decision_level = self.stats.max_decision_level.max(self.current_level());
     }
 
     fn pop_decision(&mut self) -> Option<(Literal, Decision)> {
         self.decision_stack.pop().map(|literal| {
             trace!("Unset {}", literal);
-            self.score_heuristic.insert(literal.variable());
+            self.score_heuristic.unassign(literal.variable());
+            self.phases.save(literal);
             self.tracker.unset(literal.variable());
             let decision = self.decisions[literal.index()].take().unwrap();
-            if let DecisionReason::Decision = decision.reason {
-                self.frame.pop();
+            match decision.reason {
+                DecisionReason::Decision => {
+                    self.frame.pop();
+                }
+                DecisionReason::UnitPropagation(_) => (),
+                DecisionReason::XorPropagation | DecisionReason::PbPropagation(_) => {
+                    self.explanations[literal.index()].take();
+                }
+            }
+            if let Some(gauss) = &mut self.gauss {
+                gauss.backtrack(decision.position);
+            }
+            if let Some(pb) = &mut self.pb {
+                pb.unassign(decision.position, literal);
             }
             (literal, decision)
         })
     }
+
+    /// Performs unit propagation, then propagation of parity and pseudo-Boolean constraints,
+    /// until none of them implies anything new.
+    /// Returns the falsified constraint if a conflict is found.
+    fn propagate(&mut self) -> Option<Conflict> {
+        loop {
+            if let Some(&conflict_clause_index) = self.tracker.falsified_clauses().iter().next() {
+                return Some(Conflict::Clause(conflict_clause_index));
+            }
+
+            if let Some(&clause_idx) = self.tracker.unit_clauses().iter().next() {
+                let literal = self.tracker.get_unit_clause_literal(clause_idx);
+                self.push_decision(literal, DecisionReason::UnitPropagation(clause_idx));
+                continue;
+            }
+
+            match self.propagate_xors() {
+                Err(conflict) => return Some(conflict),
+                Ok(true) => continue,
+                Ok(false) => (),
+            }
+
+            match self.propagate_pbs() {
+                Err(conflict) => return Some(conflict),
+                Ok(true) => continue,
+                Ok(false) => return None,
+            }
+        }
+    }
+
+    /// Runs Gauss-Jordan elimination over the new assignments.
+    /// Returns whether any literal was assigned.
+    fn propagate_xors(&mut self) -> Result<bool, Conflict> {
+        let gauss = match &mut self.gauss {
+            Some(gauss) => gauss,
+            None => return Ok(false),
+        };
+        let propagations = match gauss.propagate(&self.decision_stack, self.tracker.assignments()) {
+            GaussResult::Conflict(clause) => return Err(Conflict::Implied(clause)),
+            GaussResult::Propagate(propagations) => propagations,
+        };
+
+        let mut assigned = false;
+        for (literal, reason) in propagations {
+            match literal.partial_value(self.tracker.assignments()) {
+                Some(true) => (),
+                Some(false) => return Err(Conflict::Implied(reason)),
+                None => {
+                    self.explanations[literal.index()] = OnceCell::from(reason);
+                    self.push_decision(literal, DecisionReason::XorPropagation);
+                    assigned = true;
+                }
+            }
+        }
+        Ok(assigned)
+    }
+
+    /// Updates the slacks of pseudo-Boolean constraints with the new assignments.
+    /// Returns whether any literal was assigned.
+    fn propagate_pbs(&mut self) -> Result<bool, Conflict> {
+        let pb = match &mut self.pb {
+            Some(pb) => pb,
+            None => return Ok(false),
+        };
+        let propagations = match pb.propagate(&self.decision_stack, self.tracker.assignments()) {
+            PbResult::Conflict(index) => {
+                return Err(self.pb_conflict(index));
+            }
+            PbResult::Propagate(propagations) => propagations,
+        };
+
+        let mut assigned = false;
+        for (literal, index) in propagations {
+            match literal.partial_value(self.tracker.assignments()) {
+                Some(true) => (),
+                Some(false) => return Err(self.pb_conflict(index)),
+                None => {
+                    self.push_decision(literal, DecisionReason::PbPropagation(index));
+                    assigned = true;
+                }
+            }
+        }
+        Ok(assigned)
+    }
+
+    /// Returns the clause of the false literals of a falsified pseudo-Boolean constraint.
+    fn pb_conflict(&self, index: usize) -> Conflict {
+        let constraint = self.pb.as_ref().unwrap().constraint(index);
+        let assignments = self.tracker.assignments();
+        Conflict::Implied(Clause::new(
+            constraint
+                .terms()
+                .iter()
+                .map(|&(_, literal)| literal)
+                .filter(|literal| literal.partial_value(assignments) == Some(false))
+                .collect(),
+        ))
+    }
+
+    fn conflicting_clause<'a>(&'a self, conflict: &'a Conflict) -> &'a Clause {
+        match conflict {
+            Conflict::Clause(clause_index) => self.tracker.original_clause(*clause_index),
+            Conflict::Implied(clause) => clause,
+        }
+    }
+
+    /// Returns the highest decision level among the literals of the conflict.
+    fn conflict_level(&self, conflict: &Conflict) -> usize {
+        self.conflicting_clause(conflict)
+            .iter()
+            .map(|literal| self.decisions[literal.index()].unwrap().decision_level)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Learns a conflict clause from the falsified constraint and rewinds the decisions.
+    fn learn_from_conflict(&mut self, conflict: Conflict) {
+        // Analysis requires a literal on the current level
+        let conflict_level = self.conflict_level(&conflict);
+        self.rewind(conflict_level);
+        let current_level = self.current_level();
+
+        if self
+            .modes
+            .as_ref()
+            .is_some_and(|modes| modes.mode() == Mode::Stable)
+        {
+            // The levels below the conflict are consistent
+            let consistent = self.frame[current_level - 1];
+            self.phases
+                .update_target(&self.decision_stack[..consistent]);
+        }
+
+        let on_conflict_level = self
+            .conflicting_clause(&conflict)
+            .iter()
+            .filter(|literal| {
+                self.decisions[literal.index()].unwrap().decision_level == conflict_level
+            })
+            .count();
+        if on_conflict_level == 1 {
+            // The clause became unit on a lower level than its last literal was assigned,
+            // so it propagates after backtracking one level.
+            // Implied clauses are added, since their constraints may not be examined again.
+            if let Conflict::Implied(clause) = conflict {
+                self.tracker.add_clause(clause, true);
+            }
+            self.rewind(conflict_level - 1);
+            return;
+        }
+
+        let data_provider = CdclDataProvider::new(
+            &self.tracker,
+            &self.decisions,
+            &self.explanations,
+            self.pb.as_ref(),
+        );
+        let conflicting_clause = match &conflict {
+            Conflict::Clause(clause_index) => self.tracker.original_clause(*clause_index),
+            Conflict::Implied(clause) => clause,
+        };
+        trace!("Conflict {}", conflicting_clause);
+
+        let clause_to_learn = self.conflict_analyzer.analyze(
+            &data_provider,
+            current_level,
+            conflicting_clause,
+            &self.decision_stack[*self.frame.last().unwrap()..],
+        );
+        trace!("Learn {}", clause_to_learn);
+
+        let second_max = clause_to_learn
+            .iter()
+            .map(|literal| self.decisions[literal.index()].unwrap().decision_level)
+            .filter(|&level| level < current_level)
+            .max();
+
+        let asserting_level = match second_max {
+            None => {
+                debug_assert_eq!(clause_to_learn.len(), 1);
+                0
+            }
+            Some(val) => val,
+        };
+        // A long backjump would undo assignments that are likely to be made again
+        let rewind_until = if current_level - asserting_level > self.chrono_threshold {
+            current_level - 1
+        } else {
+            asserting_level
+        };
+        self.score_heuristic
+            .conflict(self.conflict_analyzer.participants(), &clause_to_learn);
+
+        let mut levels: Vec<_> = clause_to_learn
+            .iter()
+            .map(|literal| self.decisions[literal.index()].unwrap().decision_level)
+            .collect();
+        levels.sort_unstable();
+        levels.dedup();
+        self.stats.learned(clause_to_learn.len(), levels.len());
+        // Peers may number auxiliary variables of encodings differently, or lack them
+        let num_variables = self.formula.num_variables();
+        if let Some(exchange) = &mut self.exchange {
+            if clause_to_learn
+                .iter()
+                .all(|literal| literal.index() < num_variables)
+            {
+                exchange.export(&clause_to_learn, levels.len());
+            }
+        }
+
+        self.tracker.add_clause(clause_to_learn, true);
+
+        trace!("rewind_until {}", rewind_until);
+        self.rewind(rewind_until);
+    }
+
+    /// Collects the assumptions implying the negation of the false assumption.
+    /// Every decision is an assumption at this point, since they are decided first.
+    fn analyze_final(&self, assumption: Literal) -> Vec<Literal> {
+        let data_provider = CdclDataProvider::new(
+            &self.tracker,
+            &self.decisions,
+            &self.explanations,
+            self.pb.as_ref(),
+        );
+
+        let mut failed = vec![assumption];
+        let mut seen = vec![false; self.decisions.len()];
+        let mut queue = vec![assumption.variable()];
+        seen[assumption.index()] = true;
+        while let Some(variable) = queue.pop() {
+            if data_provider.level(variable) == 0 {
+                continue;
+            }
+            match data_provider.antecedents(variable) {
+                None => failed.push(Literal::new(variable, data_provider.value(variable))),
+                Some(clause) => {
+                    for literal in clause.iter() {
+                        if !seen[literal.index()] {
+                            seen[literal.index()] = true;
+                            queue.push(literal.variable());
+                        }
+                    }
+                }
+            }
+        }
+        failed
+    }
+
+    /// Pops decisions until the current level becomes `level`.
+    /// Literals on the popped part of the trail with levels up to `level`,
+    /// left there by chronological backtracking, are assigned again in the same order.
+    fn rewind(&mut self, level: usize) {
+        if self.current_level() <= level {
+            return;
+        }
+
+        let start = self.frame[level];
+        let mut kept = Vec::new();
+        while self.decision_stack.len() > start {
+            let literal = *self.decision_stack.last().unwrap();
+            let explanation = self.explanations[literal.index()].take();
+            let (literal, decision) = self.pop_decision().unwrap();
+            if decision.decision_level <= level {
+                kept.push((literal, decision, explanation));
+            }
+        }
+
+        for (literal, decision, explanation) in kept.into_iter().rev() {
+            if let Some(explanation) = explanation {
+                self.explanations[literal.index()] = OnceCell::from(explanation);
+            }
+            self.assign(literal, decision.decision_level, decision.reason);
+        }
+    }
+
+    /// Rewinds all decisions and runs inprocessing at the root level.
+    fn restart(&mut self) {
+        debug!("Restart");
+        self.stats.restarts += 1;
+        self.restart_policy.restarted();
+        self.rewind(0);
+        if self
+            .modes
+            .as_ref()
+            .is_some_and(|modes| modes.should_switch(self.stats.conflicts))
+        {
+            self.switch_mode();
+        }
+        self.import_shared_clauses();
+        let start = Instant::now();
+        self.vivify();
+        self.stats.timings.vivification += start.elapsed();
+
+        // Target phases found by a walk are useless while decisions ignore them
+        if self.walker.is_some()
+            && self.phases.is_target_used()
+            && self.phases.should_rephase(self.stats.conflicts)
+        {
+            self.phases.rephased(self.stats.conflicts);
+            let start = Instant::now();
+            self.walk();
+            self.stats.timings.local_search += start.elapsed();
+        }
+    }
+
+    /// Exchanges learned clauses with peers and adds the received ones.
+    fn import_shared_clauses(&mut self) {
+        if let Some(exchange) = &mut self.exchange {
+            let clauses = exchange.exchange();
+            trace!("Import {} clauses", clauses.len());
+            for clause in clauses {
+                self.tracker.add_clause(clause, true);
+            }
+        }
+    }
 }
 
-impl Solver for CdclSolver {
-    fn new(formula: Cnf) -> Self {
-        let tracker = Tracker::from_cnf(&formula);
-        let score_heuristic = VsidsScoring::new(&tracker);
+impl CdclSolver {
+    /// Creates a new solver instance with the given options.
+    pub fn with_config(formula: Cnf, config: CdclConfig) -> Self {
+        let encoded = config
+            .pb_encoding
+            .map(|encoding| formula.encode_pbs(encoding));
+        let target = encoded.as_ref().unwrap_or(&formula);
+
+        let tracker = Tracker::from_cnf(target);
+        let mut phases = Phases::new(target.num_variables());
+        let (modes, score_heuristic, restart_policy) = if config.mode_switching {
+            let (modes, heuristic, restart_policy) = ModeScheduler::new(&tracker, config.seed);
+            // Target phases are only for the stable mode
+            phases.use_target(false);
+            (Some(modes), heuristic, restart_policy)
+        } else {
+            (
+                None,
+                config.heuristic.build(&tracker, config.seed),
+                config.restart.build(),
+            )
+        };
+
+        let num_variables = target.num_variables();
+        // Local search only sees clauses
+        let clauses_only = target.xors().is_empty()
+            && target.cardinalities().is_empty()
+            && target.pbs().is_empty();
+        let mut xors = target.xors().to_vec();
+        if config.xor_detection {
+            xors.extend(gauss::detect_xors(target.clauses()));
+        }
+        let gauss =
+            Some(GaussElimination::new(num_variables, &xors)).filter(|gauss| !gauss.is_empty());
+        let pbs: Vec<PbConstraint> = target
+            .cardinalities()
+            .iter()
+            .map(PbConstraint::from)
+            .chain(target.pbs().iter().cloned())
+            .collect();
+        let pb = Some(PbPropagator::new(num_variables, pbs)).filter(|pb| !pb.is_empty());
 
-        let num_variables = formula.num_variables();
         CdclSolver {
             formula,
             conflict_analyzer: ConflictAnalyzer::new(num_variables),
@@ -124,71 +700,123 @@ impl Solver for CdclSolver {
             frame: Vec::new(),
             tracker,
             score_heuristic,
+            restart_policy,
+            modes,
+            vivifier: Vivifier::new(),
+            phases,
+            walker: (config.local_search && clauses_only).then(|| Walker::new(config.seed)),
+            stats: Statistics::default(),
+            interrupt: Interrupt::new(),
+            exchange: None,
+            gauss,
+            pb,
+            explanations: vec![OnceCell::new(); num_variables],
+            failed_assumptions: Vec::new(),
+            unsat: false,
+            chrono_threshold: config.chrono_threshold,
+            phase_timings: config.phase_timings,
         }
     }
 
-    fn solve(mut self) -> Option<Model> {
-        while self.tracker.satisfied_clauses().len() != self.tracker.num_clauses() {
-            // Learn conflict clause from the first falsified clause
-            if let Some(conflict_clause_index) = self.tracker.falsified_clauses().iter().next() {
-                let current_level = self.current_level();
+    /// Solves the formula with the assumption literals forced to be true.
+    /// Learned clauses are kept, so the solver can be called again with other assumptions.
+    /// `Unsat` means that the formula conjoined with the assumptions is unsatisfiable.
+    pub fn solve_with_assumptions(&mut self, assumptions: &[Literal]) -> SolveResult {
+        let start = Instant::now();
+        let result = self.search(assumptions);
+        self.stats.timings.total += start.elapsed();
+        self.stats.memory = self.tracker.memory_usage();
+        result
+    }
+
+    /// Returns the counters of all calls so far.
+    pub fn statistics(&self) -> &Statistics {
+        &self.stats
+    }
+
+    fn search(&mut self, assumptions: &[Literal]) -> SolveResult {
+        self.rewind(0);
+        self.failed_assumptions.clear();
+        // Adding clauses cannot make the formula satisfiable again
+        if self.unsat {
+            return SolveResult::Unsat;
+        }
 
+        loop {
+            if self.interrupt.is_interrupted() {
+                return SolveResult::Unknown;
+            }
+
+            let start = self.phase_timings.then(Instant::now);
+            let conflict = self.propagate();
+            if let Some(start) = start {
+                self.stats.timings.propagation += start.elapsed();
+            }
+            if let Some(conflict) = conflict {
                 // Panic at root means UNSAT
-                if current_level == 0 {
-                    return None;
+                if self.conflict_level(&conflict) == 0 {
+                    self.unsat = true;
+                    return SolveResult::Unsat;
                 }
 
-                let data_provider = CdclDataProvider::new(&self.tracker, &self.decisions);
-                let conflicting_clause = self.tracker.original_clause(*conflict_clause_index);
-                trace!("Conflict {}", conflicting_clause);
-
-                let clause_to_learn = self.conflict_analyzer.analyze(
-                    &data_provider,
-                    current_level,
-                    conflicting_clause,
-                    &self.decision_stack[*self.frame.last().unwrap()..],
-                );
-                trace!("Learn {}", clause_to_learn);
+                let start = self.phase_timings.then(Instant::now);
+                self.learn_from_conflict(conflict);
+                if let Some(start) = start {
+                    self.stats.timings.conflict_analysis += start.elapsed();
+                }
+                self.restart_policy.on_conflict();
+                self.stats.conflicts += 1;
+                continue;
+            }
 
-                let second_max = clause_to_learn
-                    .iter()
-                    .map(|literal| self.decisions[literal.index()].unwrap().decision_level)
-                    .filter(|&level| level < current_level)
-                    .max();
+            if self.restart_policy.should_restart()
+                || self
+                    .modes
+                    .as_ref()
+                    .is_some_and(|modes| modes.should_switch(self.stats.conflicts))
+            {
+                self.restart();
+                continue;
+            }
 
-                let rewind_until = match second_max {
-                    None => {
-                        debug_assert_eq!(clause_to_learn.len(), 1);
-                        0
+            // Decide the first unassigned assumption
+            let mut next_assumption = None;
+            for &assumption in assumptions {
+                match assumption.partial_value(self.tracker.assignments()) {
+                    Some(true) => (),
+                    Some(false) => {
+                        self.failed_assumptions = self.analyze_final(assumption);
+                        self.rewind(0);
+                        return SolveResult::Unsat;
                     }
-                    Some(val) => {
-                        self.score_heuristic.learn_clause(&clause_to_learn);
-                        val
+                    None => {
+                        next_assumption = Some(assumption);
+                        break;
                     }
-                };
-                self.score_heuristic.decay();
-
-                self.tracker.add_clause(clause_to_learn);
-
-                trace!("rewind_until {}", rewind_until);
-                while self.current_level() > rewind_until {
-                    self.pop_decision();
                 }
-
+            }
+            if let Some(assumption) = next_assumption {
+                self.decide(assumption);
                 continue;
             }
 
-            let unit = self.tracker.unit_clauses();
-            if let Some(clause_idx) = unit.iter().next().copied() {
-                // Perform unit propagation
-                let literal = self.tracker.get_unit_clause_literal(clause_idx);
-                self.push_decision(literal, DecisionReason::UnitPropagation(clause_idx));
-            } else {
-                // Make a new decision based on VSIDS
-                let variable = self.score_heuristic.top();
-                let literal = Literal::new(variable, true);
-                self.push_decision(literal, DecisionReason::Decision);
+            if self.tracker.satisfied_clauses().len() == self.tracker.num_clauses()
+                && self
+                    .gauss
+                    .as_ref()
+                    .is_none_or(|gauss| gauss.is_assigned(self.tracker.assignments()))
+                && self
+                    .pb
+                    .as_ref()
+                    .is_none_or(|pb| pb.is_satisfied(self.tracker.assignments()))
+            {
+                break;
             }
+
+            // Make a new decision based on the heuristic
+            let variable = self.score_heuristic.decide();
+            let literal = self.phases.decide(variable);
+            self.decide(literal);
         }
 
         // All clauses are satisfied, fill remaining variables and return.
@@ -199,6 +827,83 @@ impl Solver for CdclSolver {
             .map(|assign| assign.unwrap_or(true))
             .collect::<Vec<_>>();
 
-        return Some(Model::new(self.formula, assignment));
+        self.rewind(0);
+        SolveResult::Sat(Model::from_extended(self.formula.clone(), &assignment))
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.formula.num_variables()
+    }
+
+    /// Adds a fresh variable numbered after the existing variables of the formula.
+    ///
+    /// # Panics
+    ///
+    /// Panics when pseudo-Boolean constraints are encoded into clauses
+    /// with auxiliary variables, which take the numbers after those of the formula.
+    pub fn new_variable(&mut self) -> Variable {
+        assert_eq!(self.tracker.num_variables(), self.formula.num_variables());
+
+        let variable = self.formula.new_variable();
+        self.tracker.add_variable();
+        self.conflict_analyzer.add_variable();
+        self.decisions.push(None);
+        self.explanations.push(OnceCell::new());
+        self.phases.add_variable();
+        self.score_heuristic.add_variable(variable);
+        if let Some(modes) = &mut self.modes {
+            modes.add_variable(variable);
+        }
+        if let Some(gauss) = &mut self.gauss {
+            gauss.add_variable();
+        }
+        if let Some(pb) = &mut self.pb {
+            pb.add_variable();
+        }
+        variable
+    }
+
+    /// Adds a clause to the formula, keeping the learned clauses,
+    /// so that the solver can be called again on the strengthened formula.
+    /// Parity constraints are not detected among added clauses.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `clause` contains invalid literals.
+    pub fn add_clause(&mut self, clause: Clause) {
+        self.rewind(0);
+        self.formula.add_clause(clause.clone());
+        self.tracker.add_clause(clause, false);
+    }
+
+    /// Returns a subset of the assumptions of the last call that is unsatisfiable
+    /// with the formula, if it returned `Unsat`. It is empty if the formula itself is.
+    pub fn failed_assumptions(&self) -> &[Literal] {
+        &self.failed_assumptions
+    }
+
+    /// Shares learned clauses with other solvers connected to the same exchange.
+    pub fn set_exchange(&mut self, exchange: ExchangePort) {
+        self.exchange = Some(exchange);
+    }
+}
+
+impl Solver for CdclSolver {
+    fn new(formula: Cnf) -> Self {
+        CdclSolver::with_config(formula, CdclConfig::default())
+    }
+
+    fn set_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt = interrupt;
+    }
+
+    fn solve(mut self) -> SolveResult {
+        self.solve_with_assumptions(&[])
+    }
+
+    fn solve_with_statistics(mut self) -> (SolveResult, Statistics) {
+        self.phase_timings = true;
+        let result = self.solve_with_assumptions(&[]);
+        (result, self.stats)
     }
 }
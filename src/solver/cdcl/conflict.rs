@@ -16,6 +16,7 @@ pub struct ConflictAnalyzer {
     /// Bitmap to check if each variable is previously seen.
     seen: Vec<bool>,
     /// A queue that records seen variables.
+    /// Kept until the next analysis so that heuristics can inspect them.
     seen_queue: Vec<Variable>,
 }
 
@@ -78,11 +79,20 @@ impl ConflictAnalyzer {
         }
     }
 
+    /// Makes room for a variable numbered after the existing variables.
+    pub fn add_variable(&mut self) {
+        self.seen.push(false);
+    }
+
     fn clear(&mut self) {
         for &var in &self.seen_queue {
             self.seen[var.index()] = false;
         }
-        self.seen_queue.clear();
+    }
+
+    /// Returns the variables seen during the last analysis.
+    pub fn participants(&self) -> &[Variable] {
+        &self.seen_queue
     }
 
     /// Mark the variable, return true if the variable is previously unseen.
@@ -106,12 +116,14 @@ impl ConflictAnalyzer {
     where
         P: ConflictDataProvider,
     {
+        self.seen_queue.clear();
         let mut session = Session::new(self, data_provider, current_level);
         session.add_clause(conflicting_clause);
 
+        // Literals of lower levels may be interleaved after chronological backtracking
         for literal in literals.iter().rev().copied() {
             let variable = literal.variable();
-            if session.seen(variable) {
+            if session.seen(variable) && data_provider.level(variable) == current_level {
                 session.unresolved_on_current_level -= 1;
                 if session.unresolved_on_current_level == 0 {
                     // First UIP reached
@@ -0,0 +1,416 @@
+/*!
+Gauss-Jordan elimination over parity constraints.
+
+Parity constraints form a linear system over GF(2). Each row has a basic
+variable that appears in no other row. While the basic variable of a row is
+unassigned, the row cannot propagate. When the basic variable gets assigned,
+another unassigned variable of the row becomes basic (a pivot), eliminating it
+from the other rows. The pivot adds the row to them, assigned old basic variable
+included, so only the basic variables are kept out of the other rows and the
+system is not in reduced row echelon form over all variables.
+A row propagates its basic variable as soon as it is the only unassigned one.
+As pivots combine rows, this finds implications of several constraints that
+single constraints miss, though not necessarily every implication of the system.
+
+Besides its basic variable, each row watches one more unassigned variable,
+so only the rows of the assigned variable are examined.
+Row operations are equivalences, so nothing needs to be undone on backtracking.
+
+Propagations and conflicts are explained by the clause that the (combined) row
+implies under the current assignment.
+*/
+
+use std::collections::HashMap;
+
+use crate::formula::{Clause, Literal, Variable, XorClause};
+
+/// The outcome of Gauss-Jordan propagation.
+pub enum GaussResult {
+    /// Literals implied by the system, with clauses explaining them.
+    Propagate(Vec<(Literal, Clause)>),
+    /// A clause falsified by the current assignment.
+    Conflict(Clause),
+}
+
+struct Row {
+    /// Bitset of columns.
+    bits: Vec<u64>,
+    parity: bool,
+    /// The column that appears only in this row.
+    basic: usize,
+    /// Another column watched for assignments.
+    watch: Option<usize>,
+    /// The last event that examined this row, to skip duplicated watches.
+    stamp: usize,
+}
+
+impl Row {
+    fn contains(&self, column: usize) -> bool {
+        self.bits[column / 64] >> (column % 64) & 1 == 1
+    }
+
+    fn columns(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits
+            .iter()
+            .enumerate()
+            .flat_map(|(word_index, &word)| {
+                (0..64)
+                    .filter(move |bit| word >> bit & 1 == 1)
+                    .map(move |bit| word_index * 64 + bit)
+            })
+    }
+}
+
+/// Rows that became parity constraints of a single form after elimination.
+enum RowState {
+    /// At least two unassigned columns.
+    Open { unassigned: [usize; 2] },
+    /// Exactly one unassigned column, which must take the value.
+    Unit { column: usize, value: bool },
+    /// Every column is assigned, satisfying the row or not.
+    Assigned { satisfied: bool },
+}
+
+pub struct GaussElimination {
+    /// Column -> variable
+    variables: Vec<Variable>,
+    /// Variable index -> column
+    columns: Vec<Option<usize>>,
+    rows: Vec<Row>,
+    /// Column -> the row having it as the basic column
+    basic_row: Vec<Option<usize>>,
+    /// Column -> rows watching it (may contain stale entries)
+    watchers: Vec<Vec<usize>>,
+    /// Rows modified by pivots, to be examined again
+    dirty: Vec<usize>,
+    /// The position of the trail to process next
+    head: usize,
+    stamp: usize,
+    /// Whether the system has no solution
+    inconsistent: bool,
+}
+
+impl GaussElimination {
+    pub fn new(num_variables: usize, xors: &[XorClause]) -> Self {
+        let mut variables = Vec::new();
+        let mut columns = vec![None; num_variables];
+        for xor in xors {
+            for &variable in xor.variables() {
+                if columns[variable.index()].is_none() {
+                    columns[variable.index()] = Some(variables.len());
+                    variables.push(variable);
+                }
+            }
+        }
+
+        let num_words = variables.len().div_ceil(64);
+        let mut matrix: Vec<(Vec<u64>, bool)> = xors
+            .iter()
+            .map(|xor| {
+                let mut bits = vec![0; num_words];
+                for variable in xor.variables() {
+                    let column = columns[variable.index()].unwrap();
+                    bits[column / 64] |= 1 << (column % 64);
+                }
+                (bits, xor.parity())
+            })
+            .collect();
+
+        // Initial Gauss-Jordan elimination
+        let mut rank = 0;
+        let mut pivots = Vec::new();
+        for column in 0..variables.len() {
+            let word = column / 64;
+            let mask = 1 << (column % 64);
+            let pivot = match (rank..matrix.len()).find(|&row| matrix[row].0[word] & mask != 0) {
+                Some(pivot) => pivot,
+                None => continue,
+            };
+            matrix.swap(rank, pivot);
+            for row in 0..matrix.len() {
+                if row != rank && matrix[row].0[word] & mask != 0 {
+                    let (bits, parity) = matrix[rank].clone();
+                    for (target, source) in matrix[row].0.iter_mut().zip(bits) {
+                        *target ^= source;
+                    }
+                    matrix[row].1 ^= parity;
+                }
+            }
+            pivots.push(column);
+            rank += 1;
+        }
+
+        // Remaining rows are `0 = parity`
+        let inconsistent = matrix[rank..].iter().any(|(_, parity)| *parity);
+        matrix.truncate(rank);
+
+        let mut gauss = GaussElimination {
+            columns,
+            rows: Vec::new(),
+            basic_row: vec![None; variables.len()],
+            watchers: vec![Vec::new(); variables.len()],
+            variables,
+            dirty: Vec::new(),
+            head: 0,
+            stamp: 0,
+            inconsistent,
+        };
+        for (index, ((bits, parity), basic)) in matrix.into_iter().zip(pivots).enumerate() {
+            gauss.basic_row[basic] = Some(index);
+            gauss.rows.push(Row {
+                bits,
+                parity,
+                basic,
+                watch: None,
+                stamp: 0,
+            });
+            // Examined on the first propagation to set the watches
+            gauss.dirty.push(index);
+        }
+
+        debug!(
+            "Gauss-Jordan: {} rows over {} columns",
+            gauss.rows.len(),
+            gauss.variables.len()
+        );
+        gauss
+    }
+
+    /// Adds a variable numbered after the existing variables, which is in no row.
+    pub fn add_variable(&mut self) {
+        self.columns.push(None);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty() && !self.inconsistent
+    }
+
+    /// Returns whether every variable in the system is assigned.
+    pub fn is_assigned(&self, assignments: &[Option<bool>]) -> bool {
+        self.variables
+            .iter()
+            .all(|variable| assignments[variable.index()].is_some())
+    }
+
+    /// Notifies that the trail was shortened to `len`.
+    pub fn backtrack(&mut self, len: usize) {
+        self.head = self.head.min(len);
+    }
+
+    fn value(&self, column: usize, assignments: &[Option<bool>]) -> Option<bool> {
+        assignments[self.variables[column].index()]
+    }
+
+    fn state(&self, row: usize, assignments: &[Option<bool>]) -> RowState {
+        let mut unassigned = Vec::with_capacity(2);
+        let mut sum = false;
+        for column in self.rows[row].columns() {
+            match self.value(column, assignments) {
+                Some(value) => sum ^= value,
+                None => unassigned.push(column),
+            }
+        }
+
+        match unassigned.len() {
+            0 => RowState::Assigned {
+                satisfied: sum == self.rows[row].parity,
+            },
+            1 => RowState::Unit {
+                column: unassigned[0],
+                value: sum ^ self.rows[row].parity,
+            },
+            _ => {
+                // Prefer keeping the current basic and watch columns
+                let row = &self.rows[row];
+                unassigned.sort_by_key(|&column| {
+                    (column != row.basic, Some(column) != row.watch, column)
+                });
+                RowState::Open {
+                    unassigned: [unassigned[0], unassigned[1]],
+                }
+            }
+        }
+    }
+
+    /// Makes `column` the basic column of `row`, eliminating it from the other rows.
+    fn pivot(&mut self, row: usize, column: usize) {
+        let old_basic = self.rows[row].basic;
+        if old_basic == column {
+            return;
+        }
+
+        let (bits, parity) = (self.rows[row].bits.clone(), self.rows[row].parity);
+        for other in 0..self.rows.len() {
+            if other != row && self.rows[other].contains(column) {
+                for (target, source) in self.rows[other].bits.iter_mut().zip(&bits) {
+                    *target ^= source;
+                }
+                self.rows[other].parity ^= parity;
+                self.dirty.push(other);
+            }
+        }
+
+        self.basic_row[old_basic] = None;
+        self.basic_row[column] = Some(row);
+        self.rows[row].basic = column;
+    }
+
+    fn set_watch(&mut self, row: usize, column: usize) {
+        if self.rows[row].watch != Some(column) {
+            self.rows[row].watch = Some(column);
+            self.watchers[column].push(row);
+        }
+    }
+
+    /// Explains the row under the current assignment,
+    /// with `implied` being the literal derived from the row if any.
+    fn explain(
+        &self,
+        row: usize,
+        implied: Option<Literal>,
+        assignments: &[Option<bool>],
+    ) -> Clause {
+        let mut literals: Vec<Literal> = implied.into_iter().collect();
+        for column in self.rows[row].columns() {
+            if let Some(value) = self.value(column, assignments) {
+                literals.push(Literal::new(self.variables[column], !value));
+            }
+        }
+        Clause::new(literals)
+    }
+
+    /// Examines the row after an assignment or a modification.
+    /// Returns a propagation or a conflict of the row.
+    fn examine(
+        &mut self,
+        row: usize,
+        assignments: &[Option<bool>],
+        propagations: &mut Vec<(Literal, Clause)>,
+    ) -> Option<Clause> {
+        match self.state(row, assignments) {
+            RowState::Open { unassigned } => {
+                self.pivot(row, unassigned[0]);
+                self.set_watch(row, unassigned[1]);
+            }
+            RowState::Unit { column, value } => {
+                self.pivot(row, column);
+                let literal = Literal::new(self.variables[column], value);
+                propagations.push((literal, self.explain(row, Some(literal), assignments)));
+            }
+            RowState::Assigned { satisfied: true } => (),
+            RowState::Assigned { satisfied: false } => {
+                return Some(self.explain(row, None, assignments));
+            }
+        }
+        None
+    }
+
+    /// Processes the assignments on the trail since the last call.
+    /// Rows left unexamined after a conflict are examined on the next call.
+    pub fn propagate(&mut self, trail: &[Literal], assignments: &[Option<bool>]) -> GaussResult {
+        if self.inconsistent {
+            return GaussResult::Conflict(Clause::new(Vec::new()));
+        }
+
+        let mut propagations = Vec::new();
+        loop {
+            while let Some(row) = self.dirty.pop() {
+                if let Some(conflict) = self.examine(row, assignments, &mut propagations) {
+                    return GaussResult::Conflict(conflict);
+                }
+            }
+
+            let column = match trail.get(self.head) {
+                Some(literal) => {
+                    self.head += 1;
+                    match self.columns[literal.index()] {
+                        Some(column) => column,
+                        None => continue,
+                    }
+                }
+                None => break,
+            };
+
+            self.stamp += 1;
+            let mut rows = std::mem::take(&mut self.watchers[column]);
+            rows.retain(|&row| self.rows[row].watch == Some(column));
+            rows.extend(self.basic_row[column]);
+
+            for (position, &row) in rows.iter().enumerate() {
+                if self.rows[row].stamp == self.stamp {
+                    continue;
+                }
+                self.rows[row].stamp = self.stamp;
+
+                let conflict = self.examine(row, assignments, &mut propagations);
+                if conflict.is_some() {
+                    // Rows not examined yet keep watching the column
+                    for &rest in &rows[position + 1..] {
+                        if self.rows[rest].watch == Some(column) {
+                            self.watchers[column].push(rest);
+                        }
+                    }
+                }
+                if self.rows[row].watch == Some(column) {
+                    self.watchers[column].push(row);
+                }
+                if let Some(conflict) = conflict {
+                    return GaussResult::Conflict(conflict);
+                }
+            }
+        }
+
+        GaussResult::Propagate(propagations)
+    }
+}
+
+/// Finds parity constraints encoded in clauses, where a constraint over `k` variables
+/// is the set of all `2^(k-1)` clauses over them with the same parity of negations.
+/// The clauses stay in the formula.
+pub fn detect_xors(clauses: &[Clause]) -> Vec<XorClause> {
+    /// Longer constraints are rarely encoded directly.
+    const MAX_XOR_LEN: usize = 6;
+
+    // Sorted variables -> bitmasks of negated positions
+    let mut groups: HashMap<Vec<Variable>, Vec<u32>> = HashMap::new();
+    for clause in clauses {
+        if clause.len() < 3 || clause.len() > MAX_XOR_LEN {
+            continue;
+        }
+
+        let mut literals = clause.as_slice().to_vec();
+        literals.sort_unstable();
+        let variables: Vec<Variable> = literals.iter().map(|literal| literal.variable()).collect();
+        if variables.windows(2).any(|pair| pair[0] == pair[1]) {
+            continue;
+        }
+
+        let mask = literals
+            .iter()
+            .enumerate()
+            .filter(|(_, literal)| !literal.positive())
+            .fold(0, |mask, (position, _)| mask | 1 << position);
+        groups.entry(variables).or_default().push(mask);
+    }
+
+    let mut xors = Vec::new();
+    for (variables, mut masks) in groups {
+        masks.sort_unstable();
+        masks.dedup();
+        let required = 1 << (variables.len() - 1);
+        for negation_parity in [0, 1] {
+            let count = masks
+                .iter()
+                .filter(|mask| mask.count_ones() % 2 == negation_parity)
+                .count();
+            if count == required {
+                // A clause forbids the assignment with its negated positions set to true
+                xors.push(XorClause::with_parity(
+                    variables.clone(),
+                    negation_parity == 0,
+                ));
+            }
+        }
+    }
+    xors
+}
@@ -0,0 +1,104 @@
+/*!
+Decision heuristics for the CDCL solver.
+*/
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    formula::{Clause, Variable},
+    prelude::*,
+    solver::{Error, UnknownHeuristic},
+};
+
+use self::{chb::ChbScoring, lrb::LrbScoring, vmtf::VmtfQueue, vsids::VsidsScoring};
+
+use super::tracker::Tracker;
+
+mod chb;
+mod lrb;
+mod score_queue;
+mod vmtf;
+mod vsids;
+
+pub trait BranchingHeuristic {
+    /// Notifies that an unassigned variable is added, numbered after the existing variables.
+    fn add_variable(&mut self, variable: Variable);
+
+    /// Notifies that the variable is assigned.
+    fn assign(&mut self, variable: Variable);
+
+    /// Notifies that the variable is unassigned.
+    fn unassign(&mut self, variable: Variable);
+
+    /// Picks an unassigned variable to branch on.
+    /// Panics if all variables are assigned.
+    fn decide(&mut self) -> Variable;
+
+    /// Notifies the result of a conflict analysis.
+    /// `participants` are the variables seen during the analysis.
+    fn conflict(&mut self, participants: &[Variable], learned: &Clause);
+
+    /// Returns how active the variable is. Higher is more active.
+    fn activity(&self, variable: Variable) -> f64;
+}
+
+/// Selects the decision heuristic of the CDCL solver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CdclHeuristic {
+    /// Variable State Independent Decaying Sum
+    #[default]
+    Vsids,
+    /// Variable Move-To-Front
+    Vmtf,
+    /// Conflict History-based Branching
+    Chb,
+    /// Learning Rate Branching
+    Lrb,
+}
+
+impl CdclHeuristic {
+    pub const ALL: [CdclHeuristic; 4] = [
+        CdclHeuristic::Vsids,
+        CdclHeuristic::Vmtf,
+        CdclHeuristic::Chb,
+        CdclHeuristic::Lrb,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CdclHeuristic::Vsids => "vsids",
+            CdclHeuristic::Vmtf => "vmtf",
+            CdclHeuristic::Chb => "chb",
+            CdclHeuristic::Lrb => "lrb",
+        }
+    }
+
+    /// Creates the heuristic, seeding the tie-breaking of those ordering variables by scores.
+    pub(super) fn build(self, tracker: &Tracker, seed: u64) -> Box<dyn BranchingHeuristic> {
+        let num_variables = tracker.num_variables();
+        match self {
+            CdclHeuristic::Vsids => Box::new(VsidsScoring::new(tracker, seed)),
+            CdclHeuristic::Vmtf => Box::new(VmtfQueue::new(num_variables)),
+            CdclHeuristic::Chb => Box::new(ChbScoring::new(num_variables, seed)),
+            CdclHeuristic::Lrb => Box::new(LrbScoring::new(num_variables, seed)),
+        }
+    }
+}
+
+impl FromStr for CdclHeuristic {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CdclHeuristic::ALL
+            .iter()
+            .copied()
+            .find(|heuristic| heuristic.name() == s)
+            .context(UnknownHeuristic { name: s })
+    }
+}
+
+impl Display for CdclHeuristic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
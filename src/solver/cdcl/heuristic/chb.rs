@@ -0,0 +1,91 @@
+use crate::formula::{Clause, Variable};
+
+use super::{score_queue::ScoreQueue, BranchingHeuristic};
+
+/// Conflict History-based Branching (CHB) heuristic.
+/// Based on "Exponential Recency Weighted Average Branching Heuristic for SAT Solvers"
+/// by Liang et al.
+///
+/// Variables assigned by a propagation round are rewarded when the round ends,
+/// with a higher reward if the round ended with a conflict
+/// and if the variable recently participated in a conflict.
+pub struct ChbScoring {
+    /// Step size of the exponential recency weighted average.
+    step_size: f64,
+    queue: ScoreQueue,
+    /// The number of conflicts when each variable last participated in a conflict.
+    last_conflict: Vec<u64>,
+    num_conflicts: u64,
+    /// Variables assigned in the current propagation round.
+    pending: Vec<Variable>,
+}
+
+impl ChbScoring {
+    const INITIAL_STEP_SIZE: f64 = 0.4;
+    const MIN_STEP_SIZE: f64 = 0.06;
+    const STEP_SIZE_DECREMENT: f64 = 1e-6;
+    const CONFLICT_MULTIPLIER: f64 = 1.0;
+    const NO_CONFLICT_MULTIPLIER: f64 = 0.9;
+
+    pub fn new(num_variables: usize, seed: u64) -> Self {
+        ChbScoring {
+            step_size: Self::INITIAL_STEP_SIZE,
+            queue: ScoreQueue::new(vec![0.0; num_variables], seed),
+            last_conflict: vec![0; num_variables],
+            num_conflicts: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Rewards the variables assigned in the current propagation round.
+    fn flush(&mut self, multiplier: f64) {
+        for variable in std::mem::take(&mut self.pending) {
+            let age = self.num_conflicts - self.last_conflict[variable.index()] + 1;
+            let reward = multiplier / age as f64;
+            let old_score = self.queue.score(variable);
+            self.queue.set_score(
+                variable,
+                (1.0 - self.step_size) * old_score + self.step_size * reward,
+            );
+        }
+    }
+}
+
+impl BranchingHeuristic for ChbScoring {
+    fn add_variable(&mut self, _variable: Variable) {
+        self.queue.push(0.0);
+        self.last_conflict.push(0);
+    }
+
+    fn assign(&mut self, variable: Variable) {
+        self.queue.remove(variable);
+        self.pending.push(variable);
+    }
+
+    fn unassign(&mut self, variable: Variable) {
+        self.queue.insert(variable);
+    }
+
+    fn decide(&mut self) -> Variable {
+        // The previous propagation round finished without a conflict
+        self.flush(Self::NO_CONFLICT_MULTIPLIER);
+        self.queue.top()
+    }
+
+    fn conflict(&mut self, participants: &[Variable], _learned: &Clause) {
+        self.num_conflicts += 1;
+        for variable in participants {
+            self.last_conflict[variable.index()] = self.num_conflicts;
+        }
+
+        self.flush(Self::CONFLICT_MULTIPLIER);
+
+        if self.step_size > Self::MIN_STEP_SIZE {
+            self.step_size -= Self::STEP_SIZE_DECREMENT;
+        }
+    }
+
+    fn activity(&self, variable: Variable) -> f64 {
+        self.queue.score(variable)
+    }
+}
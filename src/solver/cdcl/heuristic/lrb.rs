@@ -0,0 +1,82 @@
+use crate::formula::{Clause, Variable};
+
+use super::{score_queue::ScoreQueue, BranchingHeuristic};
+
+/// Learning Rate Branching (LRB) heuristic.
+/// Based on "Learning Rate Based Branching Heuristic for SAT Solvers" by Liang et al.
+///
+/// The learning rate of a variable is the ratio of learned clauses it participated in
+/// while it was assigned. The score is the exponential recency weighted average
+/// of the learning rate, updated when the variable gets unassigned.
+pub struct LrbScoring {
+    /// Step size of the exponential recency weighted average.
+    step_size: f64,
+    queue: ScoreQueue,
+    /// The number of learned clauses when each variable was assigned.
+    assigned_at: Vec<u64>,
+    /// The number of conflicts each variable participated in since its assignment.
+    participated: Vec<u64>,
+    num_learned: u64,
+}
+
+impl LrbScoring {
+    const INITIAL_STEP_SIZE: f64 = 0.4;
+    const MIN_STEP_SIZE: f64 = 0.06;
+    const STEP_SIZE_DECREMENT: f64 = 1e-6;
+
+    pub fn new(num_variables: usize, seed: u64) -> Self {
+        LrbScoring {
+            step_size: Self::INITIAL_STEP_SIZE,
+            queue: ScoreQueue::new(vec![0.0; num_variables], seed),
+            assigned_at: vec![0; num_variables],
+            participated: vec![0; num_variables],
+            num_learned: 0,
+        }
+    }
+}
+
+impl BranchingHeuristic for LrbScoring {
+    fn add_variable(&mut self, _variable: Variable) {
+        self.queue.push(0.0);
+        self.assigned_at.push(0);
+        self.participated.push(0);
+    }
+
+    fn assign(&mut self, variable: Variable) {
+        self.queue.remove(variable);
+        self.assigned_at[variable.index()] = self.num_learned;
+        self.participated[variable.index()] = 0;
+    }
+
+    fn unassign(&mut self, variable: Variable) {
+        let interval = self.num_learned - self.assigned_at[variable.index()];
+        if interval > 0 {
+            let rate = self.participated[variable.index()] as f64 / interval as f64;
+            let old_score = self.queue.score(variable);
+            self.queue.set_score(
+                variable,
+                (1.0 - self.step_size) * old_score + self.step_size * rate,
+            );
+        }
+        self.queue.insert(variable);
+    }
+
+    fn decide(&mut self) -> Variable {
+        self.queue.top()
+    }
+
+    fn conflict(&mut self, participants: &[Variable], _learned: &Clause) {
+        self.num_learned += 1;
+        for variable in participants {
+            self.participated[variable.index()] += 1;
+        }
+
+        if self.step_size > Self::MIN_STEP_SIZE {
+            self.step_size -= Self::STEP_SIZE_DECREMENT;
+        }
+    }
+
+    fn activity(&self, variable: Variable) -> f64 {
+        self.queue.score(variable)
+    }
+}
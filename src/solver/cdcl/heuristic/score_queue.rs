@@ -1,8 +1,8 @@
 use std::{cmp::Ordering, collections::BTreeSet};
 
-use crate::formula::{Clause, Variable};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-use super::tracker::Tracker;
+use crate::formula::Variable;
 
 #[derive(Clone, Copy)]
 struct VecEntry {
@@ -11,17 +11,14 @@ struct VecEntry {
 }
 
 impl VecEntry {
-    pub fn new(score: f64) -> Self {
-        VecEntry {
-            score,
-            nonce: rand::random(),
-        }
+    pub fn new(score: f64, nonce: f64) -> Self {
+        VecEntry { score, nonce }
     }
 
-    /// Update the score by delta, change the nonce, and return the updated score.
-    pub fn update(&mut self, delta: f64) -> f64 {
-        self.score += delta;
-        self.nonce = rand::random();
+    /// Set the score, change the nonce, and return the updated score.
+    pub fn set(&mut self, score: f64, nonce: f64) -> f64 {
+        self.score = score;
+        self.nonce = nonce;
         self.score
     }
 }
@@ -73,90 +70,88 @@ impl Ord for SetEntry {
     }
 }
 
-/// Variable State Independent Decaying Sum (VSIDS) heuristic.
-/// Based on MiniSAT implementation.
-pub struct VsidsScoring {
-    current_rate: f64,
+/// A set of unassigned variables ordered by their scores.
+/// Ties are broken randomly.
+pub struct ScoreQueue {
     scores: Vec<VecEntry>,
     btree: BTreeSet<SetEntry>,
+    /// Draws the nonces breaking ties
+    rng: StdRng,
 }
 
-impl VsidsScoring {
-    const DECAY_RATE: f64 = 0.95;
-    const REBALANCE_THRESHOLD: f64 = 1e100;
-
-    pub fn new(tracker: &Tracker) -> Self {
-        let num_variables = tracker.num_variables();
-
-        let mut scores = Vec::with_capacity(num_variables);
+impl ScoreQueue {
+    /// Creates a queue that contains all variables with the given initial scores,
+    /// breaking ties with the seeded random number generator.
+    pub fn new(scores: impl IntoIterator<Item = f64>, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut vec = Vec::new();
         let mut btree = BTreeSet::new();
 
-        for index in 0..num_variables {
+        for (index, score) in scores.into_iter().enumerate() {
             let variable = Variable::from_index(index).unwrap();
-            let vec_entry = VecEntry::new(tracker.variable_occurrence(variable) as f64);
-            scores.push(vec_entry);
+            let vec_entry = VecEntry::new(score, rng.gen());
+            vec.push(vec_entry);
             btree.insert(SetEntry::from_vec_entry(variable, vec_entry));
         }
 
-        VsidsScoring {
-            current_rate: 1.0,
-            scores,
+        ScoreQueue {
+            scores: vec,
             btree,
+            rng,
         }
     }
 
-    fn bump_score(&mut self, variable: Variable) {
+    /// Adds a variable numbered after the existing variables with the given score.
+    pub fn push(&mut self, score: f64) {
+        let variable = Variable::from_index(self.scores.len()).unwrap();
+        self.scores.push(VecEntry::new(score, self.rng.gen()));
+        self.btree.insert(self.set_entry(variable));
+    }
+
+    fn set_entry(&self, variable: Variable) -> SetEntry {
+        SetEntry::from_vec_entry(variable, self.scores[variable.index()])
+    }
+
+    pub fn score(&self, variable: Variable) -> f64 {
+        self.scores[variable.index()].score
+    }
+
+    /// Updates the score of the variable and returns the new score.
+    pub fn set_score(&mut self, variable: Variable, score: f64) -> f64 {
         let present = self.btree.remove(&self.set_entry(variable));
 
-        let new_score = self.scores[variable.index()].update(self.current_rate);
+        let nonce = self.rng.gen();
+        let new_score = self.scores[variable.index()].set(score, nonce);
 
         if present {
             self.btree.insert(self.set_entry(variable));
         }
 
-        if new_score >= Self::REBALANCE_THRESHOLD {
-            self.rebalance();
-        }
+        new_score
     }
 
-    fn rebalance(&mut self) {
-        self.current_rate /= Self::REBALANCE_THRESHOLD;
+    /// Divides all scores by `divisor`, keeping the relative order.
+    pub fn rescale(&mut self, divisor: f64) {
         for index in 0..self.scores.len() {
             let variable = Variable::from_index(index).unwrap();
             let present = self.btree.remove(&self.set_entry(variable));
-            self.scores[index].score /= Self::REBALANCE_THRESHOLD;
+            self.scores[index].score /= divisor;
             if present {
                 self.btree.insert(self.set_entry(variable));
             }
         }
     }
 
-    fn set_entry(&self, variable: Variable) -> SetEntry {
-        SetEntry::from_vec_entry(variable, self.scores[variable.index()])
-    }
-
     pub fn insert(&mut self, variable: Variable) {
-        trace!("VSIDS insert {}", variable);
         self.btree.insert(self.set_entry(variable));
     }
 
     pub fn remove(&mut self, variable: Variable) {
-        trace!("VSIDS remove {}", variable);
         self.btree.remove(&self.set_entry(variable));
     }
 
-    pub fn top(&mut self) -> Variable {
-        let variable = self.btree.iter().next().unwrap().variable;
-        variable
-    }
-
-    pub fn decay(&mut self) {
-        self.current_rate /= Self::DECAY_RATE;
-    }
-
-    pub fn learn_clause(&mut self, clause: &Clause) {
-        for literal in clause.iter() {
-            self.bump_score(literal.variable());
-        }
+    /// Returns the variable with the highest score.
+    pub fn top(&self) -> Variable {
+        self.btree.iter().next_back().unwrap().variable
     }
 }
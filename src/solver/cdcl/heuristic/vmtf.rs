@@ -0,0 +1,142 @@
+use crate::formula::{Clause, Variable};
+
+use super::BranchingHeuristic;
+
+#[derive(Clone, Copy)]
+struct Link {
+    prev: Option<Variable>,
+    next: Option<Variable>,
+}
+
+/// Variable Move-To-Front (VMTF) heuristic.
+/// Based on Kissat implementation.
+///
+/// Variables are kept in a doubly linked list ordered by the time they were bumped.
+/// Variables that participated in a conflict are moved to the end of the list,
+/// and the decision picks the most recently bumped unassigned variable.
+pub struct VmtfQueue {
+    links: Vec<Link>,
+    /// The most recently bumped variable.
+    tail: Option<Variable>,
+    /// Bump timestamp of each variable.
+    stamps: Vec<u64>,
+    next_stamp: u64,
+    assigned: Vec<bool>,
+    /// All variables after this one are assigned.
+    search: Option<Variable>,
+}
+
+impl VmtfQueue {
+    pub fn new(num_variables: usize) -> Self {
+        let mut queue = VmtfQueue {
+            links: vec![
+                Link {
+                    prev: None,
+                    next: None
+                };
+                num_variables
+            ],
+            tail: None,
+            stamps: vec![0; num_variables],
+            next_stamp: 0,
+            assigned: vec![false; num_variables],
+            search: None,
+        };
+
+        for index in 0..num_variables {
+            queue.enqueue(Variable::from_index(index).unwrap());
+        }
+        queue.search = queue.tail;
+
+        queue
+    }
+
+    /// Appends the variable at the end of the list with a fresh timestamp.
+    fn enqueue(&mut self, variable: Variable) {
+        self.links[variable.index()] = Link {
+            prev: self.tail,
+            next: None,
+        };
+        if let Some(tail) = self.tail {
+            self.links[tail.index()].next = Some(variable);
+        }
+        self.tail = Some(variable);
+
+        self.stamps[variable.index()] = self.next_stamp;
+        self.next_stamp += 1;
+    }
+
+    fn dequeue(&mut self, variable: Variable) {
+        let link = self.links[variable.index()];
+        if let Some(prev) = link.prev {
+            self.links[prev.index()].next = link.next;
+        }
+        match link.next {
+            Some(next) => self.links[next.index()].prev = link.prev,
+            None => self.tail = link.prev,
+        }
+    }
+
+    fn is_newer(&self, lhs: Variable, rhs: Option<Variable>) -> bool {
+        match rhs {
+            Some(rhs) => self.stamps[lhs.index()] > self.stamps[rhs.index()],
+            None => true,
+        }
+    }
+}
+
+impl BranchingHeuristic for VmtfQueue {
+    fn add_variable(&mut self, variable: Variable) {
+        self.links.push(Link {
+            prev: None,
+            next: None,
+        });
+        self.stamps.push(0);
+        self.assigned.push(false);
+        self.enqueue(variable);
+        // The newest variable is unassigned
+        self.search = Some(variable);
+    }
+
+    fn assign(&mut self, variable: Variable) {
+        self.assigned[variable.index()] = true;
+    }
+
+    fn unassign(&mut self, variable: Variable) {
+        self.assigned[variable.index()] = false;
+        if self.is_newer(variable, self.search) {
+            self.search = Some(variable);
+        }
+    }
+
+    fn decide(&mut self) -> Variable {
+        let mut current = self.search;
+        while let Some(variable) = current {
+            if !self.assigned[variable.index()] {
+                self.search = Some(variable);
+                return variable;
+            }
+            current = self.links[variable.index()].prev;
+        }
+
+        panic!("No unassigned variable left")
+    }
+
+    fn conflict(&mut self, participants: &[Variable], _learned: &Clause) {
+        // Keep the relative order of bumped variables
+        let mut bumped = participants.to_vec();
+        bumped.sort_by_key(|variable| self.stamps[variable.index()]);
+
+        for variable in bumped {
+            self.dequeue(variable);
+            self.enqueue(variable);
+            if !self.assigned[variable.index()] {
+                self.search = Some(variable);
+            }
+        }
+    }
+
+    fn activity(&self, variable: Variable) -> f64 {
+        self.stamps[variable.index()] as f64
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    formula::{Clause, Variable},
+    solver::cdcl::tracker::Tracker,
+};
+
+use super::{score_queue::ScoreQueue, BranchingHeuristic};
+
+/// Variable State Independent Decaying Sum (VSIDS) heuristic.
+/// Based on MiniSAT implementation.
+pub struct VsidsScoring {
+    current_rate: f64,
+    queue: ScoreQueue,
+}
+
+impl VsidsScoring {
+    const DECAY_RATE: f64 = 0.95;
+    const REBALANCE_THRESHOLD: f64 = 1e100;
+
+    pub fn new(tracker: &Tracker, seed: u64) -> Self {
+        let scores = (0..tracker.num_variables()).map(|index| {
+            let variable = Variable::from_index(index).unwrap();
+            tracker.variable_occurrence(variable) as f64
+        });
+
+        VsidsScoring {
+            current_rate: 1.0,
+            queue: ScoreQueue::new(scores, seed),
+        }
+    }
+
+    fn bump_score(&mut self, variable: Variable) {
+        let new_score = self
+            .queue
+            .set_score(variable, self.queue.score(variable) + self.current_rate);
+
+        if new_score >= Self::REBALANCE_THRESHOLD {
+            self.rebalance();
+        }
+    }
+
+    fn rebalance(&mut self) {
+        self.current_rate /= Self::REBALANCE_THRESHOLD;
+        self.queue.rescale(Self::REBALANCE_THRESHOLD);
+    }
+
+    fn decay(&mut self) {
+        self.current_rate /= Self::DECAY_RATE;
+    }
+}
+
+impl BranchingHeuristic for VsidsScoring {
+    fn add_variable(&mut self, _variable: Variable) {
+        self.queue.push(0.0);
+    }
+
+    fn assign(&mut self, variable: Variable) {
+        trace!("VSIDS remove {}", variable);
+        self.queue.remove(variable);
+    }
+
+    fn unassign(&mut self, variable: Variable) {
+        trace!("VSIDS insert {}", variable);
+        self.queue.insert(variable);
+    }
+
+    fn decide(&mut self) -> Variable {
+        self.queue.top()
+    }
+
+    fn conflict(&mut self, _participants: &[Variable], learned: &Clause) {
+        for literal in learned.iter() {
+            self.bump_score(literal.variable());
+        }
+        self.decay();
+    }
+
+    fn activity(&self, variable: Variable) -> f64 {
+        self.queue.score(variable)
+    }
+}
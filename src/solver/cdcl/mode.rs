@@ -0,0 +1,118 @@
+/*!
+Alternation between focused and stable search modes.
+
+In focused mode, the solver restarts frequently and branches on the most recently
+bumped variables (VMTF), which tends to find short refutations quickly.
+In stable mode, it restarts rarely and branches by VSIDS towards the target phases,
+the assignment of the longest trail without conflicts seen in the mode,
+which tends to find models. The target phases are discarded on entering stable mode.
+Local search on rephasing only runs in stable mode, where decisions follow the target
+phases it finds, so a rephasing due in focused mode waits for the next stable mode.
+
+Each mode has its own heuristic and restart policy. The inactive ones are set aside
+at the root level and resume where they stopped, after being told about the root level
+assignments made in the meantime. Modes switch on a restart after a number of conflicts,
+which doubles after every stable mode.
+*/
+
+use crate::formula::Variable;
+
+use super::{
+    heuristic::{BranchingHeuristic, CdclHeuristic},
+    restart::{CdclRestart, LubyRestart, RestartPolicy},
+    tracker::Tracker,
+    CdclSolver,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Focused,
+    Stable,
+}
+
+/// The heuristic and restart policy of the inactive mode.
+pub struct ModeScheduler {
+    mode: Mode,
+    heuristic: Box<dyn BranchingHeuristic>,
+    restart_policy: Box<dyn RestartPolicy>,
+    /// The length of the root level trail when the inactive mode stopped
+    trail_len: usize,
+    /// The number of conflicts at which the mode switches next
+    next_switch: usize,
+    /// The number of switches so far
+    switches: usize,
+}
+
+impl ModeScheduler {
+    /// Conflicts in the first focused mode.
+    const FIRST_INTERVAL: usize = 1000;
+    /// Restart interval unit in stable mode, compared to 100 in focused mode.
+    const STABLE_RESTART_UNIT: usize = 1024;
+
+    /// Creates the scheduler starting in focused mode, along with the heuristic
+    /// and restart policy of the focused mode.
+    pub fn new(
+        tracker: &Tracker,
+        seed: u64,
+    ) -> (Self, Box<dyn BranchingHeuristic>, Box<dyn RestartPolicy>) {
+        let scheduler = ModeScheduler {
+            mode: Mode::Focused,
+            heuristic: CdclHeuristic::Vsids.build(tracker, seed),
+            restart_policy: Box::new(LubyRestart::with_unit(Self::STABLE_RESTART_UNIT)),
+            trail_len: 0,
+            next_switch: Self::FIRST_INTERVAL,
+            switches: 0,
+        };
+        (
+            scheduler,
+            CdclHeuristic::Vmtf.build(tracker, seed),
+            CdclRestart::Luby.build(),
+        )
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn should_switch(&self, num_conflicts: usize) -> bool {
+        num_conflicts >= self.next_switch
+    }
+
+    /// Notifies the heuristic of the inactive mode of an added variable.
+    pub fn add_variable(&mut self, variable: Variable) {
+        self.heuristic.add_variable(variable);
+    }
+}
+
+impl CdclSolver {
+    /// Swaps the heuristic and restart policy with those of the other mode.
+    pub(super) fn switch_mode(&mut self) {
+        debug_assert_eq!(self.current_level(), 0);
+
+        let modes = self.modes.as_mut().unwrap();
+        std::mem::swap(&mut self.score_heuristic, &mut modes.heuristic);
+        std::mem::swap(&mut self.restart_policy, &mut modes.restart_policy);
+
+        // Root level assignments are never undone, so the new ones follow the old ones
+        for &literal in &self.decision_stack[modes.trail_len..] {
+            self.score_heuristic.assign(literal.variable());
+        }
+        modes.trail_len = self.decision_stack.len();
+
+        modes.switches += 1;
+        self.stats.mode_switches += 1;
+        let interval = ModeScheduler::FIRST_INTERVAL << (modes.switches / 2);
+        modes.next_switch = self.stats.conflicts + interval;
+        modes.mode = match modes.mode {
+            Mode::Focused => Mode::Stable,
+            Mode::Stable => Mode::Focused,
+        };
+        debug!("Switch to {:?} mode for {} conflicts", modes.mode, interval);
+
+        let stable = modes.mode == Mode::Stable;
+        self.phases.use_target(stable);
+        if stable {
+            self.phases.reset_target();
+        }
+    }
+}
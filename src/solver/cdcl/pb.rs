@@ -0,0 +1,168 @@
+/*!
+Counter-based propagation of pseudo-Boolean constraints.
+
+Each constraint `a1 l1 + ... + an ln ≥ k` tracks its slack,
+the amount by which the sum of the coefficients of the literals that are not false
+exceeds `k`. A literal whose coefficient is larger than the slack must be true,
+and a negative slack is a conflict. Cardinality constraints are the special case
+with unit coefficients.
+Slacks are updated as the propagator walks the trail, and restored on backtracking.
+
+Explanation clauses are not stored. The reason of a propagated literal `l` is
+`l ∨ m1 ∨ ... ∨ mj` over the literals `mi` of the constraint that were false before `l`,
+which can be rebuilt from the trail when conflict analysis needs it.
+*/
+
+use crate::formula::{Literal, PbConstraint};
+
+/// The outcome of pseudo-Boolean propagation.
+pub enum PbResult {
+    /// Literals implied by constraints, with the indices of the constraints.
+    Propagate(Vec<(Literal, usize)>),
+    /// The index of a constraint falsified by the current assignment.
+    Conflict(usize),
+}
+
+pub struct PbPropagator {
+    constraints: Vec<PbConstraint>,
+    /// Literal code of a trail literal -> constraints containing its negation,
+    /// with its coefficient
+    occurrences: Vec<Vec<(usize, u64)>>,
+    /// Constraint -> slack under the processed trail
+    slack: Vec<i128>,
+    /// Constraints to examine regardless of the trail
+    pending: Vec<usize>,
+    /// The position of the trail to process next
+    head: usize,
+}
+
+fn code(literal: Literal) -> usize {
+    literal.index() * 2 + literal.positive() as usize
+}
+
+impl PbPropagator {
+    pub fn new(num_variables: usize, constraints: Vec<PbConstraint>) -> Self {
+        let mut occurrences = vec![Vec::new(); num_variables * 2];
+        let mut slack = Vec::new();
+        for (index, constraint) in constraints.iter().enumerate() {
+            let mut sum = 0;
+            for &(coefficient, literal) in constraint.terms() {
+                occurrences[code(!literal)].push((index, coefficient));
+                sum += coefficient as i128;
+            }
+            slack.push(sum - constraint.bound() as i128);
+        }
+
+        PbPropagator {
+            // Constraints with little slack propagate without any assignment
+            pending: (0..constraints.len()).collect(),
+            constraints,
+            occurrences,
+            slack,
+            head: 0,
+        }
+    }
+
+    /// Adds a variable numbered after the existing variables, which is in no constraint.
+    pub fn add_variable(&mut self) {
+        self.occurrences.push(Vec::new());
+        self.occurrences.push(Vec::new());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    pub fn constraint(&self, index: usize) -> &PbConstraint {
+        &self.constraints[index]
+    }
+
+    /// Returns whether every constraint is satisfied by the assigned literals.
+    pub fn is_satisfied(&self, assignments: &[Option<bool>]) -> bool {
+        self.constraints.iter().all(|constraint| {
+            let sum: u128 = constraint
+                .terms()
+                .iter()
+                .filter(|(_, literal)| literal.partial_value(assignments) == Some(true))
+                .map(|&(coefficient, _)| coefficient as u128)
+                .sum();
+            sum >= constraint.bound() as u128
+        })
+    }
+
+    /// Notifies that the literal at `position` of the trail was unassigned.
+    pub fn unassign(&mut self, position: usize, literal: Literal) {
+        if position < self.head {
+            for &(index, coefficient) in &self.occurrences[code(literal)] {
+                self.slack[index] += coefficient as i128;
+            }
+            self.head = position;
+        }
+    }
+
+    /// Examines the constraint, collecting its unassigned literals
+    /// whose coefficients exceed the slack.
+    /// Returns whether the constraint is falsified.
+    fn examine(
+        &self,
+        index: usize,
+        assignments: &[Option<bool>],
+        propagations: &mut Vec<(Literal, usize)>,
+    ) -> bool {
+        let slack = self.slack[index];
+        if slack < 0 {
+            return true;
+        }
+
+        // Coefficients are sorted in descending order
+        for &(coefficient, literal) in self.constraints[index].terms() {
+            if coefficient as i128 <= slack {
+                break;
+            }
+            if literal.partial_value(assignments).is_none() {
+                propagations.push((literal, index));
+            }
+        }
+        false
+    }
+
+    /// Processes the assignments on the trail since the last call.
+    pub fn propagate(&mut self, trail: &[Literal], assignments: &[Option<bool>]) -> PbResult {
+        let mut propagations = Vec::new();
+
+        while let Some(index) = self.pending.pop() {
+            if self.examine(index, assignments, &mut propagations) {
+                return self.conflict(index, &propagations);
+            }
+        }
+
+        while let Some(&literal) = trail.get(self.head) {
+            self.head += 1;
+
+            let occurrences = &self.occurrences[code(literal)];
+            for &(index, coefficient) in occurrences {
+                self.slack[index] -= coefficient as i128;
+            }
+            for &(index, _) in occurrences {
+                if self.examine(index, assignments, &mut propagations) {
+                    // The constraints after it are not examined yet
+                    self.pending
+                        .extend(occurrences.iter().map(|&(index, _)| index));
+                    return self.conflict(index, &propagations);
+                }
+            }
+        }
+
+        PbResult::Propagate(propagations)
+    }
+
+    /// Reports the falsified constraint. The backjump may keep the processed part
+    /// of the trail, so the constraints whose propagations are discarded
+    /// are examined again on the next call.
+    fn conflict(&mut self, index: usize, propagations: &[(Literal, usize)]) -> PbResult {
+        self.pending
+            .extend(propagations.iter().map(|&(_, index)| index));
+        self.pending.push(index);
+        PbResult::Conflict(index)
+    }
+}
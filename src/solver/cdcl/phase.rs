@@ -0,0 +1,104 @@
+use crate::formula::{Literal, Variable};
+
+/// Polarity selection for decisions.
+///
+/// The saved phase of a variable is the value it had when it was last unassigned
+/// (phase saving). The target phase, when present and in use, overrides the saved phase
+/// and steers the search towards a promising assignment.
+pub struct Phases {
+    saved: Vec<bool>,
+    target: Option<Vec<bool>>,
+    use_target: bool,
+    /// The length of the longest trail copied to the target phases since the last reset
+    target_len: usize,
+    /// The number of conflicts at which the next rephasing happens.
+    next_rephase: usize,
+    /// The number of rephasings so far.
+    rephase_count: usize,
+}
+
+impl Phases {
+    /// Conflicts between rephasings grow arithmetically by this amount.
+    const REPHASE_INTERVAL: usize = 1000;
+
+    pub fn new(num_variables: usize) -> Self {
+        Phases {
+            saved: vec![true; num_variables],
+            target: None,
+            use_target: true,
+            target_len: 0,
+            next_rephase: Self::REPHASE_INTERVAL,
+            rephase_count: 0,
+        }
+    }
+
+    /// Adds a variable numbered after the existing variables, with positive phases.
+    pub fn add_variable(&mut self) {
+        self.saved.push(true);
+        if let Some(target) = &mut self.target {
+            target.push(true);
+        }
+    }
+
+    /// Returns the literal of the variable to decide.
+    pub fn decide(&self, variable: Variable) -> Literal {
+        let phases = self
+            .target
+            .as_ref()
+            .filter(|_| self.use_target)
+            .unwrap_or(&self.saved);
+        Literal::new(variable, phases[variable.index()])
+    }
+
+    /// Saves the polarity of the literal being unassigned.
+    pub fn save(&mut self, literal: Literal) {
+        self.saved[literal.index()] = literal.positive();
+    }
+
+    pub fn saved(&self) -> &[bool] {
+        &self.saved
+    }
+
+    pub fn set_target(&mut self, target: Vec<bool>) {
+        self.target = Some(target);
+    }
+
+    /// Sets whether decisions follow the target phases.
+    pub fn use_target(&mut self, use_target: bool) {
+        self.use_target = use_target;
+    }
+
+    /// Copies the conflict-free trail to the target phases
+    /// if it is longer than any copied since the last reset.
+    pub fn update_target(&mut self, trail: &[Literal]) {
+        if trail.len() <= self.target_len {
+            return;
+        }
+        self.target_len = trail.len();
+
+        let saved = &self.saved;
+        let target = self.target.get_or_insert_with(|| saved.clone());
+        for literal in trail {
+            target[literal.index()] = literal.positive();
+        }
+    }
+
+    pub fn is_target_used(&self) -> bool {
+        self.use_target
+    }
+
+    /// Discards the target phases until the next update or walk.
+    pub fn reset_target(&mut self) {
+        self.target = None;
+        self.target_len = 0;
+    }
+
+    pub fn should_rephase(&self, num_conflicts: usize) -> bool {
+        num_conflicts >= self.next_rephase
+    }
+
+    pub fn rephased(&mut self, num_conflicts: usize) {
+        self.rephase_count += 1;
+        self.next_rephase = num_conflicts + Self::REPHASE_INTERVAL * (self.rephase_count + 1);
+    }
+}
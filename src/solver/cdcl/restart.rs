@@ -0,0 +1,166 @@
+/*!
+Restart policies for the CDCL solver.
+*/
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    prelude::*,
+    solver::{Error, UnknownRestart},
+};
+
+pub trait RestartPolicy {
+    /// Notifies that a conflict happened.
+    fn on_conflict(&mut self);
+
+    /// Returns whether the solver should restart now.
+    fn should_restart(&self) -> bool;
+
+    /// Notifies that the solver restarted.
+    fn restarted(&mut self);
+}
+
+/// Selects the restart policy of the CDCL solver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CdclRestart {
+    /// Restart intervals follow the Luby sequence
+    #[default]
+    Luby,
+    /// Restart intervals grow geometrically
+    Geometric,
+}
+
+impl CdclRestart {
+    pub const ALL: [CdclRestart; 2] = [CdclRestart::Luby, CdclRestart::Geometric];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CdclRestart::Luby => "luby",
+            CdclRestart::Geometric => "geometric",
+        }
+    }
+
+    pub(super) fn build(self) -> Box<dyn RestartPolicy> {
+        match self {
+            CdclRestart::Luby => Box::new(LubyRestart::new()),
+            CdclRestart::Geometric => Box::new(GeometricRestart::new()),
+        }
+    }
+}
+
+impl FromStr for CdclRestart {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CdclRestart::ALL
+            .iter()
+            .copied()
+            .find(|restart| restart.name() == s)
+            .context(UnknownRestart { name: s })
+    }
+}
+
+impl Display for CdclRestart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Restart schedule based on the Luby sequence (1, 1, 2, 1, 1, 2, 4, ...).
+/// Based on MiniSAT implementation.
+pub struct LubyRestart {
+    /// The number of conflicts that corresponds to one unit of the sequence.
+    unit: usize,
+    /// The index of the current element in the Luby sequence.
+    index: usize,
+    /// Conflicts since the last restart.
+    conflicts: usize,
+}
+
+impl LubyRestart {
+    const DEFAULT_UNIT: usize = 100;
+
+    pub fn new() -> Self {
+        Self::with_unit(Self::DEFAULT_UNIT)
+    }
+
+    pub fn with_unit(unit: usize) -> Self {
+        LubyRestart {
+            unit,
+            index: 0,
+            conflicts: 0,
+        }
+    }
+}
+
+/// Returns the `index`-th (0-based) element of the Luby sequence.
+pub fn luby(mut index: usize) -> usize {
+    // Find the finite subsequence that contains index, and the size of that subsequence
+    let mut size = 1;
+    let mut seq = 0;
+    while size < index + 1 {
+        seq += 1;
+        size = 2 * size + 1;
+    }
+
+    while size - 1 != index {
+        size = (size - 1) >> 1;
+        seq -= 1;
+        index %= size;
+    }
+
+    1 << seq
+}
+
+impl RestartPolicy for LubyRestart {
+    fn on_conflict(&mut self) {
+        self.conflicts += 1;
+    }
+
+    fn should_restart(&self) -> bool {
+        self.conflicts >= self.unit * luby(self.index)
+    }
+
+    fn restarted(&mut self) {
+        self.index += 1;
+        self.conflicts = 0;
+    }
+}
+
+/// Restart schedule whose interval is multiplied by a constant factor on each restart.
+/// Based on MiniSAT implementation.
+pub struct GeometricRestart {
+    /// Conflicts until the next restart.
+    interval: f64,
+    factor: f64,
+    /// Conflicts since the last restart.
+    conflicts: usize,
+}
+
+impl GeometricRestart {
+    const INITIAL_INTERVAL: f64 = 100.0;
+    const FACTOR: f64 = 1.5;
+
+    pub fn new() -> Self {
+        GeometricRestart {
+            interval: Self::INITIAL_INTERVAL,
+            factor: Self::FACTOR,
+            conflicts: 0,
+        }
+    }
+}
+
+impl RestartPolicy for GeometricRestart {
+    fn on_conflict(&mut self) {
+        self.conflicts += 1;
+    }
+
+    fn should_restart(&self) -> bool {
+        self.conflicts as f64 >= self.interval
+    }
+
+    fn restarted(&mut self) {
+        self.interval *= self.factor;
+        self.conflicts = 0;
+    }
+}
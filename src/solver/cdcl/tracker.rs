@@ -1,6 +1,7 @@
 use std::{
     cell::Cell,
     collections::BTreeSet,
+    mem::size_of,
     ops::{Index, IndexMut},
 };
 
@@ -266,6 +267,10 @@ struct TrackedClause {
     stat: ClauseStat,
     original: Clause,
     literals: TiVec<ClauseCol, WatchedLiteral>,
+    /// Whether the clause was learned during the search.
+    learned: bool,
+    /// Whether the clause was removed from the tracker.
+    removed: bool,
 }
 
 struct WatchedLiteral {
@@ -284,6 +289,8 @@ pub struct Tracker {
     watch: Watch,
     /// Inverse-map of watches.
     clauses: TiVec<ClauseIdx, TrackedClause>,
+    /// The number of removed clauses.
+    removed_count: usize,
     /// Faster lookup table for clauses.
     clause_cache: ClauseStateCache,
 }
@@ -296,6 +303,7 @@ impl Tracker {
             assigned_count: 0,
             watch: Watch::new(num_variables),
             clauses: TiVec::new(),
+            removed_count: 0,
             clause_cache: ClauseStateCache::new(),
         }
     }
@@ -303,17 +311,41 @@ impl Tracker {
     pub fn from_cnf(formula: &Cnf) -> Self {
         let mut tracker = Tracker::new(formula.num_variables());
         for clause in formula.clauses() {
-            tracker.add_clause(clause.clone());
+            tracker.add_clause(clause.clone(), false);
         }
         tracker
     }
 
-    pub fn add_clause(&mut self, clause: Clause) {
+    /// Adds a variable numbered after the existing variables.
+    pub fn add_variable(&mut self) {
+        self.num_variables += 1;
+        self.assignments.push(None);
+        self.watch.positive.push(TiVec::new());
+        self.watch.negative.push(TiVec::new());
+    }
+
+    /// Adds a clause and returns its index.
+    /// `learned` marks redundant clauses derived during the search.
+    pub fn add_clause(&mut self, clause: Clause, learned: bool) -> ClauseIdx {
+        let clause_index = self.clauses.push_and_get_key(TrackedClause {
+            stat: ClauseStat::new(clause.len(), 0, 0),
+            original: clause,
+            literals: TiVec::new(),
+            learned,
+            removed: false,
+        });
+        self.attach_clause(clause_index);
+
+        clause_index
+    }
+
+    /// Registers watches of the clause and computes its status from the current assignments.
+    fn attach_clause(&mut self, clause_index: ClauseIdx) {
         let mut satisfied = 0;
         let mut unsatisfied = 0;
 
         let mut literals = TiVec::new();
-        let clause_index = self.clauses.next_key();
+        let clause = &self.clauses[clause_index].original;
 
         for literal in clause.iter() {
             match literal.partial_value(&self.assignments) {
@@ -340,11 +372,53 @@ impl Tracker {
         let stat = ClauseStat::new(clause.len(), satisfied, unsatisfied);
         self.clause_cache[stat.status()].insert(clause_index);
 
-        self.clauses.push(TrackedClause {
-            stat,
-            original: clause,
-            literals,
-        });
+        let tracked_clause = &mut self.clauses[clause_index];
+        tracked_clause.stat = stat;
+        tracked_clause.literals = literals;
+    }
+
+    /// Restores a removed clause with the same index.
+    /// Panics if the clause is not removed.
+    pub fn restore_clause(&mut self, index: ClauseIdx) {
+        let tracked_clause = &mut self.clauses[index];
+        assert!(tracked_clause.removed);
+        tracked_clause.removed = false;
+        self.removed_count -= 1;
+
+        self.attach_clause(index);
+    }
+
+    /// Removes a clause from the tracker.
+    /// The index of the removed clause is not reused unless it is restored.
+    /// Panics if the clause is already removed.
+    pub fn remove_clause(&mut self, index: ClauseIdx) {
+        let tracked_clause = &mut self.clauses[index];
+        assert!(!tracked_clause.removed);
+        tracked_clause.removed = true;
+        assert!(self.clause_cache[tracked_clause.stat.status()].remove(&index));
+        self.removed_count += 1;
+
+        let original = tracked_clause.original.clone();
+        for literal in original.iter() {
+            let row = &mut self.watch[literal];
+            let variable_col = VariableCol(
+                row.iter()
+                    .position(|watch| watch.clause_idx == index)
+                    .unwrap(),
+            );
+            row.swap_remove(variable_col);
+
+            // Fix up the watch element that was moved into the hole
+            if let Some(moved) = self.watch[literal].get(variable_col) {
+                if let (false, Some(clause_col)) =
+                    (moved.clause_idx == index, moved.clause_col.get())
+                {
+                    self.clauses[moved.clause_idx].literals[clause_col].variable_col = variable_col;
+                }
+            }
+        }
+
+        self.clauses[index].literals.clear();
     }
 
     /// Get a reference to the tracker's assignments.
@@ -371,8 +445,54 @@ impl Tracker {
         self.num_variables
     }
 
+    /// Returns the number of clauses that are not removed.
     pub fn num_clauses(&self) -> usize {
-        self.clauses.len()
+        self.clauses.len() - self.removed_count
+    }
+
+    /// Returns an upper bound of clause indices, including removed clauses.
+    pub fn clause_index_bound(&self) -> ClauseIdx {
+        self.clauses.next_key()
+    }
+
+    pub fn is_removed(&self, index: ClauseIdx) -> bool {
+        self.clauses[index].removed
+    }
+
+    pub fn is_learned(&self, index: ClauseIdx) -> bool {
+        self.clauses[index].learned
+    }
+
+    /// Returns the clauses that are neither learned nor removed.
+    pub fn irredundant_clauses(&self) -> Vec<Clause> {
+        self.clauses
+            .iter()
+            .filter(|clause| !clause.learned && !clause.removed)
+            .map(|clause| clause.original.clone())
+            .collect()
+    }
+
+    /// Returns an estimate of the memory held by the clauses, watches and assignments in bytes.
+    pub fn memory_usage(&self) -> usize {
+        let clauses: usize = self
+            .clauses
+            .iter()
+            .map(|clause| {
+                size_of::<TrackedClause>()
+                    + clause.original.len() * size_of::<Literal>()
+                    + clause.literals.capacity() * size_of::<WatchedLiteral>()
+            })
+            .sum();
+        let watches: usize = self
+            .watch
+            .positive
+            .iter()
+            .chain(&self.watch.negative)
+            .map(|row| row.capacity() * size_of::<WatchElement>())
+            .sum();
+        // Each clause is in one set of the cache
+        let cache = self.clauses.len() * size_of::<ClauseIdx>();
+        clauses + watches + cache + self.assignments.capacity() * size_of::<Option<bool>>()
     }
 
     pub fn variable_occurrence(&self, variable: Variable) -> usize {
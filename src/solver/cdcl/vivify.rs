@@ -0,0 +1,157 @@
+/*!
+Clause vivification.
+
+A clause `(l1 ∨ l2 ∨ ... ∨ ln)` is vivified by assigning `¬l1, ¬l2, ...` one by one
+and propagating them with the rest of the formula.
+- If a conflict occurs after assigning `¬l1 ... ¬lk`, `(l1 ∨ ... ∨ lk)` is implied.
+- If some `li` becomes true, `(l1 ∨ ... ∨ lk ∨ li)` is implied for the decided prefix.
+- If some `li` becomes false, it can be dropped from the clause.
+
+If the whole clause is implied without shortening, it is redundant and deleted.
+*/
+
+use std::cmp::Ordering;
+
+use crate::formula::{Clause, Literal};
+
+use super::{tracker::ClauseIdx, CdclSolver, DecisionReason};
+
+/// Tracks which clauses to vivify on each restart.
+pub struct Vivifier {
+    /// Index of the next candidate clause (round-robin over all clauses).
+    cursor: usize,
+}
+
+enum Outcome {
+    /// The clause is implied by the rest of the formula and can be deleted.
+    Redundant,
+    /// The clause can be replaced with a shorter one.
+    Shortened(Clause),
+    /// No improvement.
+    Unchanged,
+}
+
+impl Vivifier {
+    /// The number of clauses examined on each restart.
+    const CLAUSES_PER_ROUND: usize = 64;
+    /// Binary clauses are never shortened in practice.
+    const MIN_CLAUSE_LEN: usize = 3;
+
+    pub fn new() -> Self {
+        Vivifier { cursor: 0 }
+    }
+}
+
+impl CdclSolver {
+    /// Vivifies a batch of clauses at the root level.
+    pub(super) fn vivify(&mut self) {
+        debug_assert_eq!(self.current_level(), 0);
+
+        let bound: usize = self.tracker.clause_index_bound().into();
+        if bound == 0 {
+            return;
+        }
+
+        let mut examined = 0;
+        let mut visited = 0;
+        while examined < Vivifier::CLAUSES_PER_ROUND && visited < bound {
+            // Root level conflict and interrupts are handled by the main loop
+            if self.interrupt.is_interrupted() || self.propagate().is_some() {
+                return;
+            }
+
+            let clause_idx = ClauseIdx::from(self.vivifier.cursor % bound);
+            self.vivifier.cursor = (self.vivifier.cursor + 1) % bound;
+            visited += 1;
+
+            if self.is_vivify_candidate(clause_idx) {
+                examined += 1;
+                self.vivify_clause(clause_idx);
+            }
+        }
+    }
+
+    fn is_vivify_candidate(&self, clause_idx: ClauseIdx) -> bool {
+        if self.tracker.is_removed(clause_idx) {
+            return false;
+        }
+
+        let clause = self.tracker.original_clause(clause_idx);
+        let assignments = self.tracker.assignments();
+        clause.len() >= Vivifier::MIN_CLAUSE_LEN
+            && !clause
+                .iter()
+                .any(|literal| literal.partial_value(assignments) == Some(true))
+    }
+
+    fn vivify_clause(&mut self, clause_idx: ClauseIdx) {
+        let clause = self.tracker.original_clause(clause_idx).clone();
+        let learned = self.tracker.is_learned(clause_idx);
+
+        // Propagate without the clause itself, otherwise it always becomes unit
+        self.tracker.remove_clause(clause_idx);
+
+        // Try the most active literals first
+        let mut literals: Vec<Literal> = clause.iter().collect();
+        literals.sort_by(|a, b| {
+            let score_a = self.score_heuristic.activity(a.variable());
+            let score_b = self.score_heuristic.activity(b.variable());
+            score_b.partial_cmp(&score_a).unwrap_or(Ordering::Equal)
+        });
+
+        let outcome = self.vivify_literals(&literals);
+        self.rewind(0);
+
+        match outcome {
+            Outcome::Redundant => {
+                trace!("Vivify delete {}", clause);
+                self.stats.deleted_clauses += 1;
+            }
+            Outcome::Shortened(shortened) => {
+                trace!("Vivify {} => {}", clause, shortened);
+                self.stats.deleted_clauses += 1;
+                self.stats.vivified_literals += clause.len() - shortened.len();
+                self.tracker.add_clause(shortened, learned);
+            }
+            Outcome::Unchanged => {
+                self.tracker.restore_clause(clause_idx);
+            }
+        }
+    }
+
+    fn vivify_literals(&mut self, literals: &[Literal]) -> Outcome {
+        let mut decided = Vec::new();
+
+        for &literal in literals {
+            match literal.partial_value(self.tracker.assignments()) {
+                Some(true) => {
+                    decided.push(literal);
+                    return if decided.len() < literals.len() {
+                        Outcome::Shortened(Clause::new(decided))
+                    } else {
+                        Outcome::Redundant
+                    };
+                }
+                // Implied to be false, drop it
+                Some(false) => (),
+                None => {
+                    decided.push(literal);
+                    self.push_decision(!literal, DecisionReason::Decision);
+                    if self.propagate().is_some() {
+                        return if decided.len() < literals.len() {
+                            Outcome::Shortened(Clause::new(decided))
+                        } else {
+                            Outcome::Redundant
+                        };
+                    }
+                }
+            }
+        }
+
+        if decided.len() < literals.len() {
+            Outcome::Shortened(Clause::new(decided))
+        } else {
+            Outcome::Unchanged
+        }
+    }
+}
@@ -0,0 +1,103 @@
+/*!
+Local search for phase initialization.
+
+On rephasing, a bounded ProbSAT walk over the irredundant clauses starts from
+the saved phases. Root level assignments are fixed: the walk starts from them and
+never flips them, as clauses they satisfy are dropped and their false literals removed.
+The assignment with the fewest falsified clauses seen during the walk
+becomes the target phase, so the following decisions try to reproduce it.
+If the walk satisfies every clause, the search finds a model without conflicts.
+
+The walk only sees clauses, so it is disabled for formulas with parity, cardinality
+or pseudo-Boolean constraints, whose target phases could violate them.
+*/
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    formula::Clause,
+    solver::local_search::{LocalSearchAlgorithm, SearchState},
+};
+
+use super::CdclSolver;
+
+/// Random state of walks, kept across rephasings.
+pub struct Walker {
+    rng: StdRng,
+}
+
+impl Walker {
+    /// Flips per irredundant clause in a walk.
+    const FLIPS_PER_CLAUSE: usize = 10;
+    const MIN_FLIPS: usize = 10_000;
+    const MAX_FLIPS: usize = 1_000_000;
+
+    pub fn new(seed: u64) -> Self {
+        Walker {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl CdclSolver {
+    /// Runs a walk from the saved phases and sets the best assignment as the target phase.
+    pub(super) fn walk(&mut self) {
+        debug_assert_eq!(self.current_level(), 0);
+
+        let assignments = self.tracker.assignments();
+        let mut clauses = Vec::new();
+        for clause in self.tracker.irredundant_clauses() {
+            if clause
+                .iter()
+                .any(|literal| literal.partial_value(assignments) == Some(true))
+            {
+                continue;
+            }
+            let unassigned: Vec<_> = clause
+                .iter()
+                .filter(|literal| literal.partial_value(assignments).is_none())
+                .collect();
+            // Root level conflict is handled by the main loop
+            if unassigned.is_empty() {
+                return;
+            }
+            clauses.push(Clause::new(unassigned));
+        }
+
+        let num_variables = self.tracker.num_variables();
+        let mut state = SearchState::new(num_variables, &clauses);
+
+        let initial = self
+            .tracker
+            .assignments()
+            .iter()
+            .zip(self.phases.saved())
+            .map(|(assignment, &saved)| assignment.unwrap_or(saved))
+            .collect();
+        state.reset(initial);
+
+        let max_flips =
+            (clauses.len() * Walker::FLIPS_PER_CLAUSE).clamp(Walker::MIN_FLIPS, Walker::MAX_FLIPS);
+        let rng = &mut self.walker.as_mut().unwrap().rng;
+        let mut best = state.assignment().to_vec();
+        let mut best_falsified = state.falsified().len();
+        for _ in 0..max_flips {
+            let falsified = state.falsified();
+            if falsified.is_empty() || self.interrupt.is_interrupted() {
+                break;
+            }
+
+            let clause_index = falsified[rng.gen_range(0..falsified.len())];
+            let variable = state.pick(LocalSearchAlgorithm::PROBSAT, clause_index, rng);
+            state.flip(variable);
+
+            if state.falsified().len() < best_falsified {
+                best_falsified = state.falsified().len();
+                best.copy_from_slice(state.assignment());
+            }
+        }
+
+        debug!("Walk: {} falsified clauses", best_falsified);
+        self.phases.set_target(best);
+    }
+}
@@ -0,0 +1,443 @@
+/*!
+Cube-and-conquer.
+
+The cuber splits a formula into cubes, conjunctions of literals whose disjunction
+covers every satisfying assignment. It builds a decision tree with lookahead:
+on each node, every candidate literal is tentatively assigned and propagated.
+A literal whose propagation fails is refuted, so its negation is implied.
+Otherwise, the reduction of the formula it causes is measured, and the variable
+maximizing the product of the reductions of both polarities is branched on,
+as in the march solver. Nodes refuted by propagation are not emitted.
+
+The conquer phase solves the cubes in parallel, each worker running an incremental
+`CdclSolver` with the cubes as assumptions. The formula is satisfiable iff any cube is.
+*/
+
+use std::{
+    io::{self, Write},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::formula::{Cnf, Literal, Variable};
+
+use super::{pool::run_workers, CdclConfig, CdclSolver, Interrupt, SolveResult, Solver};
+
+/// Tunable options of cube-and-conquer.
+#[derive(Debug, Clone)]
+pub struct CubeConfig {
+    /// The maximum number of decisions in a cube.
+    pub max_depth: usize,
+    /// The number of variables examined with lookahead on each node.
+    pub max_candidates: usize,
+    /// The number of conquer threads.
+    pub threads: usize,
+    /// Configuration of the conquer solvers.
+    pub cdcl: CdclConfig,
+}
+
+impl Default for CubeConfig {
+    fn default() -> Self {
+        CubeConfig {
+            max_depth: 8,
+            max_candidates: 32,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            cdcl: CdclConfig::default(),
+        }
+    }
+}
+
+fn literal_code(literal: Literal) -> usize {
+    literal.index() * 2 + literal.positive() as usize
+}
+
+/// The decision of a lookahead on a node.
+enum Lookahead {
+    /// Branch on the variable.
+    Branch(Variable),
+    /// No candidate is left, emit the node as a cube.
+    Leaf,
+    /// The node is refuted by failed literals.
+    Refuted,
+}
+
+/// Lookahead state with a simple occurrence-list propagation.
+struct Cuber<'a> {
+    formula: &'a Cnf,
+    /// The variables below this index are branched on,
+    /// the others are auxiliary variables of encoded constraints.
+    num_branch_variables: usize,
+    config: &'a CubeConfig,
+    /// Literal code -> indices of clauses containing the literal.
+    occurrences: Vec<Vec<usize>>,
+    assignment: Vec<Option<bool>>,
+    trail: Vec<Literal>,
+    /// Clause index -> the last lookahead that counted its reduction.
+    reduced_stamp: Vec<usize>,
+    stamp: usize,
+    cubes: Vec<Vec<Literal>>,
+}
+
+impl<'a> Cuber<'a> {
+    /// Weight of a clause with `k` unassigned literals is `REDUCTION_WEIGHT^(k - 2)`,
+    /// so newly created binary clauses count the most.
+    const REDUCTION_WEIGHT: f64 = 0.2;
+    /// Factor on the product of both polarities' reductions.
+    const PRODUCT_WEIGHT: f64 = 1024.0;
+
+    fn new(formula: &'a Cnf, num_branch_variables: usize, config: &'a CubeConfig) -> Self {
+        let num_variables = formula.num_variables();
+        let mut occurrences = vec![Vec::new(); num_variables * 2];
+        for (clause_index, clause) in formula.clauses().iter().enumerate() {
+            for literal in clause.iter() {
+                occurrences[literal_code(literal)].push(clause_index);
+            }
+        }
+
+        Cuber {
+            formula,
+            num_branch_variables,
+            config,
+            occurrences,
+            assignment: vec![None; num_variables],
+            trail: Vec::new(),
+            reduced_stamp: vec![0; formula.clauses().len()],
+            stamp: 0,
+            cubes: Vec::new(),
+        }
+    }
+
+    fn value(&self, literal: Literal) -> Option<bool> {
+        literal.partial_value(&self.assignment)
+    }
+
+    /// Assigns the literal and propagates it.
+    /// Returns `false` on conflict; the assignments stay on the trail either way.
+    fn assign(&mut self, literal: Literal) -> bool {
+        match self.value(literal) {
+            Some(value) => return value,
+            None => {
+                self.assignment[literal.index()] = Some(literal.positive());
+                self.trail.push(literal);
+            }
+        }
+
+        let mut head = self.trail.len() - 1;
+        while head < self.trail.len() {
+            let falsified = !self.trail[head];
+            head += 1;
+
+            for i in 0..self.occurrences[literal_code(falsified)].len() {
+                let clause_index = self.occurrences[literal_code(falsified)][i];
+                let mut unit = None;
+                let mut unassigned = 0;
+                let mut satisfied = false;
+                for literal in self.formula.clauses()[clause_index].iter() {
+                    match self.value(literal) {
+                        Some(true) => {
+                            satisfied = true;
+                            break;
+                        }
+                        Some(false) => (),
+                        None => {
+                            unassigned += 1;
+                            unit = Some(literal);
+                        }
+                    }
+                }
+
+                if satisfied {
+                    continue;
+                }
+                match unassigned {
+                    0 => return false,
+                    1 => {
+                        let unit = unit.unwrap();
+                        self.assignment[unit.index()] = Some(unit.positive());
+                        self.trail.push(unit);
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Undoes assignments until the trail length becomes `len`.
+    fn backtrack(&mut self, len: usize) {
+        for literal in self.trail.drain(len..) {
+            self.assignment[literal.index()] = None;
+        }
+    }
+
+    /// Measures how much the assignments on `trail[from..]` reduced the formula.
+    fn reduction(&mut self, from: usize) -> f64 {
+        self.stamp += 1;
+        let mut reduction = 0.0;
+        for position in from..self.trail.len() {
+            let falsified = !self.trail[position];
+            for &clause_index in &self.occurrences[literal_code(falsified)] {
+                if self.reduced_stamp[clause_index] == self.stamp {
+                    continue;
+                }
+                self.reduced_stamp[clause_index] = self.stamp;
+
+                let clause = &self.formula.clauses()[clause_index];
+                let mut unassigned = 0;
+                let mut satisfied = false;
+                for literal in clause.iter() {
+                    match literal.partial_value(&self.assignment) {
+                        Some(true) => satisfied = true,
+                        Some(false) => (),
+                        None => unassigned += 1,
+                    }
+                }
+                if !satisfied && unassigned >= 2 {
+                    reduction += Self::REDUCTION_WEIGHT.powi(unassigned - 2);
+                }
+            }
+        }
+        reduction
+    }
+
+    /// Unassigned variables that occur the most in short clauses.
+    fn candidates(&self) -> Vec<Variable> {
+        let mut scores = vec![0.0; self.formula.num_variables()];
+        for clause in self.formula.clauses() {
+            if clause
+                .iter()
+                .any(|literal| self.value(literal) == Some(true))
+            {
+                continue;
+            }
+            let unassigned = clause
+                .iter()
+                .filter(|&literal| self.value(literal).is_none())
+                .count();
+            let weight = Self::REDUCTION_WEIGHT.powi(unassigned as i32 - 2);
+            for literal in clause.iter() {
+                scores[literal.index()] += weight;
+            }
+        }
+
+        let mut candidates: Vec<_> = (0..self.num_branch_variables)
+            .filter(|&index| self.assignment[index].is_none() && scores[index] > 0.0)
+            .collect();
+        candidates.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).unwrap());
+        candidates.truncate(self.config.max_candidates);
+        candidates
+            .into_iter()
+            .map(|index| Variable::from_index(index).unwrap())
+            .collect()
+    }
+
+    /// Runs lookahead on the candidates and picks the variable to branch on.
+    fn lookahead(&mut self) -> Lookahead {
+        let mut best = None;
+        let mut best_score = -1.0;
+
+        for variable in self.candidates() {
+            if self.assignment[variable.index()].is_some() {
+                // Implied by a failed literal
+                continue;
+            }
+
+            let mut reductions = [0.0; 2];
+            for (reduction, positive) in reductions.iter_mut().zip([true, false]) {
+                let literal = Literal::new(variable, positive);
+                let mark = self.trail.len();
+                if self.assign(literal) {
+                    *reduction = self.reduction(mark);
+                    self.backtrack(mark);
+                } else {
+                    // Failed literal, its negation is implied on this node
+                    self.backtrack(mark);
+                    if !self.assign(!literal) {
+                        return Lookahead::Refuted;
+                    }
+                    break;
+                }
+            }
+
+            if self.assignment[variable.index()].is_some() {
+                continue;
+            }
+            let [positive, negative] = reductions;
+            let score = Self::PRODUCT_WEIGHT * positive * negative + positive + negative;
+            if score > best_score {
+                best = Some(variable);
+                best_score = score;
+            }
+        }
+
+        match best {
+            Some(variable) => Lookahead::Branch(variable),
+            None => Lookahead::Leaf,
+        }
+    }
+
+    fn split(&mut self, cube: &mut Vec<Literal>) {
+        if cube.len() < self.config.max_depth {
+            let variable = match self.lookahead() {
+                Lookahead::Branch(variable) => variable,
+                Lookahead::Leaf => {
+                    self.cubes.push(cube.clone());
+                    return;
+                }
+                Lookahead::Refuted => return,
+            };
+
+            for positive in [true, false] {
+                let literal = Literal::new(variable, positive);
+                let mark = self.trail.len();
+                if self.assign(literal) {
+                    cube.push(literal);
+                    self.split(cube);
+                    cube.pop();
+                }
+                self.backtrack(mark);
+            }
+        } else {
+            self.cubes.push(cube.clone());
+        }
+    }
+}
+
+/// Splits the formula into cubes with lookahead.
+/// Returns no cube if the formula is refuted during the split.
+pub fn cube(formula: &Cnf, config: &CubeConfig) -> Vec<Vec<Literal>> {
+    // Lookahead only propagates clauses. The cubes stay over the variables of the formula,
+    // so that they can be solved with the constraints propagated natively.
+    let encoded = formula.encode_constraints();
+    let mut cuber = Cuber::new(&encoded, formula.num_variables(), config);
+
+    for clause in encoded.clauses() {
+        if clause.len() == 1 && !cuber.assign(clause.as_slice()[0]) {
+            return Vec::new();
+        }
+    }
+
+    cuber.split(&mut Vec::new());
+    debug!("Split into {} cubes", cuber.cubes.len());
+    cuber.cubes
+}
+
+/// Writes the formula and the cubes in the iCNF format.
+///
+/// # Errors
+///
+/// Fails with `InvalidInput` if the formula has parity, cardinality
+/// or pseudo-Boolean constraints, which iCNF cannot express.
+pub fn write_icnf(
+    writer: &mut impl Write,
+    formula: &Cnf,
+    cubes: &[Vec<Literal>],
+) -> io::Result<()> {
+    if !formula.xors().is_empty()
+        || !formula.cardinalities().is_empty()
+        || !formula.pbs().is_empty()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "iCNF can only express clauses",
+        ));
+    }
+
+    writeln!(writer, "p inccnf")?;
+    for clause in formula.clauses() {
+        for literal in clause.iter() {
+            write!(writer, "{} ", literal.dimacs())?;
+        }
+        writeln!(writer, "0")?;
+    }
+    for cube in cubes {
+        write!(writer, "a ")?;
+        for literal in cube {
+            write!(writer, "{} ", literal.dimacs())?;
+        }
+        writeln!(writer, "0")?;
+    }
+    Ok(())
+}
+
+pub struct CubeAndConquerSolver {
+    formula: Cnf,
+    config: CubeConfig,
+    /// Cubes to solve, computed by `solve` if not given.
+    cubes: Option<Vec<Vec<Literal>>>,
+    /// Stops the search when triggered
+    interrupt: Interrupt,
+}
+
+impl CubeAndConquerSolver {
+    /// Creates a new solver instance with the given options.
+    pub fn with_config(formula: Cnf, config: CubeConfig) -> Self {
+        CubeAndConquerSolver {
+            formula,
+            config,
+            cubes: None,
+            interrupt: Interrupt::new(),
+        }
+    }
+
+    /// Creates a solver that conquers the given cubes instead of splitting the formula.
+    /// The cubes must cover every satisfying assignment of the formula.
+    /// No cube stands for the empty cube, as in an iCNF file without assumptions,
+    /// so the whole formula is solved.
+    pub fn with_cubes(formula: Cnf, mut cubes: Vec<Vec<Literal>>, config: CubeConfig) -> Self {
+        if cubes.is_empty() {
+            cubes.push(Vec::new());
+        }
+        CubeAndConquerSolver {
+            cubes: Some(cubes),
+            ..CubeAndConquerSolver::with_config(formula, config)
+        }
+    }
+}
+
+impl Solver for CubeAndConquerSolver {
+    fn new(formula: Cnf) -> Self {
+        CubeAndConquerSolver::with_config(formula, CubeConfig::default())
+    }
+
+    fn set_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt = interrupt;
+    }
+
+    fn solve(mut self) -> SolveResult {
+        let cubes = match self.cubes.take() {
+            Some(cubes) => cubes,
+            // No cube means that the split refuted the formula
+            None => cube(&self.formula, &self.config),
+        };
+
+        let next_cube = AtomicUsize::new(0);
+        let num_threads = self.config.threads.clamp(1, cubes.len().max(1));
+        let workers = (0..num_threads)
+            .map(|_| {
+                let (formula, config) = (self.formula.clone(), self.config.cdcl.clone());
+                let (cubes, next_cube) = (&cubes, &next_cube);
+                // Workers take cubes in order and stop at the first non-refuted one
+                move |cancel| {
+                    let mut solver = CdclSolver::with_config(formula, config);
+                    solver.set_interrupt(cancel);
+                    while let Some(cube) = cubes.get(next_cube.fetch_add(1, Ordering::Relaxed)) {
+                        let result = solver.solve_with_assumptions(cube);
+                        if !result.is_unsat() {
+                            return result;
+                        }
+                    }
+                    SolveResult::Unsat
+                }
+            })
+            .collect();
+
+        match run_workers(workers, &self.interrupt, SolveResult::is_sat) {
+            Some(model) => model,
+            None if self.interrupt.is_interrupted() => SolveResult::Unknown,
+            // Every cube is refuted if no worker finds a model
+            None => SolveResult::Unsat,
+        }
+    }
+}
@@ -1,8 +1,17 @@
+use std::{mem::size_of, time::Instant};
+
 use crate::formula::{Cnf, Literal, Model, Variable};
 
-use self::inner::Watch;
+use self::{
+    heuristic::DecisionDataProvider,
+    inner::{LiteralMap, Watch},
+};
+
+use super::{Interrupt, SolveResult, Solver, Statistics};
 
-use super::Solver;
+pub use self::heuristic::DpllHeuristic;
+
+mod heuristic;
 
 /// Internal modules whose implementation details are hidden from the solver.
 mod inner {
@@ -10,18 +19,28 @@ mod inner {
 
     use crate::formula::{Clause, Literal};
 
+    /// A map from literals to values.
     #[derive(Debug)]
-    pub struct Watch {
-        positive: Vec<Vec<usize>>,
-        negative: Vec<Vec<usize>>,
+    pub struct LiteralMap<T> {
+        positive: Vec<T>,
+        negative: Vec<T>,
+    }
+
+    impl<T: Clone> LiteralMap<T> {
+        pub fn new(num_variables: usize, value: T) -> Self {
+            LiteralMap {
+                positive: vec![value.clone(); num_variables],
+                negative: vec![value; num_variables],
+            }
+        }
     }
 
+    /// Maps literals to the indices of clauses that contain them.
+    pub type Watch = LiteralMap<Vec<usize>>;
+
     impl Watch {
-        pub fn new(clauses: &[Clause]) -> Self {
-            let mut watch = Self {
-                positive: vec![Vec::new(); clauses.len()],
-                negative: vec![Vec::new(); clauses.len()],
-            };
+        pub fn from_clauses(num_variables: usize, clauses: &[Clause]) -> Self {
+            let mut watch = Self::new(num_variables, Vec::new());
 
             for (idx, clause) in clauses.iter().enumerate() {
                 for literal in clause.iter() {
@@ -33,8 +52,8 @@ mod inner {
         }
     }
 
-    impl Index<Literal> for Watch {
-        type Output = Vec<usize>;
+    impl<T> Index<Literal> for LiteralMap<T> {
+        type Output = T;
 
         fn index(&self, literal: Literal) -> &Self::Output {
             if literal.positive() {
@@ -45,7 +64,7 @@ mod inner {
         }
     }
 
-    impl IndexMut<Literal> for Watch {
+    impl<T> IndexMut<Literal> for LiteralMap<T> {
         fn index_mut(&mut self, literal: Literal) -> &mut Self::Output {
             if literal.positive() {
                 &mut self.positive[literal.index()]
@@ -64,8 +83,22 @@ struct ClauseStat {
     unsatisfied: usize,
 }
 
+/// A decision marker on the trail.
+#[derive(Debug)]
+struct Branch {
+    /// The length of the trail before the decision.
+    trail_len: usize,
+    /// The decided literal.
+    literal: Literal,
+    /// Whether the opposite literal was already tried.
+    flipped: bool,
+}
+
 #[derive(Debug)]
 pub struct DpllSolver {
+    /// The formula given by the user.
+    original: Cnf,
+    /// The formula to solve, with parity, cardinality and pseudo-Boolean constraints encoded as clauses.
     formula: Cnf,
     watch: Watch,
     /// Variable index -> assigned status
@@ -76,7 +109,23 @@ pub struct DpllSolver {
     satisfied_clauses: usize,
     /// Cache for `clauses.count(unsatisfied_literals == clause.num_literals)`
     unsatisfied_clauses: usize,
-    assigned_stack: Vec<Literal>,
+    /// Literal -> the number of unresolved clauses that contain the literal
+    unresolved_occurrences: LiteralMap<usize>,
+    /// Assigned literals in order
+    trail: Vec<Literal>,
+    /// Decisions on the trail
+    branches: Vec<Branch>,
+    /// Clauses that may have become unit
+    unit_queue: Vec<usize>,
+    /// Literals that may have become pure
+    pure_queue: Vec<Literal>,
+    heuristic: DpllHeuristic,
+    /// Stops the search when triggered
+    interrupt: Interrupt,
+    /// Counters of the search
+    stats: Statistics,
+    /// Whether propagation is timed, which costs two clock reads per step
+    time_propagation: bool,
 }
 
 impl DpllSolver {
@@ -96,67 +145,74 @@ impl DpllSolver {
         }
     }
 
-    /// Finds the next unit clause if exists and returns the forced literal.
-    fn search_unit_clause(&self) -> Option<Literal> {
-        for clause_index in 0..self.formula.clauses().len() {
-            if let Some(literal) = self.forced_assignment(clause_index) {
-                return Some(literal);
-            }
-        }
-
-        None
+    /// Returns true if the literal can be set without falsifying any unresolved clause.
+    fn is_pure(&self, literal: Literal) -> bool {
+        literal.partial_value(&self.assignment).is_none()
+            && self.unresolved_occurrences[!literal] == 0
+            && self.unresolved_occurrences[literal] > 0
     }
 
-    fn first_unassigned(&self) -> Variable {
-        let index = self
-            .assignment
-            .iter()
-            .position(|assigned| assigned.is_none())
-            .unwrap();
-
-        Variable::from_index(index).unwrap()
+    /// Updates occurrence counters of literals in a clause that became (un)resolved.
+    fn update_occurrences(&mut self, clause_index: usize, resolved: bool) {
+        for literal in self.formula.clauses()[clause_index].iter() {
+            let count = &mut self.unresolved_occurrences[literal];
+            if resolved {
+                *count -= 1;
+                if *count == 0 {
+                    self.pure_queue.push(!literal);
+                }
+            } else {
+                *count += 1;
+            }
+        }
     }
 
     fn assign_literal(&mut self, literal: Literal) {
-        self.assigned_stack.push(literal);
+        self.trail.push(literal);
         self.assignment[literal.index()] = Some(literal.positive());
 
-        for &clause_index in &self.watch[literal] {
-            let mut stat = &mut self.clause_stats[clause_index];
+        for i in 0..self.watch[literal].len() {
+            let clause_index = self.watch[literal][i];
+            let stat = &mut self.clause_stats[clause_index];
 
-            if stat.satisfied == 0 {
+            stat.satisfied += 1;
+            if stat.satisfied == 1 {
                 self.satisfied_clauses += 1;
+                self.update_occurrences(clause_index, true);
             }
-            stat.satisfied += 1;
         }
 
         for &clause_index in &self.watch[!literal] {
             let clause = &self.formula.clauses()[clause_index];
-            let mut stat = &mut self.clause_stats[clause_index];
+            let stat = &mut self.clause_stats[clause_index];
 
             stat.unsatisfied += 1;
             if stat.unsatisfied == clause.len() {
                 self.unsatisfied_clauses += 1;
+            } else if stat.satisfied == 0 && stat.unsatisfied + 1 == clause.len() {
+                self.unit_queue.push(clause_index);
             }
         }
     }
 
     fn pop_assignment(&mut self) {
-        let literal = self.assigned_stack.pop().unwrap();
+        let literal = self.trail.pop().unwrap();
         self.assignment[literal.index()] = None;
 
-        for &clause_index in &self.watch[literal] {
-            let mut stat = &mut self.clause_stats[clause_index];
+        for i in 0..self.watch[literal].len() {
+            let clause_index = self.watch[literal][i];
+            let stat = &mut self.clause_stats[clause_index];
 
-            if stat.satisfied == 1 {
+            stat.satisfied -= 1;
+            if stat.satisfied == 0 {
                 self.satisfied_clauses -= 1;
+                self.update_occurrences(clause_index, false);
             }
-            stat.satisfied -= 1;
         }
 
         for &clause_index in &self.watch[!literal] {
             let clause = &self.formula.clauses()[clause_index];
-            let mut stat = &mut self.clause_stats[clause_index];
+            let stat = &mut self.clause_stats[clause_index];
 
             if stat.unsatisfied == clause.len() {
                 self.unsatisfied_clauses -= 1;
@@ -164,78 +220,201 @@ impl DpllSolver {
             stat.unsatisfied -= 1;
         }
     }
+
+    /// Assigns unit and pure literals until no more can be found or a conflict occurs.
+    fn propagate(&mut self) {
+        while self.unsatisfied_clauses == 0 {
+            if let Some(clause_index) = self.unit_queue.pop() {
+                if let Some(literal) = self.forced_assignment(clause_index) {
+                    self.assign_literal(literal);
+                    self.stats.propagations += 1;
+                }
+            } else if let Some(literal) = self.pure_queue.pop() {
+                if self.is_pure(literal) {
+                    self.assign_literal(literal);
+                    self.stats.propagations += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Undoes assignments up to the last decision that has an untried branch,
+    /// and tries the opposite literal. Returns false if there is no such decision.
+    fn backtrack(&mut self) -> bool {
+        // Propagation was complete before each decision, so pending work is stale
+        self.unit_queue.clear();
+        self.pure_queue.clear();
+
+        while let Some(branch) = self.branches.pop() {
+            while self.trail.len() > branch.trail_len {
+                self.pop_assignment();
+            }
+
+            if !branch.flipped {
+                self.branches.push(Branch {
+                    trail_len: branch.trail_len,
+                    literal: !branch.literal,
+                    flipped: true,
+                });
+                self.assign_literal(!branch.literal);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Returns an estimate of the memory held by the clauses, watches and assignments in bytes.
+    fn memory_usage(&self) -> usize {
+        let literals: usize = self
+            .formula
+            .clauses()
+            .iter()
+            .map(|clause| clause.len())
+            .sum();
+        let clauses = self.formula.clauses().len();
+        let variables = self.formula.num_variables();
+        // Each literal occurrence is also in a watch list
+        literals * (size_of::<Literal>() + size_of::<usize>())
+            + clauses * size_of::<ClauseStat>()
+            + variables * (size_of::<Option<bool>>() + 2 * size_of::<usize>())
+    }
+
+    fn search(&mut self) -> SolveResult {
+        loop {
+            if self.interrupt.is_interrupted() {
+                return SolveResult::Unknown;
+            }
+
+            let start = self.time_propagation.then(Instant::now);
+            self.propagate();
+            if let Some(start) = start {
+                self.stats.timings.propagation += start.elapsed();
+            }
+
+            if self.unsatisfied_clauses > 0 {
+                // There is a clause that can be never satisfied.
+                self.stats.conflicts += 1;
+                if !self.backtrack() {
+                    return SolveResult::Unsat;
+                }
+            } else if self.satisfied_clauses == self.formula.clauses().len() {
+                // All clauses are satisfied, fill remaining variables and return.
+                let assignment = self
+                    .assignment
+                    .iter()
+                    .map(|assign| assign.unwrap_or(true))
+                    .collect::<Vec<_>>();
+                return SolveResult::Sat(Model::from_extended(self.original.clone(), &assignment));
+            } else {
+                // Branch on the literal chosen by the heuristic.
+                let literal = self.heuristic.choose(self);
+                self.branches.push(Branch {
+                    trail_len: self.trail.len(),
+                    literal,
+                    flipped: false,
+                });
+                self.assign_literal(literal);
+                self.stats.decisions += 1;
+                self.stats.max_decision_level =
+                    self.stats.max_decision_level.max(self.branches.len());
+            }
+        }
+    }
 }
+impl DecisionDataProvider for DpllSolver {
+    fn num_variables(&self) -> usize {
+        self.formula.num_variables()
+    }
 
-impl Solver for DpllSolver {
-    fn new(formula: Cnf) -> Self {
+    fn is_assigned(&self, variable: Variable) -> bool {
+        variable.partial_value(&self.assignment).is_some()
+    }
+
+    fn unresolved_clause_sizes(&self, literal: Literal, sizes: &mut Vec<usize>) {
+        for &clause_index in &self.watch[literal] {
+            let stat = &self.clause_stats[clause_index];
+            if stat.satisfied == 0 {
+                let clause = &self.formula.clauses()[clause_index];
+                sizes.push(clause.len() - stat.unsatisfied);
+            }
+        }
+    }
+}
+
+impl DpllSolver {
+    /// Creates a new solver instance with the given branching heuristic.
+    pub fn with_heuristic(original: Cnf, heuristic: DpllHeuristic) -> Self {
+        let formula = original.encode_constraints();
         let num_variables = formula.num_variables();
         let num_clauses = formula.clauses().len();
 
-        let watch = Watch::new(formula.clauses());
+        let watch = Watch::from_clauses(num_variables, formula.clauses());
         let assignment = vec![None; num_variables];
         let clause_stats = vec![Default::default(); num_clauses];
 
+        let mut unresolved_occurrences = LiteralMap::new(num_variables, 0);
+        for clause in formula.clauses() {
+            for literal in clause.iter() {
+                unresolved_occurrences[literal] += 1;
+            }
+        }
+
+        // Initially, unit clauses and pure literals are pending
+        let unit_queue = formula
+            .clauses()
+            .iter()
+            .enumerate()
+            .filter(|(_, clause)| clause.len() == 1)
+            .map(|(clause_index, _)| clause_index)
+            .collect();
+        let pure_queue = (0..num_variables)
+            .map(|index| Variable::from_index(index).unwrap())
+            .flat_map(|variable| vec![Literal::new(variable, true), Literal::new(variable, false)])
+            .collect();
+
         DpllSolver {
+            original,
             formula,
             watch,
             assignment,
             clause_stats,
             satisfied_clauses: 0,
             unsatisfied_clauses: 0,
-            assigned_stack: Vec::with_capacity(num_variables),
+            unresolved_occurrences,
+            trail: Vec::with_capacity(num_variables),
+            branches: Vec::new(),
+            unit_queue,
+            pure_queue,
+            heuristic,
+            interrupt: Interrupt::new(),
+            stats: Statistics::default(),
+            time_propagation: false,
         }
     }
+}
 
-    fn solve(mut self) -> Option<Model> {
-        fn solve_inner(solver: &mut DpllSolver) -> Option<Vec<bool>> {
-            if solver.satisfied_clauses == solver.formula.clauses().len() {
-                // All clauses are satisfied, fill remaining variables and return.
-                let assignment = solver
-                    .assignment
-                    .iter()
-                    .map(|assign| assign.unwrap_or(true))
-                    .collect::<Vec<_>>();
-
-                return Some(assignment);
-            } else if solver.unsatisfied_clauses > 0 {
-                // There is a clause that can be never satisfied.
-                return None;
-            }
-
-            // We need to explore more.
-
-            // See if there is a unit assignment.
-            if let Some(literal) = solver.search_unit_clause() {
-                solver.assign_literal(literal);
-                if let Some(assignment) = solve_inner(solver) {
-                    return Some(assignment);
-                }
-                solver.pop_assignment();
-
-                None
-            } else {
-                // Try the first unassigned variable.
-                // Note: This is an inefficient heuristics.
-                let variable = solver.first_unassigned();
-                let literal = Literal::new(variable, true);
-
-                solver.assign_literal(literal);
-                if let Some(assignment) = solve_inner(solver) {
-                    return Some(assignment);
-                }
-                solver.pop_assignment();
+impl Solver for DpllSolver {
+    fn new(formula: Cnf) -> Self {
+        DpllSolver::with_heuristic(formula, DpllHeuristic::default())
+    }
 
-                solver.assign_literal(!literal);
-                if let Some(assignment) = solve_inner(solver) {
-                    return Some(assignment);
-                }
-                solver.pop_assignment();
+    fn set_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt = interrupt;
+    }
 
-                None
-            }
-        }
+    fn solve(mut self) -> SolveResult {
+        self.search()
+    }
 
-        let assignment = solve_inner(&mut self);
-        assignment.map(|assignment| Model::new(self.formula, assignment))
+    fn solve_with_statistics(mut self) -> (SolveResult, Statistics) {
+        self.time_propagation = true;
+        let start = Instant::now();
+        let result = self.search();
+        self.stats.timings.total = start.elapsed();
+        self.stats.memory = self.memory_usage();
+        (result, self.stats)
     }
 }
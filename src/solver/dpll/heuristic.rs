@@ -0,0 +1,230 @@
+/*!
+Classic branching heuristics for the DPLL solver.
+
+All heuristics only look at unresolved clauses, i.e., clauses that are not satisfied yet.
+The size of an unresolved clause is the number of its unassigned literals.
+*/
+
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use crate::{
+    formula::{Literal, Variable},
+    prelude::*,
+    solver::{Error, UnknownHeuristic},
+};
+
+pub trait DecisionDataProvider {
+    fn num_variables(&self) -> usize;
+
+    fn is_assigned(&self, variable: Variable) -> bool;
+
+    /// Appends the sizes of unresolved clauses that contain the literal to `sizes`.
+    fn unresolved_clause_sizes(&self, literal: Literal, sizes: &mut Vec<usize>);
+}
+
+/// Selects the branching heuristic of the DPLL solver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DpllHeuristic {
+    /// The first unassigned variable, always set to true first.
+    #[default]
+    First,
+    /// Dynamic Largest Individual Sum: the literal that appears in the most clauses.
+    Dlis,
+    /// Dynamic Largest Combined Sum: the variable that appears in the most clauses.
+    Dlcs,
+    /// Maximum Occurrences in clauses of Minimum Size.
+    Moms,
+    /// Two-sided Jeroslow-Wang: clauses of size `n` are weighted by `2^-n`.
+    JeroslowWang,
+    /// Böhm's heuristic: prefers occurrences in shorter clauses lexicographically.
+    Bohm,
+}
+
+/// Occurrences of a variable in unresolved clauses.
+struct Occurrences {
+    variable: Variable,
+    /// Sizes of unresolved clauses that contain the positive literal.
+    positive: Vec<usize>,
+    /// Sizes of unresolved clauses that contain the negative literal.
+    negative: Vec<usize>,
+}
+
+impl Occurrences {
+    fn new<P: DecisionDataProvider>(data_provider: &P, variable: Variable) -> Self {
+        let mut positive = Vec::new();
+        let mut negative = Vec::new();
+        data_provider.unresolved_clause_sizes(Literal::new(variable, true), &mut positive);
+        data_provider.unresolved_clause_sizes(Literal::new(variable, false), &mut negative);
+
+        Occurrences {
+            variable,
+            positive,
+            negative,
+        }
+    }
+
+    fn literal(&self, positive: bool) -> Literal {
+        Literal::new(self.variable, positive)
+    }
+
+    fn sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.positive.iter().chain(self.negative.iter()).copied()
+    }
+
+    fn count_of_size(sizes: &[usize], size: usize) -> usize {
+        sizes.iter().filter(|&&s| s == size).count()
+    }
+
+    fn weight(sizes: &[usize]) -> f64 {
+        sizes.iter().map(|&size| 0.5f64.powi(size as i32)).sum()
+    }
+
+    /// Böhm's score vector. `H_i = α * max(h_i(x), h_i(¬x)) + β * min(h_i(x), h_i(¬x))`.
+    fn bohm_vector(&self, max_size: usize) -> Vec<usize> {
+        const ALPHA: usize = 1;
+        const BETA: usize = 2;
+
+        (1..=max_size)
+            .map(|size| {
+                let pos = Self::count_of_size(&self.positive, size);
+                let neg = Self::count_of_size(&self.negative, size);
+                ALPHA * pos.max(neg) + BETA * pos.min(neg)
+            })
+            .collect()
+    }
+}
+
+impl DpllHeuristic {
+    pub const ALL: [DpllHeuristic; 6] = [
+        DpllHeuristic::First,
+        DpllHeuristic::Dlis,
+        DpllHeuristic::Dlcs,
+        DpllHeuristic::Moms,
+        DpllHeuristic::JeroslowWang,
+        DpllHeuristic::Bohm,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DpllHeuristic::First => "first",
+            DpllHeuristic::Dlis => "dlis",
+            DpllHeuristic::Dlcs => "dlcs",
+            DpllHeuristic::Moms => "moms",
+            DpllHeuristic::JeroslowWang => "jw",
+            DpllHeuristic::Bohm => "bohm",
+        }
+    }
+
+    /// Chooses a literal to branch on.
+    /// Panics if all variables are assigned.
+    pub fn choose<P: DecisionDataProvider>(self, data_provider: &P) -> Literal {
+        let mut unassigned = (0..data_provider.num_variables())
+            .map(|index| Variable::from_index(index).unwrap())
+            .filter(|&variable| !data_provider.is_assigned(variable));
+
+        if let DpllHeuristic::First = self {
+            return Literal::new(unassigned.next().unwrap(), true);
+        }
+
+        let candidates: Vec<_> = unassigned
+            .map(|variable| Occurrences::new(data_provider, variable))
+            .collect();
+
+        match self {
+            DpllHeuristic::First => unreachable!(),
+            DpllHeuristic::Dlis => Self::choose_by_score(&candidates, |occ| {
+                let pos = occ.positive.len() as f64;
+                let neg = occ.negative.len() as f64;
+                (pos.max(neg), pos >= neg)
+            }),
+            DpllHeuristic::Dlcs => Self::choose_by_score(&candidates, |occ| {
+                let pos = occ.positive.len() as f64;
+                let neg = occ.negative.len() as f64;
+                (pos + neg, pos >= neg)
+            }),
+            DpllHeuristic::Moms => {
+                const K: i32 = 10;
+
+                let min_size = candidates
+                    .iter()
+                    .flat_map(Occurrences::sizes)
+                    .min()
+                    .unwrap_or(0);
+
+                Self::choose_by_score(&candidates, |occ| {
+                    let pos = Occurrences::count_of_size(&occ.positive, min_size) as f64;
+                    let neg = Occurrences::count_of_size(&occ.negative, min_size) as f64;
+                    ((pos + neg) * 2f64.powi(K) + pos * neg, pos >= neg)
+                })
+            }
+            DpllHeuristic::JeroslowWang => Self::choose_by_score(&candidates, |occ| {
+                let pos = Occurrences::weight(&occ.positive);
+                let neg = Occurrences::weight(&occ.negative);
+                (pos + neg, pos >= neg)
+            }),
+            DpllHeuristic::Bohm => {
+                let max_size = candidates
+                    .iter()
+                    .flat_map(Occurrences::sizes)
+                    .max()
+                    .unwrap_or(0);
+
+                let mut best: Option<(&Occurrences, Vec<usize>)> = None;
+                for occ in &candidates {
+                    let vector = occ.bohm_vector(max_size);
+                    let better = match &best {
+                        None => true,
+                        Some((_, best_vector)) => vector > *best_vector,
+                    };
+                    if better {
+                        best = Some((occ, vector));
+                    }
+                }
+
+                let occ = best.unwrap().0;
+                occ.literal(occ.positive.len() >= occ.negative.len())
+            }
+        }
+    }
+
+    /// Chooses the variable with the highest score.
+    /// `score` returns the score and the polarity to try first.
+    fn choose_by_score(
+        candidates: &[Occurrences],
+        score: impl Fn(&Occurrences) -> (f64, bool),
+    ) -> Literal {
+        let mut best: Option<(Literal, f64)> = None;
+        for occ in candidates {
+            let (value, positive) = score(occ);
+            let better = match &best {
+                None => true,
+                Some((_, best_value)) => {
+                    value.partial_cmp(best_value).expect("NaN in score") == Ordering::Greater
+                }
+            };
+            if better {
+                best = Some((occ.literal(positive), value));
+            }
+        }
+
+        best.unwrap().0
+    }
+}
+
+impl FromStr for DpllHeuristic {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DpllHeuristic::ALL
+            .iter()
+            .copied()
+            .find(|heuristic| heuristic.name() == s)
+            .context(UnknownHeuristic { name: s })
+    }
+}
+
+impl Display for DpllHeuristic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
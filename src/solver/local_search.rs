@@ -0,0 +1,335 @@
+/*!
+Stochastic local search (SLS) solver.
+
+Starting from a random assignment, SLS repeatedly picks a falsified clause
+and flips one of its variables until all clauses are satisfied.
+The variable to flip is chosen based on its break value,
+the number of clauses that become falsified by flipping it.
+SLS cannot prove unsatisfiability, so it returns `Unknown` when it gives up.
+*/
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::formula::{Clause, Cnf, Literal, Model, Variable};
+
+use super::{Interrupt, SolveResult, Solver};
+
+/// Probability distribution of ProbSAT over break values.
+#[derive(Debug, Clone, Copy)]
+pub enum BreakDistribution {
+    /// `(eps + break)^-cb`
+    Polynomial { cb: f64, eps: f64 },
+    /// `cb^-break`
+    Exponential { cb: f64 },
+}
+
+impl BreakDistribution {
+    fn weight(self, break_value: usize) -> f64 {
+        match self {
+            BreakDistribution::Polynomial { cb, eps } => (eps + break_value as f64).powf(-cb),
+            BreakDistribution::Exponential { cb } => cb.powi(-(break_value as i32)),
+        }
+    }
+}
+
+/// Selects how to pick a variable in a falsified clause.
+#[derive(Debug, Clone, Copy)]
+pub enum LocalSearchAlgorithm {
+    /// WalkSAT (SKC variant).
+    /// Flips a variable with zero break value if exists.
+    /// Otherwise, flips a random variable with probability `noise`,
+    /// or a variable with the minimum break value.
+    WalkSat { noise: f64 },
+    /// ProbSAT. Flips a variable with probability proportional to `distribution(break)`.
+    ProbSat { distribution: BreakDistribution },
+}
+
+impl LocalSearchAlgorithm {
+    /// WalkSAT with the noise known to work well on random 3-SAT.
+    pub const WALKSAT: LocalSearchAlgorithm = LocalSearchAlgorithm::WalkSat { noise: 0.567 };
+
+    /// ProbSAT with the polynomial distribution tuned for random 3-SAT.
+    pub const PROBSAT: LocalSearchAlgorithm = LocalSearchAlgorithm::ProbSat {
+        distribution: BreakDistribution::Polynomial { cb: 2.38, eps: 1.0 },
+    };
+}
+
+/// Tunable options of the local search solver.
+#[derive(Debug, Clone)]
+pub struct LocalSearchConfig {
+    pub algorithm: LocalSearchAlgorithm,
+    /// Seed of the random number generator.
+    pub seed: u64,
+    /// The number of flips in each try.
+    pub max_flips: usize,
+    /// The number of restarts from a random assignment.
+    pub max_tries: usize,
+}
+
+impl Default for LocalSearchConfig {
+    fn default() -> Self {
+        LocalSearchConfig {
+            algorithm: LocalSearchAlgorithm::PROBSAT,
+            seed: 0,
+            max_flips: 1_000_000,
+            max_tries: 10,
+        }
+    }
+}
+
+fn literal_code(literal: Literal) -> usize {
+    literal.index() * 2 + literal.positive() as usize
+}
+
+/// Assignment with incrementally maintained clause states.
+pub struct SearchState<'a> {
+    clauses: &'a [Clause],
+    /// Literal code -> indices of clauses containing the literal.
+    occurrences: Vec<Vec<usize>>,
+    assignment: Vec<bool>,
+    /// Clause index -> the number of true literals.
+    true_count: Vec<usize>,
+    /// Clause index -> XOR of indices of true literals' variables.
+    /// Identifies the only true variable when `true_count == 1`.
+    true_xor: Vec<usize>,
+    /// Variable index -> the number of clauses it solely satisfies.
+    break_count: Vec<usize>,
+    /// Falsified clause indices.
+    falsified: Vec<usize>,
+    /// Clause index -> position in `falsified`.
+    falsified_pos: Vec<Option<usize>>,
+}
+
+impl<'a> SearchState<'a> {
+    pub fn new(num_variables: usize, clauses: &'a [Clause]) -> Self {
+        let mut occurrences = vec![Vec::new(); num_variables * 2];
+        for (clause_index, clause) in clauses.iter().enumerate() {
+            for literal in clause.iter() {
+                occurrences[literal_code(literal)].push(clause_index);
+            }
+        }
+
+        SearchState {
+            clauses,
+            occurrences,
+            assignment: vec![false; num_variables],
+            true_count: vec![0; clauses.len()],
+            true_xor: vec![0; clauses.len()],
+            break_count: vec![0; num_variables],
+            falsified: Vec::new(),
+            falsified_pos: vec![None; clauses.len()],
+        }
+    }
+
+    /// Resets the state to the given assignment.
+    pub fn reset(&mut self, assignment: Vec<bool>) {
+        self.assignment = assignment;
+        self.falsified.clear();
+        for value in self.break_count.iter_mut() {
+            *value = 0;
+        }
+
+        for (clause_index, clause) in self.clauses.iter().enumerate() {
+            let mut count = 0;
+            let mut xor = 0;
+            for literal in clause.iter() {
+                if literal.value(&self.assignment) {
+                    count += 1;
+                    xor ^= literal.index();
+                }
+            }
+
+            self.true_count[clause_index] = count;
+            self.true_xor[clause_index] = xor;
+            self.falsified_pos[clause_index] = None;
+            match count {
+                0 => self.push_falsified(clause_index),
+                1 => self.break_count[xor] += 1,
+                _ => (),
+            }
+        }
+    }
+
+    pub fn assignment(&self) -> &[bool] {
+        &self.assignment
+    }
+
+    pub fn falsified(&self) -> &[usize] {
+        &self.falsified
+    }
+
+    pub fn break_value(&self, variable: Variable) -> usize {
+        self.break_count[variable.index()]
+    }
+
+    fn push_falsified(&mut self, clause_index: usize) {
+        self.falsified_pos[clause_index] = Some(self.falsified.len());
+        self.falsified.push(clause_index);
+    }
+
+    fn remove_falsified(&mut self, clause_index: usize) {
+        let pos = self.falsified_pos[clause_index].take().unwrap();
+        self.falsified.swap_remove(pos);
+        if let Some(&moved) = self.falsified.get(pos) {
+            self.falsified_pos[moved] = Some(pos);
+        }
+    }
+
+    pub fn flip(&mut self, variable: Variable) {
+        let index = variable.index();
+        self.assignment[index] = !self.assignment[index];
+        let became_true = Literal::new(variable, self.assignment[index]);
+
+        for i in 0..self.occurrences[literal_code(became_true)].len() {
+            let clause_index = self.occurrences[literal_code(became_true)][i];
+            self.true_count[clause_index] += 1;
+            self.true_xor[clause_index] ^= index;
+            match self.true_count[clause_index] {
+                1 => {
+                    self.remove_falsified(clause_index);
+                    self.break_count[index] += 1;
+                }
+                2 => {
+                    // The previously critical variable is no longer critical
+                    let critical = self.true_xor[clause_index] ^ index;
+                    self.break_count[critical] -= 1;
+                }
+                _ => (),
+            }
+        }
+
+        for i in 0..self.occurrences[literal_code(!became_true)].len() {
+            let clause_index = self.occurrences[literal_code(!became_true)][i];
+            self.true_count[clause_index] -= 1;
+            self.true_xor[clause_index] ^= index;
+            match self.true_count[clause_index] {
+                0 => {
+                    self.push_falsified(clause_index);
+                    self.break_count[index] -= 1;
+                }
+                1 => {
+                    let critical = self.true_xor[clause_index];
+                    self.break_count[critical] += 1;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Picks a variable to flip in the falsified clause.
+    pub fn pick<R: Rng>(
+        &self,
+        algorithm: LocalSearchAlgorithm,
+        clause_index: usize,
+        rng: &mut R,
+    ) -> Variable {
+        let clause = self.clauses[clause_index].as_slice();
+        match algorithm {
+            LocalSearchAlgorithm::WalkSat { noise } => {
+                let mut best = Vec::new();
+                let mut best_break = usize::MAX;
+                for literal in clause {
+                    let break_value = self.break_value(literal.variable());
+                    if break_value < best_break {
+                        best.clear();
+                        best_break = break_value;
+                    }
+                    if break_value == best_break {
+                        best.push(literal.variable());
+                    }
+                }
+
+                if best_break > 0 && rng.gen_bool(noise) {
+                    clause[rng.gen_range(0..clause.len())].variable()
+                } else {
+                    best[rng.gen_range(0..best.len())]
+                }
+            }
+            LocalSearchAlgorithm::ProbSat { distribution } => {
+                let weights: Vec<f64> = clause
+                    .iter()
+                    .map(|literal| distribution.weight(self.break_value(literal.variable())))
+                    .collect();
+
+                let mut threshold = rng.gen::<f64>() * weights.iter().sum::<f64>();
+                for (literal, weight) in clause.iter().zip(weights) {
+                    if threshold < weight {
+                        return literal.variable();
+                    }
+                    threshold -= weight;
+                }
+                clause[clause.len() - 1].variable()
+            }
+        }
+    }
+}
+
+pub struct LocalSearchSolver {
+    /// The formula given by the user.
+    original: Cnf,
+    /// The formula to solve, with parity, cardinality and pseudo-Boolean constraints encoded as clauses.
+    formula: Cnf,
+    config: LocalSearchConfig,
+    /// Stops the search when triggered
+    interrupt: Interrupt,
+}
+
+impl LocalSearchSolver {
+    /// Creates a new solver instance with the given options.
+    pub fn with_config(original: Cnf, config: LocalSearchConfig) -> Self {
+        LocalSearchSolver {
+            formula: original.encode_constraints(),
+            original,
+            config,
+            interrupt: Interrupt::new(),
+        }
+    }
+}
+
+impl Solver for LocalSearchSolver {
+    fn new(formula: Cnf) -> Self {
+        LocalSearchSolver::with_config(formula, LocalSearchConfig::default())
+    }
+
+    fn set_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt = interrupt;
+    }
+
+    fn solve(self) -> SolveResult {
+        // Empty clauses are not among the clauses to walk on, yet no assignment satisfies them
+        if self.original.empty_clause_count() > 0 {
+            return SolveResult::Unknown;
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+        let num_variables = self.formula.num_variables();
+        let mut state = SearchState::new(num_variables, self.formula.clauses());
+
+        for try_count in 0..self.config.max_tries {
+            let assignment = (0..num_variables).map(|_| rng.gen()).collect();
+            state.reset(assignment);
+
+            for _ in 0..self.config.max_flips {
+                let falsified = state.falsified();
+                if falsified.is_empty() || self.interrupt.is_interrupted() {
+                    break;
+                }
+
+                let clause_index = falsified[rng.gen_range(0..falsified.len())];
+                let variable = state.pick(self.config.algorithm, clause_index, &mut rng);
+                state.flip(variable);
+            }
+
+            if state.falsified().is_empty() {
+                let assignment = state.assignment();
+                return SolveResult::Sat(Model::from_extended(self.original, assignment));
+            }
+            if self.interrupt.is_interrupted() {
+                break;
+            }
+            debug!("Try {} failed", try_count);
+        }
+
+        SolveResult::Unknown
+    }
+}
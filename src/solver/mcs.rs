@@ -0,0 +1,212 @@
+/*!
+Minimal correction sets of soft clauses.
+
+A correction set is a set of soft clauses whose removal makes the hard clauses
+and the remaining soft clauses satisfiable, and it is minimal (an MCS) if no proper subset is.
+Each soft clause is guarded by a selector literal, so it is enabled by assuming the selector.
+
+An MCS is extracted by linear search on a single CDCL solver: starting from the soft clauses
+satisfied by a model, each falsified clause is enabled in turn. If the solver finds a model,
+every soft clause it satisfies is kept, and otherwise the clause is in the MCS.
+MCSes are enumerated by blocking each one with a clause requiring one of its soft clauses.
+
+By hitting set duality, the minimal unsatisfiable subsets (MUSes) of the soft clauses
+are the minimal hitting sets of all MCSes, and vice versa.
+A minimum MCS is the complement of a MaxSAT solution, found by minimizing
+the number of disabled soft clauses.
+*/
+
+use crate::encode::CnfBuilder;
+use crate::formula::{Clause, Cnf, Literal, Objective, Variable};
+
+use super::{minimize, CdclConfig, CdclSolver, OptimizeResult, SolveResult, Solver};
+
+/// Enumerates the MCSes of soft clauses as their sorted indices.
+/// The enumeration is empty if the hard clauses are unsatisfiable,
+/// and ends early if the solver gives up.
+pub struct McsEnumerator {
+    /// The hard clauses, the guarded soft clauses and the blocking clauses
+    formula: Cnf,
+    soft: Vec<Clause>,
+    selectors: Vec<Literal>,
+    config: CdclConfig,
+    done: bool,
+}
+
+impl McsEnumerator {
+    /// # Panics
+    ///
+    /// Panics when a soft clause contains variables out of the range of `hard`.
+    pub fn new(hard: &Cnf, soft: Vec<Clause>, config: CdclConfig) -> Self {
+        let (formula, selectors) = guard_soft_clauses(hard, &soft);
+        McsEnumerator {
+            formula,
+            soft,
+            selectors,
+            config,
+            done: false,
+        }
+    }
+
+    /// Extracts an MCS from the model of the current formula.
+    fn extract(&self) -> Option<Vec<usize>> {
+        let mut solver = CdclSolver::with_config(self.formula.clone(), self.config.clone());
+        let mut satisfied = match solver.solve_with_assumptions(&[]) {
+            SolveResult::Sat(model) => self.satisfied(model.assignment()),
+            _ => return None,
+        };
+
+        let mut correction = Vec::new();
+        for index in 0..self.soft.len() {
+            if satisfied[index] {
+                continue;
+            }
+
+            let assumptions: Vec<Literal> = self
+                .selectors
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| satisfied[other] || other == index)
+                .map(|(_, &selector)| selector)
+                .collect();
+            match solver.solve_with_assumptions(&assumptions) {
+                SolveResult::Sat(model) => {
+                    for (value, new) in satisfied.iter_mut().zip(self.satisfied(model.assignment()))
+                    {
+                        *value |= new;
+                    }
+                }
+                SolveResult::Unsat => correction.push(index),
+                SolveResult::Unknown => return None,
+            }
+        }
+        Some(correction)
+    }
+
+    /// Returns whether each soft clause is satisfied by the assignment.
+    fn satisfied(&self, assignment: &[bool]) -> Vec<bool> {
+        self.soft
+            .iter()
+            .map(|clause| clause.iter().any(|literal| literal.value(assignment)))
+            .collect()
+    }
+}
+
+impl Iterator for McsEnumerator {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let correction = self.extract();
+        match &correction {
+            // The empty MCS is the only one when all soft clauses can be satisfied
+            Some(correction) if !correction.is_empty() => {
+                debug!("Found an MCS of {} soft clauses", correction.len());
+                self.formula.add_clause(Clause::new(
+                    correction
+                        .iter()
+                        .map(|&index| self.selectors[index])
+                        .collect(),
+                ));
+            }
+            _ => self.done = true,
+        }
+        correction
+    }
+}
+
+/// Adds the soft clauses to the hard clauses, each guarded by a fresh selector.
+fn guard_soft_clauses(hard: &Cnf, soft: &[Clause]) -> (Cnf, Vec<Literal>) {
+    let num_variables = hard.num_variables();
+    let mut builder = CnfBuilder::from(hard.clone());
+    let selectors = soft
+        .iter()
+        .map(|clause| {
+            assert!(clause.iter().all(|literal| literal.index() < num_variables));
+            let selector = builder.new_literal();
+            builder.add_clause(Clause::new(
+                std::iter::once(!selector).chain(clause.iter()).collect(),
+            ));
+            selector
+        })
+        .collect();
+    (builder.build(), selectors)
+}
+
+/// Returns the minimal sets of indices that intersect every set,
+/// such as the MUSes of soft clauses given all of their MCSes.
+pub fn minimal_hitting_sets(sets: &[Vec<usize>], config: CdclConfig) -> Vec<Vec<usize>> {
+    let universe = sets.iter().flatten().map(|&index| index + 1).max();
+    let mut formula = Cnf::new(universe.unwrap_or(0));
+    let literal = |index: usize| Literal::new(Variable::from_index(index).unwrap(), true);
+    for set in sets {
+        if set.is_empty() {
+            // Nothing intersects the empty set
+            return Vec::new();
+        }
+        formula.add_clause(Clause::new(
+            set.iter().map(|&index| literal(index)).collect(),
+        ));
+    }
+
+    let mut hitting_sets = Vec::new();
+    loop {
+        let model = match CdclSolver::with_config(formula.clone(), config.clone()).solve() {
+            SolveResult::Sat(model) => model,
+            _ => return hitting_sets,
+        };
+
+        // Drop elements greedily while every set is still hit
+        let mut hitting_set: Vec<usize> = (0..formula.num_variables())
+            .filter(|&index| model.assignment()[index])
+            .collect();
+        let mut position = 0;
+        while position < hitting_set.len() {
+            let removed = hitting_set[position];
+            let still_hit = sets.iter().all(|set| {
+                set.iter()
+                    .any(|index| *index != removed && hitting_set.contains(index))
+            });
+            if still_hit {
+                hitting_set.remove(position);
+            } else {
+                position += 1;
+            }
+        }
+
+        if hitting_set.is_empty() {
+            // There are no sets to hit
+            hitting_sets.push(hitting_set);
+            return hitting_sets;
+        }
+        // Block the supersets of the hitting set
+        formula.add_clause(Clause::new(
+            hitting_set.iter().map(|&index| !literal(index)).collect(),
+        ));
+        hitting_sets.push(hitting_set);
+    }
+}
+
+/// Finds an MCS of minimum size by MaxSAT, building a solver with `build` for each step.
+/// Returns `None` if the hard clauses are unsatisfiable or the solver gives up before
+/// proving the size minimum.
+pub fn minimum_correction_set<S: Solver>(
+    hard: &Cnf,
+    soft: &[Clause],
+    build: impl FnMut(Cnf) -> S,
+) -> Option<Vec<usize>> {
+    let (formula, selectors) = guard_soft_clauses(hard, soft);
+    let objective = Objective::new(selectors.iter().map(|&selector| (1, !selector)).collect());
+    match minimize(formula, &objective, build) {
+        // Every satisfied soft clause is selected in an optimal model
+        OptimizeResult::Optimum { model, .. } => Some(
+            (0..soft.len())
+                .filter(|&index| !selectors[index].value(model.assignment()))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
@@ -0,0 +1,73 @@
+/*!
+Pseudo-Boolean optimization.
+
+Minimizes a linear objective by linear SAT-UNSAT search: after each model of cost `c`,
+the constraint `objective ≤ c - 1` is added and the formula is solved again from scratch.
+The last model is optimal once the strengthened formula becomes unsatisfiable.
+*/
+
+use crate::formula::{Cnf, Model, Objective, PbConstraint};
+
+use super::{SolveResult, Solver};
+
+/// The result of minimizing an objective.
+#[derive(Debug)]
+pub enum OptimizeResult {
+    /// The model has the minimum cost.
+    Optimum { model: Model, cost: i64 },
+    /// The solver gave up before proving that the model is optimal.
+    Feasible { model: Model, cost: i64 },
+    /// The formula is unsatisfiable.
+    Unsat,
+    /// The solver gave up without finding any model.
+    Unknown,
+}
+
+impl OptimizeResult {
+    /// Returns the best model found and its cost.
+    pub fn best(&self) -> Option<(&Model, i64)> {
+        match self {
+            OptimizeResult::Optimum { model, cost } | OptimizeResult::Feasible { model, cost } => {
+                Some((model, *cost))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Minimizes `objective` subject to `formula`, building a solver with `build` for each step.
+pub fn minimize<S: Solver>(
+    formula: Cnf,
+    objective: &Objective,
+    mut build: impl FnMut(Cnf) -> S,
+) -> OptimizeResult {
+    let mut best: Option<(Model, i64)> = None;
+    let mut constrained = formula.clone();
+
+    loop {
+        match build(constrained).solve() {
+            SolveResult::Sat(model) => {
+                let cost = objective.value(model.assignment());
+                debug!("Found a model of cost {}", cost);
+
+                let model = Model::new(formula.clone(), model.assignment().to_vec());
+                best = Some((model, cost));
+
+                constrained = formula.clone();
+                constrained.add_pb(PbConstraint::at_most(objective.terms().to_vec(), cost - 1));
+            }
+            SolveResult::Unsat => {
+                return match best {
+                    Some((model, cost)) => OptimizeResult::Optimum { model, cost },
+                    None => OptimizeResult::Unsat,
+                }
+            }
+            SolveResult::Unknown => {
+                return match best {
+                    Some((model, cost)) => OptimizeResult::Feasible { model, cost },
+                    None => OptimizeResult::Unknown,
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+/*!
+Threads of the parallel solvers.
+
+Each worker runs on its own thread with an interrupt of its own, so that finishing
+the search does not trigger the caller's interrupt. The first result accepted by the
+caller wins, and the other workers are interrupted before the threads are joined.
+*/
+
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use super::{Interrupt, SolveResult};
+
+/// How often the external interrupt is checked while waiting for workers.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs the workers in parallel and returns the first result for which `is_answer` holds.
+/// Returns `None` if every worker finishes without an answer,
+/// or if `interrupt` is triggered first.
+pub fn run_workers<F>(
+    workers: Vec<F>,
+    interrupt: &Interrupt,
+    is_answer: impl Fn(&SolveResult) -> bool,
+) -> Option<SolveResult>
+where
+    F: FnOnce(Interrupt) -> SolveResult + Send,
+{
+    if interrupt.is_interrupted() {
+        return None;
+    }
+
+    let cancel = Interrupt::new();
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for (index, worker) in workers.into_iter().enumerate() {
+            let sender = sender.clone();
+            let cancel = cancel.clone();
+            scope.spawn(move || {
+                let result = worker(cancel);
+                // The receiver outlives the scope, so sending never fails
+                let _ = sender.send((index, result));
+            });
+        }
+        drop(sender);
+
+        let mut answer = None;
+        loop {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok((index, result)) => {
+                    if is_answer(&result) {
+                        debug!("Worker {} finished first", index);
+                        answer = Some(result);
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if interrupt.is_interrupted() {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        cancel.interrupt();
+        answer
+    })
+}
@@ -0,0 +1,162 @@
+/*!
+Parallel portfolio solver.
+
+Runs differently configured solvers on separate threads over the same formula.
+CDCL workers share their learned clauses through a `ClauseExchange`.
+The first definitive answer wins, and the other workers are interrupted.
+*/
+
+use std::thread;
+
+use crate::formula::Cnf;
+
+use super::{
+    pool::run_workers,
+    sharing::{ClauseExchange, ExchangePort, SharingConfig},
+    CdclConfig, CdclHeuristic, CdclRestart, CdclSolver, DpllHeuristic, DpllSolver, Interrupt,
+    SolveResult, Solver,
+};
+
+/// A solver configuration run by one portfolio thread.
+#[derive(Debug, Clone)]
+pub enum PortfolioWorker {
+    Cdcl(CdclConfig),
+    Dpll(DpllHeuristic),
+}
+
+impl PortfolioWorker {
+    /// Connects the worker to the exchange if it shares clauses.
+    /// A connected worker that never exchanges would keep every shared clause alive.
+    pub(crate) fn connect(&self, exchange: &ClauseExchange, index: usize) -> Option<ExchangePort> {
+        match self {
+            PortfolioWorker::Cdcl(_) => Some(exchange.connect(index)),
+            PortfolioWorker::Dpll(_) => None,
+        }
+    }
+
+    fn solve(
+        &self,
+        formula: Cnf,
+        interrupt: Interrupt,
+        exchange: Option<ExchangePort>,
+    ) -> SolveResult {
+        match self {
+            PortfolioWorker::Cdcl(config) => {
+                let mut solver = CdclSolver::with_config(formula, config.clone());
+                solver.set_interrupt(interrupt);
+                if let Some(exchange) = exchange {
+                    solver.set_exchange(exchange);
+                }
+                solver.solve()
+            }
+            PortfolioWorker::Dpll(heuristic) => {
+                let mut solver = DpllSolver::with_heuristic(formula, *heuristic);
+                solver.set_interrupt(interrupt);
+                solver.solve()
+            }
+        }
+    }
+}
+
+/// Tunable options of the portfolio solver.
+#[derive(Debug, Clone)]
+pub struct PortfolioConfig {
+    /// One thread is spawned for each worker.
+    pub workers: Vec<PortfolioWorker>,
+    /// Clause sharing between CDCL workers, disabled if `None`.
+    pub sharing: Option<SharingConfig>,
+}
+
+impl PortfolioConfig {
+    /// Creates `num_threads` workers with different heuristics, restart policies and seeds.
+    /// If `with_dpll` is set, one of them is a DPLL solver.
+    pub fn diversified(num_threads: usize, with_dpll: bool) -> Self {
+        let num_cdcl = if with_dpll {
+            num_threads.saturating_sub(1).max(1)
+        } else {
+            num_threads.max(1)
+        };
+
+        let mut workers: Vec<_> = (0..num_cdcl)
+            .map(|index| {
+                let heuristics = CdclHeuristic::ALL.len();
+                let restarts = CdclRestart::ALL.len();
+                PortfolioWorker::Cdcl(CdclConfig {
+                    heuristic: CdclHeuristic::ALL[index % heuristics],
+                    restart: CdclRestart::ALL[index / heuristics % restarts],
+                    local_search: true,
+                    seed: index as u64,
+                    xor_detection: true,
+                    pb_encoding: None,
+                    chrono_threshold: CdclConfig::default().chrono_threshold,
+                    mode_switching: false,
+                    phase_timings: false,
+                })
+            })
+            .collect();
+
+        if with_dpll {
+            workers.push(PortfolioWorker::Dpll(DpllHeuristic::default()));
+        }
+
+        PortfolioConfig {
+            workers,
+            sharing: Some(SharingConfig::default()),
+        }
+    }
+}
+
+impl Default for PortfolioConfig {
+    fn default() -> Self {
+        let num_threads = thread::available_parallelism().map_or(1, |n| n.get());
+        PortfolioConfig::diversified(num_threads, false)
+    }
+}
+
+pub struct PortfolioSolver {
+    formula: Cnf,
+    config: PortfolioConfig,
+    /// Stops the search when triggered
+    interrupt: Interrupt,
+}
+
+impl PortfolioSolver {
+    /// Creates a new solver instance with the given options.
+    pub fn with_config(formula: Cnf, config: PortfolioConfig) -> Self {
+        PortfolioSolver {
+            formula,
+            config,
+            interrupt: Interrupt::new(),
+        }
+    }
+}
+
+impl Solver for PortfolioSolver {
+    fn new(formula: Cnf) -> Self {
+        PortfolioSolver::with_config(formula, PortfolioConfig::default())
+    }
+
+    fn set_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt = interrupt;
+    }
+
+    fn solve(self) -> SolveResult {
+        let exchange = self.config.sharing.clone().map(ClauseExchange::new);
+        let workers = self
+            .config
+            .workers
+            .iter()
+            .enumerate()
+            .map(|(index, worker)| {
+                let formula = self.formula.clone();
+                let port = exchange
+                    .as_ref()
+                    .and_then(|exchange| worker.connect(exchange, index));
+                move |cancel| worker.solve(formula, cancel, port)
+            })
+            .collect();
+
+        run_workers(workers, &self.interrupt, |result| !result.is_unknown())
+            .unwrap_or(SolveResult::Unknown)
+    }
+}
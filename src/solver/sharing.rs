@@ -0,0 +1,182 @@
+/*!
+Learned clause sharing between parallel solvers.
+
+Every worker solves the same formula, so a clause learned by one worker
+is implied by the formula and can be added to any other worker.
+Workers encoding constraints into clauses only share the clauses over the variables
+of the formula, as their auxiliary variables are not those of the other workers.
+Workers buffer short or low-LBD clauses as they learn them, and at restarts
+publish the buffer and import the clauses published by their peers in one batch.
+The exchange lock is taken only during that batch.
+
+Clauses read by every connected worker are dropped, and duplicates are only detected
+among the most recently published clauses, so the memory of the exchange stays bounded
+as long as all workers keep exchanging.
+*/
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use crate::formula::{Clause, Literal};
+
+/// Filters for clauses to export.
+/// A clause is exported if it satisfies either bound.
+#[derive(Debug, Clone)]
+pub struct SharingConfig {
+    /// Maximum number of literals
+    pub max_size: usize,
+    /// Maximum literal block distance (the number of distinct decision levels)
+    pub max_lbd: usize,
+}
+
+impl Default for SharingConfig {
+    fn default() -> Self {
+        SharingConfig {
+            max_size: 8,
+            max_lbd: 3,
+        }
+    }
+}
+
+struct SharedClauses {
+    /// Published clauses not yet read by every worker, with the index of the publishing worker.
+    clauses: VecDeque<(usize, Clause)>,
+    /// The number of published clauses dropped from the front of `clauses`.
+    dropped: usize,
+    /// Worker index -> the number of published clauses already seen by the worker.
+    cursors: HashMap<usize, usize>,
+    /// Sorted literals of recently published clauses, to drop duplicates.
+    seen: HashSet<Vec<Literal>>,
+    /// The keys of `seen` in publishing order, to forget the oldest ones.
+    seen_order: VecDeque<Vec<Literal>>,
+}
+
+impl SharedClauses {
+    /// The number of recently published clauses checked for duplicates.
+    const SEEN_CAPACITY: usize = 100_000;
+
+    /// Publishes the clause unless it is a recent duplicate.
+    fn publish(&mut self, worker: usize, clause: Clause) {
+        let mut key = clause.as_slice().to_vec();
+        key.sort_unstable();
+        if !self.seen.insert(key.clone()) {
+            return;
+        }
+
+        self.seen_order.push_back(key);
+        if self.seen_order.len() > Self::SEEN_CAPACITY {
+            let oldest = self.seen_order.pop_front().unwrap();
+            self.seen.remove(&oldest);
+        }
+        self.clauses.push_back((worker, clause));
+    }
+
+    /// Drops the clauses that every connected worker has seen.
+    fn drop_seen(&mut self) {
+        let min_cursor = self
+            .cursors
+            .values()
+            .copied()
+            .min()
+            .unwrap_or(self.dropped + self.clauses.len());
+        while self.dropped < min_cursor {
+            self.clauses.pop_front();
+            self.dropped += 1;
+        }
+    }
+}
+
+/// A pool of clauses shared by workers.
+#[derive(Clone)]
+pub struct ClauseExchange {
+    config: SharingConfig,
+    shared: Arc<Mutex<SharedClauses>>,
+}
+
+impl ClauseExchange {
+    pub fn new(config: SharingConfig) -> Self {
+        ClauseExchange {
+            config,
+            shared: Arc::new(Mutex::new(SharedClauses {
+                clauses: VecDeque::new(),
+                dropped: 0,
+                cursors: HashMap::new(),
+                seen: HashSet::new(),
+                seen_order: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Returns the number of published clauses kept until every connected worker reads them.
+    pub fn num_clauses(&self) -> usize {
+        self.shared.lock().unwrap().clauses.len()
+    }
+
+    /// Creates the endpoint of the worker with the given index, which must be unique.
+    /// The worker receives the clauses that are not yet read by all connected workers.
+    pub fn connect(&self, worker: usize) -> ExchangePort {
+        let mut shared = self.shared.lock().unwrap();
+        let cursor = shared.dropped;
+        shared.cursors.insert(worker, cursor);
+        ExchangePort {
+            exchange: self.clone(),
+            worker,
+            outbox: Vec::new(),
+            cursor,
+        }
+    }
+}
+
+/// The endpoint of a worker connected to a `ClauseExchange`.
+pub struct ExchangePort {
+    exchange: ClauseExchange,
+    worker: usize,
+    /// Clauses waiting for the next exchange.
+    outbox: Vec<Clause>,
+    /// The number of shared clauses already seen by this worker.
+    cursor: usize,
+}
+
+impl ExchangePort {
+    /// Buffers a learned clause if it passes the filters.
+    pub fn export(&mut self, clause: &Clause, lbd: usize) {
+        let config = &self.exchange.config;
+        if clause.len() <= config.max_size || lbd <= config.max_lbd {
+            self.outbox.push(clause.clone());
+        }
+    }
+
+    /// Publishes buffered clauses and returns new clauses published by other workers.
+    pub fn exchange(&mut self) -> Vec<Clause> {
+        let mut shared = self.exchange.shared.lock().unwrap();
+
+        let mut imported = Vec::new();
+        for (worker, clause) in shared.clauses.range(self.cursor - shared.dropped..) {
+            if *worker != self.worker {
+                imported.push(clause.clone());
+            }
+        }
+
+        for clause in self.outbox.drain(..) {
+            shared.publish(self.worker, clause);
+        }
+        self.cursor = shared.dropped + shared.clauses.len();
+        shared.cursors.insert(self.worker, self.cursor);
+        shared.drop_seen();
+
+        imported
+    }
+}
+
+impl Drop for ExchangePort {
+    /// Disconnects the worker, so that the clauses only it has not read can be dropped.
+    fn drop(&mut self) {
+        // A poisoned lock means that a worker panicked, and the exchange is useless anyway
+        if let Ok(mut shared) = self.exchange.shared.lock() {
+            shared.cursors.remove(&self.worker);
+            shared.drop_seen();
+        }
+    }
+}
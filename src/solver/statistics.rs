@@ -0,0 +1,66 @@
+/*!
+Counters collected by the DPLL and CDCL solvers during the search.
+*/
+
+use std::time::Duration;
+
+/// Counters of a search. Those that do not apply to a solver stay zero.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// Branching decisions, excluding those of clause vivification
+    pub decisions: usize,
+    /// Literals assigned by propagation
+    pub propagations: usize,
+    /// Conflicts, which are backtracks in DPLL
+    pub conflicts: usize,
+    pub restarts: usize,
+    /// Switches between the focused and stable modes of the CDCL solver
+    pub mode_switches: usize,
+    /// Clauses learned from conflicts
+    pub learned_clauses: usize,
+    /// Total length of the learned clauses
+    pub learned_literals: usize,
+    /// The number of learned clauses by their literal block distance (LBD),
+    /// the number of decision levels among their literals
+    pub lbd_histogram: Vec<usize>,
+    /// Clauses deleted or replaced by vivification
+    pub deleted_clauses: usize,
+    /// Literals removed from clauses by vivification
+    pub vivified_literals: usize,
+    pub max_decision_level: usize,
+    /// Estimated memory held by the clause database and the assignment in bytes
+    pub memory: usize,
+    pub timings: Timings,
+}
+
+/// Time spent in each phase of the search.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// The whole search, including the phases below
+    pub total: Duration,
+    pub propagation: Duration,
+    pub conflict_analysis: Duration,
+    pub vivification: Duration,
+    pub local_search: Duration,
+}
+
+impl Statistics {
+    /// Returns the average length of the learned clauses, or 0 if none are learned.
+    pub fn average_learned_len(&self) -> f64 {
+        if self.learned_clauses == 0 {
+            0.0
+        } else {
+            self.learned_literals as f64 / self.learned_clauses as f64
+        }
+    }
+
+    /// Records a learned clause of the length and LBD.
+    pub(crate) fn learned(&mut self, len: usize, lbd: usize) {
+        self.learned_clauses += 1;
+        self.learned_literals += len;
+        if self.lbd_histogram.len() <= lbd {
+            self.lbd_histogram.resize(lbd + 1, 0);
+        }
+        self.lbd_histogram[lbd] += 1;
+    }
+}
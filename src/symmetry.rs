@@ -0,0 +1,503 @@
+/*!
+Static symmetry breaking.
+
+A symmetry of a formula is a permutation of its literals, commuting with negation,
+that maps the set of clauses to itself. Symmetries are detected as automorphisms
+of a colored graph with a vertex for each literal and each clause, where a literal
+is adjacent to its negation and to the clauses containing it.
+
+Generators of the automorphism group are found by individualization and refinement:
+the first path of the search tree individualizes the first vertex of the first non-trivial
+cell until the partition is discrete. Then, from the deepest level up, every other vertex
+of the cell is tried, unless it is already in the orbit of the individualized vertex,
+and the subtree is searched for a leaf that maps the first leaf by an automorphism.
+
+Each generator is broken by a lex-leader predicate in the order of variables,
+which keeps only the assignments that are not greater than their image.
+*/
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::encode::CnfBuilder;
+use crate::formula::{Clause, Cnf, Literal, Variable};
+
+/// The maximum number of nodes searched for an automorphism mapping a vertex to another.
+const SEARCH_LIMIT: usize = 1000;
+
+/// A permutation of literals commuting with negation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symmetry {
+    images: Vec<Literal>,
+}
+
+impl Symmetry {
+    /// Creates a symmetry from the images of the positive literals, indexed by variable.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the images are not a permutation of the variables.
+    pub fn new(images: Vec<Literal>) -> Self {
+        let mut seen = vec![false; images.len()];
+        for image in &images {
+            assert!(image.index() < images.len() && !seen[image.index()]);
+            seen[image.index()] = true;
+        }
+        Symmetry { images }
+    }
+
+    pub fn apply(&self, literal: Literal) -> Literal {
+        let image = self.images[literal.index()];
+        if literal.positive() {
+            image
+        } else {
+            !image
+        }
+    }
+
+    /// Returns the variables that are not mapped to their positive literal.
+    pub fn support(&self) -> Vec<Variable> {
+        self.images
+            .iter()
+            .enumerate()
+            .filter(|&(index, image)| image.index() != index || !image.positive())
+            .map(|(index, _)| Variable::from_index(index).unwrap())
+            .collect()
+    }
+
+    /// Returns whether the symmetry maps the clauses of the formula onto themselves.
+    pub fn preserves(&self, formula: &Cnf) -> bool {
+        let clauses = normalized_clauses(formula);
+        let set: HashSet<&Vec<Literal>> = clauses.iter().collect();
+        clauses.iter().all(|clause| {
+            let mut image: Vec<Literal> =
+                clause.iter().map(|&literal| self.apply(literal)).collect();
+            image.sort();
+            set.contains(&image)
+        })
+    }
+}
+
+/// Returns the clauses with sorted and deduplicated literals, without tautologies and duplicates.
+fn normalized_clauses(formula: &Cnf) -> Vec<Vec<Literal>> {
+    let mut clauses: Vec<Vec<Literal>> = formula
+        .clauses()
+        .iter()
+        .map(|clause| {
+            let mut literals = clause.as_slice().to_vec();
+            literals.sort();
+            literals.dedup();
+            literals
+        })
+        .filter(|literals| literals.windows(2).all(|pair| pair[0] != !pair[1]))
+        .collect();
+    clauses.sort();
+    clauses.dedup();
+    clauses
+}
+
+/// A graph whose literal vertices come first, positive and negative for each variable in turn.
+struct Graph {
+    neighbors: Vec<Vec<usize>>,
+    colors: Vec<usize>,
+}
+
+impl Graph {
+    /// Returns whether the vertex mapping preserves the edges.
+    fn is_automorphism(&self, mapping: &[usize]) -> bool {
+        self.neighbors
+            .iter()
+            .enumerate()
+            .all(|(vertex, neighbors)| {
+                let mut image: Vec<usize> = neighbors.iter().map(|&other| mapping[other]).collect();
+                image.sort_unstable();
+                image == self.neighbors[mapping[vertex]]
+            })
+    }
+}
+
+/// An ordered partition of the vertices, where each cell is a range of `order`.
+#[derive(Debug, Clone)]
+struct Partition {
+    order: Vec<usize>,
+    position: Vec<usize>,
+    /// The start of the cell of each vertex
+    cell_of: Vec<usize>,
+    /// The end of each cell, indexed by its start
+    cell_end: Vec<usize>,
+    num_cells: usize,
+}
+
+impl Partition {
+    /// Creates the equitable partition refining the colors, in the order of colors.
+    fn new(graph: &Graph) -> Self {
+        let colors = &graph.colors;
+        let num_vertices = colors.len();
+        let mut order: Vec<usize> = (0..num_vertices).collect();
+        order.sort_by_key(|&vertex| colors[vertex]);
+
+        let mut partition = Partition {
+            order,
+            position: vec![0; num_vertices],
+            cell_of: vec![0; num_vertices],
+            cell_end: vec![0; num_vertices],
+            num_cells: 0,
+        };
+        if num_vertices > 0 {
+            partition.assign_cells(0, num_vertices, |vertex| colors[vertex]);
+        }
+        let cells = (0..num_vertices)
+            .filter(|&position| partition.cell_of[partition.order[position]] == position)
+            .collect();
+        partition.refine(graph, cells);
+        partition
+    }
+
+    /// Makes cells of the vertices in the range, which are sorted by `key`.
+    fn assign_cells(&mut self, start: usize, end: usize, key: impl Fn(usize) -> usize) {
+        let mut cell = start;
+        for position in start..end {
+            let vertex = self.order[position];
+            if position > start && key(vertex) != key(self.order[position - 1]) {
+                self.cell_end[cell] = position;
+                cell = position;
+                self.num_cells += 1;
+            }
+            self.position[vertex] = position;
+            self.cell_of[vertex] = cell;
+        }
+        self.cell_end[cell] = end;
+        self.num_cells += 1;
+    }
+
+    /// Returns the start of the first cell with more than one vertex.
+    fn target_cell(&self) -> Option<usize> {
+        let mut start = 0;
+        while start < self.order.len() {
+            if self.cell_end[start] - start > 1 {
+                return Some(start);
+            }
+            start = self.cell_end[start];
+        }
+        None
+    }
+
+    fn cell(&self, start: usize) -> &[usize] {
+        &self.order[start..self.cell_end[start]]
+    }
+
+    /// Returns the end of the cell at each position, which is the same
+    /// for partitions mapped to each other by an automorphism.
+    fn shape(&self) -> Vec<usize> {
+        self.order
+            .iter()
+            .map(|&vertex| self.cell_end[self.cell_of[vertex]])
+            .collect()
+    }
+
+    /// Moves the vertex into a cell of its own, in front of its former cell,
+    /// and refines the partition.
+    fn individualize(&mut self, vertex: usize, graph: &Graph) {
+        let start = self.cell_of[vertex];
+        let end = self.cell_end[start];
+        let first = self.order[start];
+        let position = self.position[vertex];
+        self.order.swap(start, position);
+        self.position[first] = position;
+        self.position[vertex] = start;
+
+        self.cell_end[start] = start + 1;
+        self.cell_end[start + 1] = end;
+        for position in start + 1..end {
+            self.cell_of[self.order[position]] = start + 1;
+        }
+        self.num_cells += 1;
+        self.refine(graph, vec![start]);
+    }
+
+    /// Splits cells by the number of neighbors in splitter cells until the partition
+    /// is equitable. Splitters are taken in order from a queue of cell starts,
+    /// where the parts of split cells are added.
+    fn refine(&mut self, graph: &Graph, splitters: Vec<usize>) {
+        let mut counts = vec![0; self.order.len()];
+        let mut queued = vec![false; self.order.len()];
+        let mut queue = VecDeque::new();
+        for start in splitters {
+            queued[start] = true;
+            queue.push_back(start);
+        }
+
+        while let Some(splitter) = queue.pop_front() {
+            queued[splitter] = false;
+            let end = self.cell_end[splitter];
+            let mut touched = Vec::new();
+            for &vertex in &self.order[splitter..end] {
+                for &neighbor in &graph.neighbors[vertex] {
+                    if counts[neighbor] == 0 {
+                        touched.push(neighbor);
+                    }
+                    counts[neighbor] += 1;
+                }
+            }
+
+            let mut cells: Vec<usize> =
+                touched.iter().map(|&vertex| self.cell_of[vertex]).collect();
+            cells.sort_unstable();
+            cells.dedup();
+            for start in cells {
+                let end = self.cell_end[start];
+                if !self.split(start, &counts) {
+                    continue;
+                }
+                let mut part = start;
+                while part < end {
+                    if !queued[part] {
+                        queued[part] = true;
+                        queue.push_back(part);
+                    }
+                    part = self.cell_end[part];
+                }
+            }
+
+            for vertex in touched {
+                counts[vertex] = 0;
+            }
+        }
+    }
+
+    /// Splits the cell by the counts of its vertices, in increasing order.
+    /// Returns whether the cell is split.
+    fn split(&mut self, start: usize, counts: &[usize]) -> bool {
+        let end = self.cell_end[start];
+        self.order[start..end].sort_by_key(|&vertex| counts[vertex]);
+        if counts[self.order[start]] == counts[self.order[end - 1]] {
+            return false;
+        }
+        self.num_cells -= 1;
+        self.assign_cells(start, end, |vertex| counts[vertex]);
+        true
+    }
+}
+
+/// The search for generators of the automorphism group.
+struct Search<'a> {
+    graph: &'a Graph,
+    /// The partitions on the first path, the last of which is discrete
+    path: Vec<Partition>,
+    shapes: Vec<Vec<usize>>,
+    /// The number of nodes visited in the current subtree
+    nodes: usize,
+}
+
+impl<'a> Search<'a> {
+    fn new(graph: &'a Graph) -> Self {
+        let mut path = vec![Partition::new(graph)];
+        while let Some(target) = path.last().unwrap().target_cell() {
+            let mut partition = path.last().unwrap().clone();
+            partition.individualize(partition.order[target], graph);
+            path.push(partition);
+        }
+
+        let shapes = path.iter().map(Partition::shape).collect();
+        Search {
+            graph,
+            path,
+            shapes,
+            nodes: 0,
+        }
+    }
+
+    /// Returns the vertex mappings of the generators found.
+    fn generators(&mut self) -> Vec<Vec<usize>> {
+        let mut orbits = Orbits::new(self.graph.neighbors.len());
+        let mut generators = Vec::new();
+        for depth in (0..self.path.len() - 1).rev() {
+            let partition = self.path[depth].clone();
+            let target = partition.target_cell().unwrap();
+            let first = partition.order[target];
+            for &vertex in partition.cell(target) {
+                if orbits.find(vertex) == orbits.find(first) {
+                    continue;
+                }
+
+                let mut child = partition.clone();
+                child.individualize(vertex, self.graph);
+                self.nodes = 0;
+                if let Some(mapping) = self.find_leaf(child, depth + 1) {
+                    for (vertex, &image) in mapping.iter().enumerate() {
+                        orbits.union(vertex, image);
+                    }
+                    generators.push(mapping);
+                }
+            }
+        }
+        generators
+    }
+
+    /// Searches the subtree for a leaf mapped from the first leaf by an automorphism.
+    fn find_leaf(&mut self, partition: Partition, depth: usize) -> Option<Vec<usize>> {
+        self.nodes += 1;
+        if self.nodes > SEARCH_LIMIT || partition.shape() != self.shapes[depth] {
+            return None;
+        }
+
+        let target = match partition.target_cell() {
+            Some(target) => target,
+            None => {
+                let leaf = &self.path.last().unwrap().order;
+                let mut mapping = vec![0; leaf.len()];
+                for (&vertex, &image) in leaf.iter().zip(&partition.order) {
+                    mapping[vertex] = image;
+                }
+                return Some(mapping).filter(|mapping| self.graph.is_automorphism(mapping));
+            }
+        };
+        for &vertex in partition.cell(target) {
+            let mut child = partition.clone();
+            child.individualize(vertex, self.graph);
+            if let Some(mapping) = self.find_leaf(child, depth + 1) {
+                return Some(mapping);
+            }
+        }
+        None
+    }
+}
+
+/// Union-find of vertices in the same orbit.
+struct Orbits {
+    parents: Vec<usize>,
+}
+
+impl Orbits {
+    fn new(num_vertices: usize) -> Self {
+        Orbits {
+            parents: (0..num_vertices).collect(),
+        }
+    }
+
+    fn find(&mut self, vertex: usize) -> usize {
+        let parent = self.parents[vertex];
+        if parent == vertex {
+            return vertex;
+        }
+        let root = self.find(parent);
+        self.parents[vertex] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+    }
+}
+
+/// Finds generators of the symmetries of the clauses.
+/// Formulas with constraints other than clauses are assumed to have no symmetries.
+/// The search gives up on a generator after a fixed number of nodes,
+/// so some symmetries may be missed, but those found are always symmetries.
+pub fn find_symmetries(formula: &Cnf) -> Vec<Symmetry> {
+    if !formula.xors().is_empty()
+        || !formula.cardinalities().is_empty()
+        || !formula.pbs().is_empty()
+    {
+        return Vec::new();
+    }
+
+    // Only the variables in the clauses have vertices
+    let clauses = normalized_clauses(formula);
+    let mut variables: Vec<Variable> = clauses
+        .iter()
+        .flatten()
+        .map(|literal| literal.variable())
+        .collect();
+    variables.sort();
+    variables.dedup();
+    let mut vertex_of = vec![0; formula.num_variables()];
+    for (index, variable) in variables.iter().enumerate() {
+        vertex_of[variable.index()] = 2 * index;
+    }
+    let literal_vertex =
+        |literal: Literal| vertex_of[literal.index()] + if literal.positive() { 0 } else { 1 };
+
+    let num_literals = 2 * variables.len();
+    let mut neighbors = vec![Vec::new(); num_literals + clauses.len()];
+    for vertex in (0..num_literals).step_by(2) {
+        neighbors[vertex].push(vertex + 1);
+        neighbors[vertex + 1].push(vertex);
+    }
+    for (index, clause) in clauses.iter().enumerate() {
+        for &literal in clause {
+            neighbors[literal_vertex(literal)].push(num_literals + index);
+            neighbors[num_literals + index].push(literal_vertex(literal));
+        }
+    }
+    for list in &mut neighbors {
+        list.sort_unstable();
+    }
+    let colors = (0..neighbors.len())
+        .map(|vertex| if vertex < num_literals { 0 } else { 1 })
+        .collect();
+    let graph = Graph { neighbors, colors };
+
+    let generators = Search::new(&graph).generators();
+    debug!("Found {} symmetry generators", generators.len());
+    generators
+        .into_iter()
+        .map(|mapping| {
+            let mut images: Vec<Literal> = (0..formula.num_variables())
+                .map(|index| Literal::new(Variable::from_index(index).unwrap(), true))
+                .collect();
+            for (index, variable) in variables.iter().enumerate() {
+                let image = mapping[2 * index];
+                images[variable.index()] = Literal::new(variables[image / 2], image % 2 == 0);
+            }
+            Symmetry::new(images)
+        })
+        // Generators only permuting duplicate clauses are trivial
+        .filter(|symmetry| !symmetry.support().is_empty())
+        .collect()
+}
+
+/// Adds lex-leader symmetry-breaking clauses for each symmetry,
+/// with fresh variables numbered after those of the formula.
+pub fn break_symmetries(formula: &Cnf, symmetries: &[Symmetry]) -> Cnf {
+    let mut builder = CnfBuilder::from(formula.clone());
+    for symmetry in symmetries {
+        add_lex_leader(&mut builder, symmetry);
+    }
+    builder.build()
+}
+
+/// Adds clauses for `x ≤ σ(x)` in the lexicographic order of the support of `σ`.
+fn add_lex_leader(builder: &mut CnfBuilder, symmetry: &Symmetry) {
+    // Implied while the prefix of `x` is equal to that of `σ(x)`, true at first
+    let mut equal: Option<Literal> = None;
+    let support = symmetry.support();
+    for (index, &variable) in support.iter().enumerate() {
+        let literal = Literal::new(variable, true);
+        let image = symmetry.apply(literal);
+        let guarded = |literals: &[Literal]| {
+            Clause::new(
+                equal
+                    .iter()
+                    .map(|&equal| !equal)
+                    .chain(literals.iter().copied())
+                    .collect(),
+            )
+        };
+
+        if image == !literal {
+            // `x ≤ ¬x` forces `x` to be false, and the prefixes differ afterwards
+            builder.add_clause(guarded(&[!literal]));
+            return;
+        }
+        builder.add_clause(guarded(&[!literal, image]));
+        if index + 1 == support.len() {
+            return;
+        }
+
+        let next = builder.new_literal();
+        builder.add_clause(guarded(&[!literal, next]));
+        builder.add_clause(guarded(&[image, next]));
+        equal = Some(next);
+    }
+}
@@ -1,10 +1,56 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
 use paste::paste;
 
 use crate::{
-    parser::parse_file,
-    solver::{CdclSolver, DpllSolver, Solver},
+    aig::{Aig, AigLiteral, AndGate},
+    bmc::{self, BmcResult, TransitionSystem},
+    bv::{BitVector, Bool, BvOperator, Comparison},
+    encode::{AmkEncoding, AmoEncoding, CnfBuilder, PbEncoding},
+    expr::{Formula, Transformation},
+    formula::{Cardinality, Clause, Cnf, Literal, Objective, PbConstraint, Variable, XorClause},
+    parser::{parse_aiger_file, parse_file, parse_icnf_file, parse_opb_file, parse_qdimacs_file},
+    qbf::{Qbf, QbfResult, Quantifier},
+    smt::SmtSession,
+    solver::{
+        backbone, cube, luby, minimal_hitting_sets, minimize, minimum_correction_set, write_icnf,
+        BackboneResult, CdclConfig, CdclHeuristic, CdclRestart, CdclSolver, ClauseExchange,
+        CubeAndConquerSolver, CubeConfig, DpllHeuristic, DpllSolver, Interrupt,
+        LocalSearchAlgorithm, LocalSearchConfig, LocalSearchSolver, McsEnumerator, OptimizeResult,
+        PortfolioConfig, PortfolioSolver, PortfolioWorker, SharingConfig, SolveResult, Solver,
+        Statistics,
+    },
+    symmetry::{break_symmetries, find_symmetries, Symmetry},
 };
 
+/// A file in the temporary directory, removed when dropped even if the test fails.
+struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    fn new(name: &str, contents: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("satire-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        TempFile { path }
+    }
+}
+
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
 macro_rules! sat_testcase_with_solver {
     ($solver:ident, $dir:ident, $name: ident) => {
         paste! {
@@ -14,7 +60,7 @@ macro_rules! sat_testcase_with_solver {
                     concat!("testcases/", stringify!($dir), "/", stringify!($name), ".cnf")
                 ).unwrap();
                 let solver = $solver::new(formula);
-                assert!(solver.solve().is_some());
+                assert!(solver.solve().is_sat());
             }
         }
     };
@@ -29,7 +75,112 @@ macro_rules! unsat_testcase_with_solver {
                     concat!("testcases/", stringify!($dir), "/", stringify!($name), ".cnf")
                 ).unwrap();
                 let solver = $solver::new(formula);
-                assert!(solver.solve().is_none());
+                assert!(solver.solve().is_unsat());
+            }
+        }
+    };
+}
+
+macro_rules! cdcl_testcase_with_heuristic {
+    ($heuristic:ident, $dir:ident, $name:ident, $expected:expr) => {
+        paste! {
+            #[test]
+            fn [< cdcl_ $heuristic:lower _ $dir _ $name >]() {
+                let formula = parse_file(
+                    concat!("testcases/", stringify!($dir), "/", stringify!($name), ".cnf")
+                ).unwrap();
+                let config = CdclConfig {
+                    heuristic: CdclHeuristic::$heuristic,
+                    ..CdclConfig::default()
+                };
+                let solver = CdclSolver::with_config(formula, config);
+                let result = solver.solve();
+                assert!(if $expected { result.is_sat() } else { result.is_unsat() });
+            }
+        }
+    };
+}
+
+macro_rules! dpll_testcase_with_heuristic {
+    ($heuristic:ident, $dir:ident, $name:ident, $expected:expr) => {
+        paste! {
+            #[test]
+            fn [< dpll_ $heuristic:lower _ $dir _ $name >]() {
+                let formula = parse_file(
+                    concat!("testcases/", stringify!($dir), "/", stringify!($name), ".cnf")
+                ).unwrap();
+                let solver = DpllSolver::with_heuristic(formula, DpllHeuristic::$heuristic);
+                let result = solver.solve();
+                assert!(if $expected { result.is_sat() } else { result.is_unsat() });
+            }
+        }
+    };
+}
+
+macro_rules! heuristic_testcase {
+    ($dir:ident, $name:ident, $expected:expr) => {
+        cdcl_testcase_with_heuristic!(Vmtf, $dir, $name, $expected);
+        cdcl_testcase_with_heuristic!(Chb, $dir, $name, $expected);
+        cdcl_testcase_with_heuristic!(Lrb, $dir, $name, $expected);
+        dpll_testcase_with_heuristic!(Dlis, $dir, $name, $expected);
+        dpll_testcase_with_heuristic!(Dlcs, $dir, $name, $expected);
+        dpll_testcase_with_heuristic!(Moms, $dir, $name, $expected);
+        dpll_testcase_with_heuristic!(JeroslowWang, $dir, $name, $expected);
+        dpll_testcase_with_heuristic!(Bohm, $dir, $name, $expected);
+    };
+}
+
+macro_rules! local_search_testcase {
+    ($algorithm:ident, $dir:ident, $name:ident) => {
+        paste! {
+            #[test]
+            fn [< $algorithm:lower _ $dir _ $name >]() {
+                let formula = parse_file(
+                    concat!("testcases/", stringify!($dir), "/", stringify!($name), ".cnf")
+                ).unwrap();
+                let config = LocalSearchConfig {
+                    algorithm: LocalSearchAlgorithm::$algorithm,
+                    ..LocalSearchConfig::default()
+                };
+                let solver = LocalSearchSolver::with_config(formula, config);
+                assert!(solver.solve().is_sat());
+            }
+        }
+    };
+}
+
+macro_rules! portfolio_testcase {
+    ($dir:ident, $name:ident, $expected:expr) => {
+        paste! {
+            #[test]
+            fn [< portfolio_ $dir _ $name >]() {
+                let formula = parse_file(
+                    concat!("testcases/", stringify!($dir), "/", stringify!($name), ".cnf")
+                ).unwrap();
+                let config = PortfolioConfig::diversified(4, true);
+                let solver = PortfolioSolver::with_config(formula, config);
+                let result = solver.solve();
+                assert!(if $expected { result.is_sat() } else { result.is_unsat() });
+            }
+        }
+    };
+}
+
+macro_rules! cube_and_conquer_testcase {
+    ($dir:ident, $name:ident, $expected:expr) => {
+        paste! {
+            #[test]
+            fn [< cube_and_conquer_ $dir _ $name >]() {
+                let formula = parse_file(
+                    concat!("testcases/", stringify!($dir), "/", stringify!($name), ".cnf")
+                ).unwrap();
+                let config = CubeConfig {
+                    threads: 4,
+                    ..CubeConfig::default()
+                };
+                let solver = CubeAndConquerSolver::with_config(formula, config);
+                let result = solver.solve();
+                assert!(if $expected { result.is_sat() } else { result.is_unsat() });
             }
         }
     };
@@ -118,3 +269,2312 @@ sat_testcase!(satch_cnfs, sqrt16129);
 sat_testcase!(satch_cnfs, sqrt63001);
 sat_testcase!(satch_cnfs, sqrt259081);
 sat_testcase!(satch_cnfs, sqrt1042441);
+
+// Decision heuristics
+heuristic_testcase!(satch_cnfs, unit7, true);
+heuristic_testcase!(satch_cnfs, unit9, false);
+heuristic_testcase!(satch_cnfs, full4, false);
+heuristic_testcase!(satch_cnfs, add8, false);
+heuristic_testcase!(satch_cnfs, ph5, false);
+heuristic_testcase!(satch_cnfs, prime961, true);
+heuristic_testcase!(satch_cnfs, sqrt10201, true);
+
+#[test]
+fn vsids_decides_highest_score_first() {
+    let variable = |index: usize| Variable::from_index(index).unwrap();
+
+    // (x0 ∨ x1) ∧ (x0 ∨ x2) ∧ ... ∧ (x0 ∨ x20): x0 occurs most often and satisfies all
+    let mut formula = Cnf::new(21);
+    for index in 1..21 {
+        formula.add_clause(Clause::new(vec![
+            Literal::new(variable(0), true),
+            Literal::new(variable(index), true),
+        ]));
+    }
+    let config = CdclConfig {
+        heuristic: CdclHeuristic::Vsids,
+        ..CdclConfig::default()
+    };
+    let (result, stats) = CdclSolver::with_config(formula, config).solve_with_statistics();
+    assert!(result.is_sat());
+    // Deciding the lowest score first takes one decision per clause
+    assert_eq!(stats.decisions, 1);
+}
+
+#[test]
+fn cdcl_search_is_deterministic() {
+    let formula = parse_file("testcases/satch_cnfs/prime961.cnf").unwrap();
+    for heuristic in CdclHeuristic::ALL {
+        let config = CdclConfig {
+            heuristic,
+            seed: 7,
+            ..CdclConfig::default()
+        };
+        let [first, second] = [(); 2].map(|_| {
+            let solver = CdclSolver::with_config(formula.clone(), config.clone());
+            let (_, stats) = solver.solve_with_statistics();
+            (stats.decisions, stats.conflicts)
+        });
+        assert_eq!(first, second, "{}", heuristic);
+    }
+}
+
+// Local search
+local_search_testcase!(WALKSAT, satch_cnfs, true);
+local_search_testcase!(WALKSAT, satch_cnfs, unit7);
+local_search_testcase!(WALKSAT, satch_cnfs, prime25);
+local_search_testcase!(WALKSAT, satch_cnfs, sqrt10201);
+local_search_testcase!(PROBSAT, satch_cnfs, true);
+local_search_testcase!(PROBSAT, satch_cnfs, unit7);
+local_search_testcase!(PROBSAT, satch_cnfs, prime25);
+local_search_testcase!(PROBSAT, satch_cnfs, sqrt10201);
+
+/// Random 3-SAT formula whose clauses are all satisfied by a hidden assignment.
+fn planted_3sat(num_variables: usize, num_clauses: usize, seed: u64) -> Cnf {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let hidden: Vec<bool> = (0..num_variables).map(|_| rng.gen()).collect();
+
+    let mut formula = Cnf::new(num_variables);
+    while formula.clauses().len() < num_clauses {
+        let literals: Vec<Literal> = (0..3)
+            .map(|_| {
+                let index = rng.gen_range(0..num_variables);
+                Literal::new(Variable::from_index(index).unwrap(), rng.gen())
+            })
+            .collect();
+        if literals.iter().any(|literal| literal.value(&hidden)) {
+            formula.add_clause(Clause::new(literals));
+        }
+    }
+    formula
+}
+
+#[test]
+fn walksat_planted_3sat() {
+    let config = LocalSearchConfig {
+        algorithm: LocalSearchAlgorithm::WALKSAT,
+        ..LocalSearchConfig::default()
+    };
+    let solver = LocalSearchSolver::with_config(planted_3sat(500, 2000, 1), config);
+    assert!(solver.solve().is_sat());
+}
+
+#[test]
+fn probsat_planted_3sat() {
+    let solver = LocalSearchSolver::new(planted_3sat(500, 2000, 1));
+    assert!(solver.solve().is_sat());
+}
+
+#[test]
+fn cdcl_planted_3sat_with_local_search() {
+    for seed in 0..3 {
+        let solver = CdclSolver::new(planted_3sat(400, 1680, seed));
+        let (result, stats) = solver.solve_with_statistics();
+        assert!(result.is_sat());
+        // The walk on the first rephasing, after 1000 conflicts, finds a model
+        // that the search follows without further conflicts.
+        // Without it, these take over 10000 conflicts.
+        // The search is deterministic as ties between variable scores are broken
+        // with the seed of the configuration.
+        assert!(stats.timings.local_search > Duration::ZERO, "seed {}", seed);
+        assert!(stats.conflicts < 2000, "seed {}", seed);
+    }
+}
+
+#[test]
+fn local_search_unsat_is_unknown() {
+    let formula = parse_file("testcases/satch_cnfs/ph4.cnf").unwrap();
+    let config = LocalSearchConfig {
+        max_flips: 1000,
+        max_tries: 2,
+        ..LocalSearchConfig::default()
+    };
+    let solver = LocalSearchSolver::with_config(formula, config.clone());
+    assert!(solver.solve().is_unknown());
+
+    // Every assignment satisfies the other clauses
+    let file = TempFile::new("empty_clause.cnf", "p cnf 1 1\n0\n");
+    let formula = parse_file(&file).unwrap();
+    for algorithm in [LocalSearchAlgorithm::WALKSAT, LocalSearchAlgorithm::PROBSAT] {
+        let config = LocalSearchConfig {
+            algorithm,
+            ..config.clone()
+        };
+        let solver = LocalSearchSolver::with_config(formula.clone(), config);
+        assert!(solver.solve().is_unknown());
+    }
+}
+
+// Restart policies
+#[test]
+fn luby_sequence() {
+    let sequence: Vec<usize> = (0..15).map(luby).collect();
+    assert_eq!(sequence, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    assert_eq!(luby(30), 16);
+    assert_eq!(luby(31), 1);
+}
+
+#[test]
+fn cdcl_geometric_restart_satch_cnfs_add16() {
+    let formula = parse_file("testcases/satch_cnfs/add16.cnf").unwrap();
+    let config = CdclConfig {
+        restart: CdclRestart::Geometric,
+        ..CdclConfig::default()
+    };
+    let solver = CdclSolver::with_config(formula, config);
+    assert!(solver.solve().is_unsat());
+}
+
+// Vivification
+#[test]
+fn cdcl_vivification_shortens_redundant_clause() {
+    for (name, expected) in [("add16", false), ("prime1369", true)] {
+        let original = parse_file(format!("testcases/satch_cnfs/{}.cnf", name)).unwrap();
+        let num_variables = original.num_variables();
+        let literal = |offset: usize, positive: bool| {
+            Literal::new(
+                Variable::from_index(num_variables + offset).unwrap(),
+                positive,
+            )
+        };
+
+        // (x ∨ y) ∧ (¬y ∨ z) imply (x ∨ z), so w is redundant in (x ∨ z ∨ w).
+        // The clauses come first to be vivified on the first restart.
+        let mut formula = Cnf::new(num_variables + 4);
+        formula.add_clause(Clause::new(vec![literal(0, true), literal(1, true)]));
+        formula.add_clause(Clause::new(vec![literal(1, false), literal(2, true)]));
+        formula.add_clause(Clause::new(vec![
+            literal(0, true),
+            literal(2, true),
+            literal(3, true),
+        ]));
+        for clause in original.clauses() {
+            formula.add_clause(clause.clone());
+        }
+
+        let (result, stats) = CdclSolver::new(formula).solve_with_statistics();
+        assert_eq!(result.is_sat(), expected, "{}", name);
+        assert!(stats.restarts > 0, "{}", name);
+        assert!(
+            stats.deleted_clauses > 0 && stats.vivified_literals > 0,
+            "{}",
+            name
+        );
+    }
+}
+
+// Chronological backtracking
+#[test]
+fn cdcl_always_chronological_backtracking() {
+    use rand::{rngs::StdRng, seq::index::sample, Rng, SeedableRng};
+
+    let config = CdclConfig {
+        chrono_threshold: 0,
+        ..CdclConfig::default()
+    };
+    for (name, expected) in [("add16", false), ("prime961", true), ("sqrt10201", true)] {
+        let formula = parse_file(format!("testcases/satch_cnfs/{}.cnf", name)).unwrap();
+        let result = CdclSolver::with_config(formula, config.clone()).solve();
+        assert_eq!(result.is_sat(), expected, "{}", name);
+    }
+
+    for seed in 0..20 {
+        let mut formula = planted_3sat(60, 240, seed);
+        for xor in random_xors(60, 10, 3, seed, true).xors() {
+            formula.add_xor(xor.clone());
+        }
+        random_pbs(&mut formula, 8, seed);
+        let expected = DpllSolver::new(formula.clone()).solve().is_sat();
+        let result = CdclSolver::with_config(formula, config.clone()).solve();
+        assert_eq!(result.is_sat(), expected, "seed {}", seed);
+    }
+
+    // Assumptions are decisions too, so failed ones are analyzed on an out-of-order trail
+    let mut rng = StdRng::seed_from_u64(48);
+    let mut num_failed = 0;
+    for _ in 0..20 {
+        let formula = planted_3sat(40, 160, rng.gen());
+        let mut solver = CdclSolver::with_config(formula.clone(), config.clone());
+        for _ in 0..5 {
+            let assumptions: Vec<Literal> = sample(&mut rng, 40, 12)
+                .iter()
+                .map(|index| Literal::new(Variable::from_index(index).unwrap(), rng.gen()))
+                .collect();
+            let restricted = |literals: &[Literal]| {
+                let mut restricted = formula.clone();
+                for &literal in literals {
+                    restricted.add_clause(Clause::new(vec![literal]));
+                }
+                restricted
+            };
+            let expected = DpllSolver::new(restricted(&assumptions)).solve().is_sat();
+            assert_eq!(
+                solver.solve_with_assumptions(&assumptions).is_sat(),
+                expected
+            );
+            if !expected {
+                let failed = solver.failed_assumptions();
+                assert!(failed.iter().all(|literal| assumptions.contains(literal)));
+                assert!(DpllSolver::new(restricted(failed)).solve().is_unsat());
+                num_failed += 1;
+            }
+        }
+
+        let sorted_backbone = |config: CdclConfig| match backbone(formula.clone(), config) {
+            BackboneResult::Backbone(mut literals) => {
+                literals.sort();
+                literals
+            }
+            result => panic!("Unexpected {:?}", result),
+        };
+        assert_eq!(
+            sorted_backbone(config.clone()),
+            sorted_backbone(CdclConfig::default())
+        );
+    }
+    assert!(num_failed > 10);
+}
+
+// Search modes
+#[test]
+fn cdcl_mode_switching() {
+    let config = CdclConfig {
+        mode_switching: true,
+        ..CdclConfig::default()
+    };
+    // Long enough to switch modes
+    for (name, expected) in [("add64", false), ("prime2209", true)] {
+        let formula = parse_file(format!("testcases/satch_cnfs/{}.cnf", name)).unwrap();
+        let (result, stats) =
+            CdclSolver::with_config(formula, config.clone()).solve_with_statistics();
+        assert_eq!(result.is_sat(), expected, "{}", name);
+        assert!(stats.mode_switches > 0, "{}", name);
+        // The walk waits for the stable mode
+        assert!(stats.timings.local_search > Duration::ZERO, "{}", name);
+    }
+
+    for seed in 0..20 {
+        let formula = planted_3sat(60, 240, seed);
+        let expected = DpllSolver::new(formula.clone()).solve().is_sat();
+        let result = CdclSolver::with_config(formula, config.clone()).solve();
+        assert_eq!(result.is_sat(), expected, "seed {}", seed);
+    }
+}
+
+// Statistics
+#[test]
+fn solver_statistics() {
+    let formula = parse_file("testcases/satch_cnfs/add16.cnf").unwrap();
+    let config = CdclConfig {
+        phase_timings: true,
+        ..CdclConfig::default()
+    };
+    let mut solver = CdclSolver::with_config(formula.clone(), config);
+    assert!(solver.solve_with_assumptions(&[]).is_unsat());
+    let stats = solver.statistics().clone();
+    assert!(stats.decisions > 0 && stats.propagations > 0 && stats.max_decision_level > 0);
+    assert!(stats.conflicts >= stats.learned_clauses && stats.learned_clauses > 0);
+    assert_eq!(
+        stats.lbd_histogram.iter().sum::<usize>(),
+        stats.learned_clauses
+    );
+    assert!(stats.average_learned_len() >= 1.0);
+    assert!(stats.memory > 0);
+    let timings = &stats.timings;
+    assert!(timings.propagation > Duration::ZERO && timings.conflict_analysis > Duration::ZERO);
+    assert!(timings.total >= timings.propagation + timings.conflict_analysis);
+
+    // Counters accumulate over calls
+    solver.solve_with_assumptions(&[]);
+    assert!(solver.statistics().timings.total > stats.timings.total);
+    let (result, stats) = CdclSolver::new(formula.clone()).solve_with_statistics();
+    assert!(result.is_unsat() && stats.conflicts > 0);
+    assert!(stats.timings.propagation > Duration::ZERO);
+
+    // Phases are only timed on demand
+    let mut solver = CdclSolver::new(formula);
+    solver.solve_with_assumptions(&[]);
+    let timings = &solver.statistics().timings;
+    assert!(timings.total > Duration::ZERO);
+    assert_eq!(
+        timings.propagation + timings.conflict_analysis,
+        Duration::ZERO
+    );
+
+    let formula = parse_file("testcases/satch_cnfs/ph4.cnf").unwrap();
+    let (result, stats) = DpllSolver::new(formula.clone()).solve_with_statistics();
+    assert!(result.is_unsat());
+    assert!(stats.decisions > 0 && stats.conflicts > 0);
+    assert_eq!(stats.learned_clauses, 0);
+
+    // Solvers without counters return empty statistics
+    let config = LocalSearchConfig {
+        max_flips: 1000,
+        max_tries: 2,
+        ..LocalSearchConfig::default()
+    };
+    let (_, stats) = LocalSearchSolver::with_config(formula, config).solve_with_statistics();
+    assert_eq!(stats, Statistics::default());
+}
+
+// Portfolio
+portfolio_testcase!(satch_cnfs, unit7, true);
+portfolio_testcase!(satch_cnfs, unit9, false);
+portfolio_testcase!(satch_cnfs, add16, false);
+portfolio_testcase!(satch_cnfs, ph5, false);
+portfolio_testcase!(satch_cnfs, prime961, true);
+portfolio_testcase!(satch_cnfs, sqrt10201, true);
+
+#[test]
+fn portfolio_without_sharing_satch_cnfs_add16() {
+    let formula = parse_file("testcases/satch_cnfs/add16.cnf").unwrap();
+    let config = PortfolioConfig {
+        sharing: None,
+        ..PortfolioConfig::diversified(4, false)
+    };
+    let solver = PortfolioSolver::with_config(formula, config);
+    assert!(solver.solve().is_unsat());
+}
+
+#[test]
+fn portfolio_sharing_with_dpll_worker() {
+    // The DPLL worker does not exchange clauses, so it must not hold them back
+    let exchange = ClauseExchange::new(SharingConfig::default());
+    let mut cdcl = PortfolioWorker::Cdcl(CdclConfig::default())
+        .connect(&exchange, 0)
+        .unwrap();
+    assert!(PortfolioWorker::Dpll(DpllHeuristic::default())
+        .connect(&exchange, 1)
+        .is_none());
+    let unit = Clause::new(vec![Literal::new(Variable::from_index(0).unwrap(), true)]);
+    cdcl.export(&unit, 1);
+    cdcl.exchange();
+    assert_eq!(exchange.num_clauses(), 0);
+
+    let config = PortfolioConfig {
+        workers: vec![
+            PortfolioWorker::Cdcl(CdclConfig::default()),
+            PortfolioWorker::Dpll(DpllHeuristic::default()),
+        ],
+        sharing: Some(SharingConfig::default()),
+    };
+    for (name, expected) in [("ph6", false), ("prime1369", true)] {
+        let formula = parse_file(format!("testcases/satch_cnfs/{}.cnf", name)).unwrap();
+        let solver = PortfolioSolver::with_config(formula, config.clone());
+        assert_eq!(solver.solve().is_sat(), expected, "{}", name);
+    }
+}
+
+#[test]
+fn portfolio_sharing_with_pb_encodings() {
+    // Workers encoding the constraints have auxiliary variables, numbered differently
+    let workers = std::iter::once(None)
+        .chain(PbEncoding::ALL.map(Some))
+        .map(|pb_encoding| {
+            PortfolioWorker::Cdcl(CdclConfig {
+                pb_encoding,
+                ..CdclConfig::default()
+            })
+        })
+        .collect();
+    let config = PortfolioConfig {
+        workers,
+        sharing: Some(SharingConfig {
+            max_size: usize::MAX,
+            max_lbd: usize::MAX,
+        }),
+    };
+
+    // Long enough for every worker to restart and exchange clauses
+    for (num_pigeons, num_holes) in [(7, 6), (9, 9)] {
+        let cardinality = pigeonhole_cardinality(num_pigeons, num_holes);
+        let mut formula = Cnf::new(cardinality.num_variables());
+        for clause in cardinality.clauses() {
+            formula.add_clause(clause.clone());
+        }
+        for constraint in cardinality.cardinalities() {
+            formula.add_pb(PbConstraint::from(constraint));
+        }
+        let solver = PortfolioSolver::with_config(formula, config.clone());
+        assert_eq!(solver.solve().is_sat(), num_pigeons <= num_holes);
+    }
+}
+
+#[test]
+fn clause_exchange_filters_and_deduplicates() {
+    let literal =
+        |index: usize, positive: bool| Literal::new(Variable::from_index(index).unwrap(), positive);
+    let short = Clause::new(vec![literal(0, true), literal(1, false)]);
+    let short_permuted = Clause::new(vec![literal(1, false), literal(0, true)]);
+    let long = Clause::new((0..5).map(|index| literal(index, true)).collect());
+
+    let exchange = ClauseExchange::new(SharingConfig {
+        max_size: 2,
+        max_lbd: 2,
+    });
+    let mut first = exchange.connect(0);
+    let mut second = exchange.connect(1);
+
+    first.export(&short, 2);
+    first.export(&long, 5);
+    second.export(&short_permuted, 2);
+    second.export(&long, 2);
+
+    // Own clauses are not imported back
+    assert!(first.exchange().is_empty());
+    let imported = second.exchange();
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].len(), 2);
+
+    // The permuted duplicate was dropped, and the low-LBD long clause is shared
+    let imported = first.exchange();
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].len(), 5);
+}
+
+#[test]
+fn clause_exchange_drops_clauses_read_by_all() {
+    let clause = |index: usize| {
+        Clause::new(vec![Literal::new(
+            Variable::from_index(index).unwrap(),
+            true,
+        )])
+    };
+    let exchange = ClauseExchange::new(SharingConfig::default());
+    let mut first = exchange.connect(0);
+    let mut second = exchange.connect(1);
+    let mut third = exchange.connect(2);
+
+    first.export(&clause(0), 1);
+    first.exchange();
+    second.exchange();
+    // Kept until the third worker reads it
+    assert_eq!(exchange.connect(3).exchange().len(), 1);
+    assert_eq!(third.exchange().len(), 1);
+    assert!(exchange.connect(4).exchange().is_empty());
+
+    // Disconnected workers do not hold clauses back
+    drop(third);
+    second.export(&clause(1), 1);
+    second.exchange();
+    assert_eq!(first.exchange().len(), 1);
+    assert!(exchange.connect(5).exchange().is_empty());
+}
+
+// Cube-and-conquer
+cube_and_conquer_testcase!(satch_cnfs, unit7, true);
+cube_and_conquer_testcase!(satch_cnfs, unit9, false);
+cube_and_conquer_testcase!(satch_cnfs, add16, false);
+cube_and_conquer_testcase!(satch_cnfs, ph6, false);
+cube_and_conquer_testcase!(satch_cnfs, prime961, true);
+cube_and_conquer_testcase!(satch_cnfs, sqrt10201, true);
+
+#[test]
+fn cdcl_solve_with_assumptions() {
+    let variable = |index: usize| Variable::from_index(index).unwrap();
+
+    // (x1 ∨ x2) ∧ (¬x1 ∨ x3) ∧ (¬x2 ∨ x3)
+    let mut formula = Cnf::new(3);
+    formula.add_clause(Clause::new(vec![
+        Literal::new(variable(0), true),
+        Literal::new(variable(1), true),
+    ]));
+    formula.add_clause(Clause::new(vec![
+        Literal::new(variable(0), false),
+        Literal::new(variable(2), true),
+    ]));
+    formula.add_clause(Clause::new(vec![
+        Literal::new(variable(1), false),
+        Literal::new(variable(2), true),
+    ]));
+
+    let mut solver = CdclSolver::new(formula);
+    let result = solver.solve_with_assumptions(&[Literal::new(variable(1), false)]);
+    let model = result.model().unwrap();
+    assert!(model.assignment()[0] && !model.assignment()[1] && model.assignment()[2]);
+
+    assert!(solver
+        .solve_with_assumptions(&[Literal::new(variable(2), false)])
+        .is_unsat());
+    assert_eq!(
+        solver.failed_assumptions(),
+        [Literal::new(variable(2), false)]
+    );
+    assert!(solver
+        .solve_with_assumptions(&[
+            Literal::new(variable(2), true),
+            Literal::new(variable(0), false),
+            Literal::new(variable(1), false)
+        ])
+        .is_unsat());
+    let mut failed = solver.failed_assumptions().to_vec();
+    failed.sort();
+    assert_eq!(
+        failed,
+        [
+            Literal::new(variable(0), false),
+            Literal::new(variable(1), false)
+        ]
+    );
+    assert!(solver.solve_with_assumptions(&[]).is_sat());
+    assert!(solver.failed_assumptions().is_empty());
+}
+
+/// Returns all models of a small formula.
+fn enumerate_models(formula: &Cnf) -> Vec<Vec<bool>> {
+    let num_variables = formula.num_variables();
+    (0..1u32 << num_variables)
+        .map(|bits| {
+            (0..num_variables)
+                .map(|index| bits >> index & 1 == 1)
+                .collect::<Vec<bool>>()
+        })
+        .filter(|assignment| {
+            formula
+                .clauses()
+                .iter()
+                .all(|clause| clause.iter().any(|literal| literal.value(assignment)))
+        })
+        .collect()
+}
+
+#[test]
+fn failed_assumptions_are_unsatisfiable() {
+    use rand::{rngs::StdRng, seq::index::sample, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(45);
+    let num_variables = 10;
+    let mut num_failed = 0;
+    for _ in 0..100 {
+        let formula = planted_3sat(num_variables, 30, rng.gen());
+        let mut solver = CdclSolver::new(formula.clone());
+        for _ in 0..5 {
+            let assumptions: Vec<Literal> = sample(&mut rng, num_variables, 5)
+                .iter()
+                .map(|index| Literal::new(Variable::from_index(index).unwrap(), rng.gen()))
+                .collect();
+            if !solver.solve_with_assumptions(&assumptions).is_unsat() {
+                continue;
+            }
+
+            let failed = solver.failed_assumptions();
+            assert!(failed.iter().all(|literal| assumptions.contains(literal)));
+            let mut restricted = formula.clone();
+            for &literal in failed {
+                restricted.add_clause(Clause::new(vec![literal]));
+            }
+            assert!(enumerate_models(&restricted).is_empty());
+            num_failed += 1;
+        }
+    }
+    assert!(num_failed > 50);
+}
+
+#[test]
+fn cdcl_add_clauses_incrementally() {
+    use rand::{rngs::StdRng, seq::index::sample, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(41);
+    let configs = CdclHeuristic::ALL
+        .iter()
+        .map(|&heuristic| CdclConfig {
+            heuristic,
+            ..CdclConfig::default()
+        })
+        .chain([CdclConfig {
+            mode_switching: true,
+            ..CdclConfig::default()
+        }]);
+    let mut num_unsat = 0;
+    for config in configs {
+        for _ in 0..5 {
+            // Random 3-SAT clauses over up to 12 variables, added with their variables
+            let mut formula = Cnf::new(0);
+            let mut solver = CdclSolver::with_config(Cnf::new(0), config.clone());
+            for _ in 0..60 {
+                let clause = Clause::new(
+                    sample(&mut rng, 12, 3)
+                        .iter()
+                        .map(|index| Literal::new(Variable::from_index(index).unwrap(), rng.gen()))
+                        .collect(),
+                );
+                while clause
+                    .iter()
+                    .any(|literal| literal.index() >= formula.num_variables())
+                {
+                    formula.new_variable();
+                    solver.new_variable();
+                }
+                formula.add_clause(clause.clone());
+                solver.add_clause(clause);
+
+                let expected = !enumerate_models(&formula).is_empty();
+                assert_eq!(solver.solve_with_assumptions(&[]).is_sat(), expected);
+                if !expected {
+                    num_unsat += 1;
+                    break;
+                }
+            }
+        }
+    }
+    assert!(num_unsat > 5);
+}
+
+/// Returns the minimal subsets of soft clauses, as sorted indices,
+/// whose inclusion (or removal) makes the formula satisfiable (or not).
+fn minimal_soft_subsets(hard: &Cnf, soft: &[Clause], satisfiable: bool) -> Vec<Vec<usize>> {
+    let subsets: Vec<Vec<usize>> = (0..1u32 << soft.len())
+        .map(|bits| {
+            (0..soft.len())
+                .filter(|index| bits >> index & 1 == 1)
+                .collect::<Vec<usize>>()
+        })
+        .filter(|subset| {
+            let mut formula = hard.clone();
+            for (index, clause) in soft.iter().enumerate() {
+                if subset.contains(&index) != satisfiable {
+                    formula.add_clause(clause.clone());
+                }
+            }
+            enumerate_models(&formula).is_empty() != satisfiable
+        })
+        .collect();
+    let mut minimal: Vec<Vec<usize>> = subsets
+        .iter()
+        .filter(|subset| {
+            !subsets.iter().any(|other| {
+                other.len() < subset.len() && other.iter().all(|index| subset.contains(index))
+            })
+        })
+        .cloned()
+        .collect();
+    minimal.sort();
+    minimal
+}
+
+#[test]
+fn mcs_and_mus_duality() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(46);
+    let num_variables = 6;
+    for _ in 0..40 {
+        let random_clause = |rng: &mut StdRng| {
+            Clause::new(
+                (0..rng.gen_range(1..3))
+                    .map(|_| {
+                        let index = rng.gen_range(0..num_variables);
+                        Literal::new(Variable::from_index(index).unwrap(), rng.gen())
+                    })
+                    .collect(),
+            )
+        };
+        let mut hard = Cnf::new(num_variables);
+        for _ in 0..rng.gen_range(0..3) {
+            hard.add_clause(random_clause(&mut rng));
+        }
+        let soft: Vec<Clause> = (0..rng.gen_range(4..10))
+            .map(|_| random_clause(&mut rng))
+            .collect();
+
+        let mut mcses: Vec<Vec<usize>> =
+            McsEnumerator::new(&hard, soft.clone(), CdclConfig::default()).collect();
+        let mut muses = minimal_hitting_sets(&mcses, CdclConfig::default());
+        let minimum = minimum_correction_set(&hard, &soft, CdclSolver::new);
+        mcses.sort();
+        muses.sort();
+
+        let expected_mcses = minimal_soft_subsets(&hard, &soft, true);
+        assert_eq!(mcses, expected_mcses);
+        if enumerate_models(&hard).is_empty() {
+            assert!(mcses.is_empty() && minimum.is_none());
+            continue;
+        }
+        assert_eq!(muses, minimal_soft_subsets(&hard, &soft, false));
+        assert_eq!(
+            minimum.map(|minimum| minimum.len()),
+            expected_mcses.iter().map(|mcs| mcs.len()).min()
+        );
+    }
+
+    // Dropping x1, or one requirement from each pair
+    let formula = parse_file("testcases/mcs/conflicts.cnf").unwrap();
+    let hard = Cnf::new(formula.num_variables());
+    let mut mcses: Vec<Vec<usize>> =
+        McsEnumerator::new(&hard, formula.clauses().to_vec(), CdclConfig::default()).collect();
+    mcses.sort();
+    assert_eq!(
+        mcses,
+        [vec![0], vec![1, 3], vec![1, 4], vec![2, 3], vec![2, 4]]
+    );
+    let mut muses = minimal_hitting_sets(&mcses, CdclConfig::default());
+    muses.sort();
+    assert_eq!(muses, [vec![0, 1, 2], vec![0, 3, 4]]);
+}
+
+#[test]
+fn backbone_matches_enumeration() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(45);
+    for num_clauses in (10..60).step_by(2) {
+        let formula = planted_3sat(12, num_clauses, rng.gen());
+        let models = enumerate_models(&formula);
+        let expected: Vec<Literal> = (0..formula.num_variables())
+            .filter(|&index| models.iter().all(|model| model[index] == models[0][index]))
+            .map(|index| Literal::new(Variable::from_index(index).unwrap(), models[0][index]))
+            .collect();
+
+        let config = CdclConfig {
+            seed: rng.gen(),
+            ..CdclConfig::default()
+        };
+        assert_eq!(
+            backbone(formula, config),
+            BackboneResult::Backbone(expected)
+        );
+    }
+
+    let formula = parse_file("testcases/satch_cnfs/ph4.cnf").unwrap();
+    assert_eq!(
+        backbone(formula, CdclConfig::default()),
+        BackboneResult::Unsat
+    );
+}
+
+#[test]
+fn cube_and_conquer_icnf_round_trip() {
+    let formula = parse_file("testcases/satch_cnfs/ph5.cnf").unwrap();
+    let config = CubeConfig {
+        max_depth: 4,
+        ..CubeConfig::default()
+    };
+    let cubes = cube(&formula, &config);
+
+    let mut icnf = Vec::new();
+    write_icnf(&mut icnf, &formula, &cubes).unwrap();
+    let file = TempFile::new("ph5.icnf", &String::from_utf8(icnf).unwrap());
+
+    let (parsed_formula, parsed_cubes) = parse_icnf_file(&file).unwrap();
+    assert_eq!(parsed_formula.num_variables(), formula.num_variables());
+    assert_eq!(parsed_formula.clauses().len(), formula.clauses().len());
+    assert_eq!(parsed_cubes, cubes);
+
+    let solver = CubeAndConquerSolver::with_cubes(parsed_formula, parsed_cubes, config);
+    assert!(solver.solve().is_unsat());
+}
+
+#[test]
+fn cube_and_conquer_icnf_without_cubes() {
+    let file = TempFile::new("no_cubes.icnf", "p inccnf\n1 2 0\n");
+    let (formula, cubes) = parse_icnf_file(&file).unwrap();
+    assert!(cubes.is_empty());
+
+    let solver = CubeAndConquerSolver::with_cubes(formula, cubes, CubeConfig::default());
+    assert!(solver.solve().is_sat());
+}
+
+#[test]
+fn interrupted_solvers_return_unknown() {
+    let formula = parse_file("testcases/satch_cnfs/ph5.cnf").unwrap();
+    let interrupt = Interrupt::new();
+    interrupt.interrupt();
+
+    let mut cdcl = CdclSolver::new(formula.clone());
+    cdcl.set_interrupt(interrupt.clone());
+    assert!(cdcl.solve().is_unknown());
+
+    let mut dpll = DpllSolver::new(formula.clone());
+    dpll.set_interrupt(interrupt.clone());
+    assert!(dpll.solve().is_unknown());
+
+    let mut portfolio =
+        PortfolioSolver::with_config(formula, PortfolioConfig::diversified(2, true));
+    portfolio.set_interrupt(interrupt);
+    assert!(portfolio.solve().is_unknown());
+}
+
+#[test]
+fn interrupt_running_solvers() {
+    /// Solves the formula on another thread, interrupts the solver, and returns its result.
+    fn interrupt_after_start<S: Solver>(formula: Cnf) -> SolveResult {
+        let interrupt = Interrupt::new();
+        let handle = std::thread::spawn({
+            let interrupt = interrupt.clone();
+            move || {
+                let mut solver = S::new(formula);
+                solver.set_interrupt(interrupt);
+                solver.solve()
+            }
+        });
+        std::thread::sleep(Duration::from_millis(100));
+        let start = Instant::now();
+        interrupt.interrupt();
+        let result = handle.join().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        result
+    }
+
+    // These take minutes without an interrupt
+    let formula = parse_file("testcases/satch_cnfs/prime4294967297.cnf").unwrap();
+    assert!(interrupt_after_start::<CdclSolver>(formula.clone()).is_unknown());
+    assert!(interrupt_after_start::<PortfolioSolver>(formula).is_unknown());
+    let formula = parse_file("testcases/satch_cnfs/add128.cnf").unwrap();
+    assert!(interrupt_after_start::<DpllSolver>(formula).is_unknown());
+}
+
+/// `x1 ∧ (x1 → x2) ∧ ... ∧ (x{n-1} → xn) ∧ ¬xn`
+fn implication_chain(num_variables: usize) -> Cnf {
+    let literal =
+        |index: usize, positive: bool| Literal::new(Variable::from_index(index).unwrap(), positive);
+
+    let mut formula = Cnf::new(num_variables);
+    formula.add_clause(Clause::new(vec![literal(0, true)]));
+    for index in 1..num_variables {
+        formula.add_clause(Clause::new(vec![
+            literal(index - 1, false),
+            literal(index, true),
+        ]));
+    }
+    formula.add_clause(Clause::new(vec![literal(num_variables - 1, false)]));
+    formula
+}
+
+#[test]
+fn dpll_long_implication_chain() {
+    let solver = DpllSolver::new(implication_chain(100_000));
+    assert!(solver.solve().is_unsat());
+}
+
+// XOR constraints
+/// A random system of XOR clauses with `len` variables each.
+/// If `consistent` is false, the last XOR contradicts the sum of the first five.
+fn random_xors(
+    num_variables: usize,
+    num_xors: usize,
+    len: usize,
+    seed: u64,
+    consistent: bool,
+) -> Cnf {
+    use rand::{rngs::StdRng, seq::index::sample, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let hidden: Vec<bool> = (0..num_variables).map(|_| rng.gen()).collect();
+
+    let mut formula = Cnf::new(num_variables);
+    for _ in 0..num_xors {
+        let variables = sample(&mut rng, num_variables, len)
+            .into_iter()
+            .map(|index| Variable::from_index(index).unwrap())
+            .collect::<Vec<_>>();
+        let parity = variables
+            .iter()
+            .fold(false, |parity, variable| parity ^ variable.value(&hidden));
+        formula.add_xor(XorClause::with_parity(variables, parity));
+    }
+
+    if !consistent {
+        let (variables, parity) =
+            formula.xors()[..5]
+                .iter()
+                .fold((Vec::new(), true), |(mut variables, parity), xor| {
+                    variables.extend_from_slice(xor.variables());
+                    (variables, parity ^ xor.parity())
+                });
+        formula.add_xor(XorClause::with_parity(variables, parity));
+    }
+    formula
+}
+
+#[test]
+fn xor_clause_parity() {
+    let literal =
+        |index: usize, positive: bool| Literal::new(Variable::from_index(index).unwrap(), positive);
+
+    // x1 ⊕ ¬x2 ⊕ x1 = ¬x2
+    let xor = XorClause::new(vec![literal(0, true), literal(1, false), literal(0, true)]);
+    assert_eq!(xor.variables(), &[Variable::from_index(1).unwrap()]);
+    assert!(!xor.parity());
+    assert!(xor.value(&[true, false]));
+    assert!(!xor.value(&[true, true]));
+}
+
+#[test]
+fn parse_xor_clauses() {
+    let file = TempFile::new("xor.cnf", "p cnf 3 3\n1 2 0\nx1 -2 3 0\nx 2 3 0\n");
+    let formula = parse_file(&file).unwrap();
+
+    assert_eq!(formula.clauses().len(), 1);
+    assert_eq!(formula.xors().len(), 2);
+    assert!(!formula.xors()[0].parity());
+    assert!(formula.xors()[1].parity());
+
+    let result = CdclSolver::new(formula.clone()).solve();
+    let model = result.model().unwrap();
+    assert!(formula
+        .xors()
+        .iter()
+        .all(|xor| xor.value(model.assignment())));
+    assert!(DpllSolver::new(formula).solve().is_sat());
+}
+
+#[test]
+fn xor_system_sat() {
+    let formula = random_xors(300, 280, 4, 1, true);
+    assert!(CdclSolver::new(formula.clone()).solve().is_sat());
+    assert!(DpllSolver::new(random_xors(40, 30, 4, 1, true))
+        .solve()
+        .is_sat());
+}
+
+#[test]
+fn xor_system_unsat() {
+    let formula = random_xors(300, 280, 4, 2, false);
+    assert!(CdclSolver::new(formula).solve().is_unsat());
+    assert!(DpllSolver::new(random_xors(40, 30, 4, 2, false))
+        .solve()
+        .is_unsat());
+}
+
+#[test]
+fn cdcl_detects_encoded_xors() {
+    // Plain CDCL needs exponentially many conflicts on this formula
+    let formula = random_xors(300, 280, 4, 3, false).encode_xors();
+    assert!(formula.xors().is_empty());
+    assert!(CdclSolver::new(formula).solve().is_unsat());
+}
+
+#[test]
+fn cdcl_xors_with_clauses() {
+    for seed in 0..20 {
+        let mut formula = planted_3sat(100, 250, seed);
+        for xor in random_xors(100, 35, 3, seed, true).xors() {
+            formula.add_xor(xor.clone());
+        }
+        let expected = DpllSolver::new(formula.clone()).solve().is_sat();
+        let result = CdclSolver::new(formula).solve();
+        assert_eq!(result.is_sat(), expected);
+    }
+}
+
+#[test]
+fn cube_and_conquer_xors() {
+    let config = CubeConfig {
+        max_depth: 4,
+        threads: 2,
+        ..CubeConfig::default()
+    };
+    for consistent in [true, false] {
+        let formula = random_xors(60, 50, 4, 4, consistent);
+        let cubes = cube(&formula, &config);
+        // Not on the auxiliary variables of the encoding
+        assert!(cubes
+            .iter()
+            .flatten()
+            .all(|literal| literal.index() < formula.num_variables()));
+        let solver = CubeAndConquerSolver::with_cubes(formula, cubes, config.clone());
+        assert_eq!(solver.solve().is_sat(), consistent);
+    }
+
+    // x1 ⊕ x2 ∧ x1 ∧ x2 would become satisfiable without the XOR
+    let file = TempFile::new("cube_xor.cnf", "p cnf 2 3\nx1 2 0\n1 0\n2 0\n");
+    let formula = parse_file(&file).unwrap();
+    let error = write_icnf(&mut Vec::new(), &formula, &cube(&formula, &config)).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+// Cardinality constraints
+/// Each pigeon sits in a hole, and each hole has at most one pigeon.
+fn pigeonhole_cardinality(num_pigeons: usize, num_holes: usize) -> Cnf {
+    let literal = |pigeon: usize, hole: usize| {
+        Literal::new(
+            Variable::from_index(pigeon * num_holes + hole).unwrap(),
+            true,
+        )
+    };
+
+    let mut formula = Cnf::new(num_pigeons * num_holes);
+    for pigeon in 0..num_pigeons {
+        formula.add_clause(Clause::new(
+            (0..num_holes).map(|hole| literal(pigeon, hole)).collect(),
+        ));
+    }
+    for hole in 0..num_holes {
+        formula.add_cardinality(Cardinality::at_most(
+            (0..num_pigeons)
+                .map(|pigeon| literal(pigeon, hole))
+                .collect(),
+            1,
+        ));
+    }
+    formula
+}
+
+/// Random cardinality constraints of various sizes and bounds.
+fn random_cardinalities(formula: &mut Cnf, num_constraints: usize, seed: u64) {
+    use rand::{rngs::StdRng, seq::index::sample, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..num_constraints {
+        let size = rng.gen_range(3..=8);
+        let literals: Vec<Literal> = sample(&mut rng, formula.num_variables(), size)
+            .into_iter()
+            .map(|index| Literal::new(Variable::from_index(index).unwrap(), rng.gen()))
+            .collect();
+        let bound = rng.gen_range(1..size);
+        let constraint = if rng.gen() {
+            Cardinality::at_most(literals, bound)
+        } else {
+            Cardinality::at_least(literals, bound)
+        };
+        formula.add_cardinality(constraint);
+    }
+}
+
+#[test]
+fn cardinality_at_most() {
+    let literal =
+        |index: usize, positive: bool| Literal::new(Variable::from_index(index).unwrap(), positive);
+
+    // x1 + ¬x2 + x3 ≤ 1  <=>  ¬x1 + x2 + ¬x3 ≥ 2
+    let constraint = Cardinality::at_most(
+        vec![literal(0, true), literal(1, false), literal(2, true)],
+        1,
+    );
+    assert_eq!(
+        constraint.literals(),
+        &[literal(0, false), literal(1, true), literal(2, false)]
+    );
+    assert_eq!(constraint.bound(), 2);
+    assert!(constraint.value(&[true, true, false]));
+    assert!(!constraint.value(&[true, false, false]));
+}
+
+#[test]
+fn parse_cardinality_constraints() {
+    let file = TempFile::new(
+        "cardinality.cnf",
+        "p cnf+ 4 3\n1 2 3 4 >= 3\n1 -2 <= 0\n-3 -4 0\n",
+    );
+    let formula = parse_file(&file).unwrap();
+
+    assert_eq!(formula.clauses().len(), 1);
+    assert_eq!(formula.cardinalities().len(), 2);
+    assert_eq!(formula.cardinalities()[0].bound(), 3);
+
+    // x1 = false and x2 = true, so exactly one of x3 and x4 is true: UNSAT with x1 + ... ≥ 3
+    assert!(CdclSolver::new(formula.clone()).solve().is_unsat());
+    assert!(DpllSolver::new(formula).solve().is_unsat());
+}
+
+#[test]
+fn parse_malformed_cardinality_constraints() {
+    let parse = |name: &str, text: &str| parse_file(TempFile::new(&format!("{}.cnf", name), text));
+
+    // Relations need the `cnf+` header
+    assert!(parse("plain", "p cnf 3 1\n1 2 3 <= 2\n").is_err());
+    // and must be a separate token before the bound
+    assert!(parse("attached", "p cnf+ 3 1\n1 2 3<=2\n").is_err());
+    assert!(parse("misplaced", "p cnf+ 3 1\n1 <= 2 3\n").is_err());
+    assert!(parse("bound", "p cnf+ 3 1\n1 2 3 <=\n").is_err());
+
+    let formula = parse("clauses", "p cnf+ 3 2\n1 2 0\n-3 >= 1\n").unwrap();
+    assert_eq!(formula.clauses().len(), 1);
+    assert_eq!(formula.cardinalities().len(), 1);
+}
+
+#[test]
+fn cdcl_cardinality_pigeonhole() {
+    assert!(CdclSolver::new(pigeonhole_cardinality(7, 6))
+        .solve()
+        .is_unsat());
+    assert!(DpllSolver::new(pigeonhole_cardinality(5, 4))
+        .solve()
+        .is_unsat());
+
+    let formula = pigeonhole_cardinality(8, 8);
+    let result = CdclSolver::new(formula.clone()).solve();
+    let model = result.model().unwrap();
+    assert!(formula
+        .cardinalities()
+        .iter()
+        .all(|constraint| constraint.value(model.assignment())));
+}
+
+#[test]
+fn cdcl_cardinalities_with_clauses() {
+    for seed in 0..20 {
+        let mut formula = planted_3sat(60, 120, seed);
+        random_cardinalities(&mut formula, 18, seed);
+        let expected = DpllSolver::new(formula.clone()).solve().is_sat();
+        assert_eq!(CdclSolver::new(formula.clone()).solve().is_sat(), expected);
+        let encoded = formula.encode_cardinalities();
+        assert_eq!(CdclSolver::new(encoded).solve().is_sat(), expected);
+    }
+}
+
+#[test]
+fn cdcl_cardinality_conflict_at_root() {
+    let literal =
+        |index: usize, positive: bool| Literal::new(Variable::from_index(index).unwrap(), positive);
+
+    // ¬x1 ∧ x1 + x2 ≥ 2
+    let mut formula = Cnf::new(3);
+    formula.add_clause(Clause::new(vec![literal(0, false)]));
+    formula.add_cardinality(Cardinality::at_least(
+        vec![literal(0, true), literal(1, true)],
+        2,
+    ));
+
+    // Later calls remember the conflict at the root level
+    let mut solver = CdclSolver::new(formula);
+    assert!(solver.solve_with_assumptions(&[]).is_unsat());
+    assert!(solver.solve_with_assumptions(&[]).is_unsat());
+    assert!(solver
+        .solve_with_assumptions(&[literal(2, true)])
+        .is_unsat());
+    assert!(solver.failed_assumptions().is_empty());
+}
+
+#[test]
+fn cube_and_conquer_cardinalities() {
+    let config = CubeConfig {
+        max_depth: 3,
+        threads: 1,
+        ..CubeConfig::default()
+    };
+    let file = TempFile::new("cube_cardinality.cnf", "p cnf+ 3 2\n1 2 3 >= 3\n-1 0\n");
+    let formula = parse_file(&file).unwrap();
+    let cubes = cube(&formula, &config);
+    let error = write_icnf(&mut Vec::new(), &formula, &cubes).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    let solver = CubeAndConquerSolver::with_cubes(formula, cubes, config.clone());
+    assert!(solver.solve().is_unsat());
+
+    for seed in 0..10 {
+        let mut formula = planted_3sat(40, 80, seed);
+        random_cardinalities(&mut formula, 12, seed);
+        let expected = DpllSolver::new(formula.clone()).solve().is_sat();
+        let solver = CubeAndConquerSolver::with_config(formula, config.clone());
+        assert_eq!(solver.solve().is_sat(), expected, "seed {}", seed);
+    }
+}
+
+// Pseudo-Boolean constraints
+/// Random pseudo-Boolean constraints with small, possibly negative coefficients.
+fn random_pbs(formula: &mut Cnf, num_constraints: usize, seed: u64) {
+    use rand::{rngs::StdRng, seq::index::sample, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..num_constraints {
+        let size = rng.gen_range(3..=7);
+        let terms: Vec<(i64, Literal)> = sample(&mut rng, formula.num_variables(), size)
+            .into_iter()
+            .map(|index| {
+                let coefficient = rng.gen_range(1..=6) * if rng.gen_bool(0.2) { -1 } else { 1 };
+                (
+                    coefficient,
+                    Literal::new(Variable::from_index(index).unwrap(), rng.gen()),
+                )
+            })
+            .collect();
+        let sum: i64 = terms.iter().map(|(coefficient, _)| coefficient.abs()).sum();
+        let bound = rng.gen_range(sum / 4..=sum / 2);
+        let constraint = if rng.gen() {
+            PbConstraint::at_most(terms, bound)
+        } else {
+            PbConstraint::at_least(terms, bound)
+        };
+        formula.add_pb(constraint);
+    }
+}
+
+#[test]
+fn pb_constraint_normalization() {
+    let literal =
+        |index: usize, positive: bool| Literal::new(Variable::from_index(index).unwrap(), positive);
+
+    // 3 x1 - 2 x2 + x1 + 5 ¬x3 ≥ 2  <=>  4 x1 + 2 ¬x2 + 5 ¬x3 ≥ 4, saturated to 4 ¬x3
+    let constraint = PbConstraint::at_least(
+        vec![
+            (3, literal(0, true)),
+            (-2, literal(1, true)),
+            (1, literal(0, true)),
+            (5, literal(2, false)),
+        ],
+        2,
+    );
+    assert_eq!(
+        constraint.terms(),
+        &[
+            (4, literal(0, true)),
+            (4, literal(2, false)),
+            (2, literal(1, false))
+        ]
+    );
+    assert_eq!(constraint.bound(), 4);
+    assert!(constraint.value(&[false, false, false]));
+    assert!(!constraint.value(&[false, false, true]));
+
+    // x1 + x2 ≤ 2 always holds
+    let trivial = PbConstraint::at_most(vec![(1, literal(0, true)), (1, literal(1, true))], 2);
+    assert!(trivial.terms().is_empty());
+    assert_eq!(trivial.bound(), 0);
+    // -2^63 x1 ≤ -2^63  <=>  2^63 x1 ≥ 2^63, without overflowing on the negation
+    let extreme = PbConstraint::at_most(vec![(i64::MIN, literal(0, true))], i64::MIN);
+    assert_eq!(extreme.terms(), &[(1 << 63, literal(0, true))]);
+    assert_eq!(extreme.bound(), 1 << 63);
+
+    let file = TempFile::new(
+        "extreme.opb",
+        "* #variable= 1 #constraint= 1\n-9223372036854775808 x1 = -9223372036854775808 ;\n",
+    );
+    let (formula, _) = parse_opb_file(&file).unwrap();
+    assert!(
+        CdclSolver::new(formula)
+            .solve()
+            .model()
+            .unwrap()
+            .assignment()[0]
+    );
+}
+
+#[test]
+fn parse_opb_file_with_objective() {
+    let file = TempFile::new(
+        "pb.opb",
+        "* #variable= 4 #constraint= 3\n\
+         min: +3 x1 +2 x2 +4 x3 -1 x4 ;\n\
+         +1 x1 +1 x2 +1 x3 >= 2 ;\n\
+         +2 x1 -1 x4 >= 0 ;\n\
+         +1 x2 +1 x3 = 1 ;\n",
+    );
+    let (formula, objective) = parse_opb_file(&file).unwrap();
+
+    assert_eq!(formula.num_variables(), 4);
+    // The equality is split into two constraints
+    assert_eq!(formula.pbs().len(), 4);
+    let objective = objective.unwrap();
+    assert_eq!(objective.terms().len(), 4);
+
+    let result = minimize(formula, &objective, CdclSolver::new);
+    assert!(matches!(result, OptimizeResult::Optimum { cost: 4, .. }));
+}
+
+#[test]
+fn pb_encodings_agree_with_native_propagation() {
+    for seed in 0..20 {
+        let mut formula = planted_3sat(40, 60, seed);
+        random_pbs(&mut formula, 12, seed);
+        let expected = DpllSolver::new(formula.clone()).solve().is_sat();
+        assert_eq!(CdclSolver::new(formula.clone()).solve().is_sat(), expected);
+        for encoding in PbEncoding::ALL {
+            let config = CdclConfig {
+                pb_encoding: Some(encoding),
+                ..CdclConfig::default()
+            };
+            let result = CdclSolver::with_config(formula.clone(), config).solve();
+            assert_eq!(result.is_sat(), expected, "{} on seed {}", encoding, seed);
+        }
+    }
+}
+
+#[test]
+fn cdcl_pb_conflict_at_root() {
+    let literal =
+        |index: usize, positive: bool| Literal::new(Variable::from_index(index).unwrap(), positive);
+
+    // 4 x2 ≥ 7 ∧ 5 x2 + 4 ¬x3 ≥ 9 ∧ (¬x2 ∨ ¬x3)
+    let mut formula = Cnf::new(3);
+    formula.add_pb(PbConstraint::at_least(vec![(4, literal(1, true))], 7));
+    formula.add_pb(PbConstraint::at_least(
+        vec![(5, literal(1, true)), (4, literal(2, false))],
+        9,
+    ));
+    formula.add_clause(Clause::new(vec![literal(1, false), literal(2, false)]));
+
+    let mut solver = CdclSolver::new(formula);
+    assert!(solver
+        .solve_with_assumptions(&[literal(2, true)])
+        .is_unsat());
+    assert!(solver
+        .solve_with_assumptions(&[literal(2, false)])
+        .is_unsat());
+    assert!(solver.failed_assumptions().is_empty());
+}
+
+#[test]
+fn cube_and_conquer_pbs() {
+    let config = CubeConfig {
+        max_depth: 3,
+        threads: 1,
+        ..CubeConfig::default()
+    };
+    for seed in 0..10 {
+        let mut formula = planted_3sat(40, 60, seed);
+        random_pbs(&mut formula, 12, seed);
+        let expected = DpllSolver::new(formula.clone()).solve().is_sat();
+        let cubes = cube(&formula, &config);
+        assert!(write_icnf(&mut Vec::new(), &formula, &cubes).is_err());
+        // One solver conquers every cube
+        let solver = CubeAndConquerSolver::with_cubes(formula, cubes, config.clone());
+        assert_eq!(solver.solve().is_sat(), expected, "seed {}", seed);
+    }
+}
+
+#[test]
+fn pb_sorting_network_falls_back_on_large_coefficients() {
+    let literal = |index: usize| Literal::new(Variable::from_index(index).unwrap(), true);
+
+    // 1000000000 x1 + 999999999 x2 + x3 ≥ 1000000000 needs x1, or x2 and x3
+    let mut formula = Cnf::new(3);
+    formula.add_pb(PbConstraint::at_least(
+        vec![
+            (1_000_000_000, literal(0)),
+            (999_999_999, literal(1)),
+            (1, literal(2)),
+        ],
+        1_000_000_000,
+    ));
+    formula.add_clause(Clause::new(vec![!literal(0)]));
+    let encoded = formula.encode_pbs(PbEncoding::SortingNetwork);
+    assert!(encoded.clauses().len() < 1000);
+    let model = CdclSolver::new(encoded).solve().into_model().unwrap();
+    assert_eq!(model.assignment()[..3], [false, true, true]);
+
+    formula.add_clause(Clause::new(vec![!literal(2)]));
+    assert!(
+        CdclSolver::new(formula.encode_pbs(PbEncoding::SortingNetwork))
+            .solve()
+            .is_unsat()
+    );
+}
+
+#[test]
+fn minimize_matches_exhaustive_search() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    for seed in 0..10 {
+        let mut formula = planted_3sat(12, 20, seed);
+        random_pbs(&mut formula, 4, seed);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let objective = Objective::new(
+            (0..12)
+                .map(|index| {
+                    let literal = Literal::new(Variable::from_index(index).unwrap(), true);
+                    (rng.gen_range(-3..=5), literal)
+                })
+                .collect(),
+        );
+
+        let mut expected = None;
+        for bits in 0..1u32 << 12 {
+            let assignment: Vec<bool> = (0..12).map(|i| bits >> i & 1 == 1).collect();
+            if formula
+                .clauses()
+                .iter()
+                .all(|clause| clause.iter().any(|literal| literal.value(&assignment)))
+                && formula.pbs().iter().all(|pb| pb.value(&assignment))
+            {
+                let cost = objective.value(&assignment);
+                expected = Some(expected.map_or(cost, |best: i64| best.min(cost)));
+            }
+        }
+
+        let result = minimize(formula.clone(), &objective, CdclSolver::new);
+        assert_eq!(result.best().map(|(_, cost)| cost), expected);
+        let result = minimize(formula, &objective, DpllSolver::new);
+        assert_eq!(result.best().map(|(_, cost)| cost), expected);
+    }
+}
+
+// Constraint encodings
+/// Checks that the encoded constraint over `n` fresh literals holds exactly when
+/// `expected` accepts the number of true literals.
+fn check_encoding(
+    n: usize,
+    encode: impl Fn(&mut CnfBuilder, &[Literal]),
+    expected: impl Fn(usize) -> bool,
+) {
+    let mut builder = CnfBuilder::new(n);
+    let literals: Vec<Literal> = (0..n)
+        .map(|index| Literal::new(Variable::from_index(index).unwrap(), true))
+        .collect();
+    encode(&mut builder, &literals);
+    let mut solver = CdclSolver::new(builder.build());
+
+    for bits in 0..1usize << n {
+        let assumptions: Vec<Literal> = (0..n)
+            .map(|i| Literal::new(literals[i].variable(), bits >> i & 1 == 1))
+            .collect();
+        let result = solver.solve_with_assumptions(&assumptions);
+        assert_eq!(
+            result.is_sat(),
+            expected(bits.count_ones() as usize),
+            "{:b}",
+            bits
+        );
+    }
+}
+
+#[test]
+fn at_most_one_encodings() {
+    for encoding in AmoEncoding::ALL {
+        for n in 0..=9 {
+            check_encoding(
+                n,
+                |builder, literals| builder.at_most_one(literals, encoding),
+                |count| count <= 1,
+            );
+            check_encoding(
+                n,
+                |builder, literals| builder.exactly_one(literals, encoding),
+                |count| count == 1,
+            );
+        }
+    }
+}
+
+#[test]
+fn at_most_k_encodings() {
+    for encoding in AmkEncoding::ALL {
+        for n in 1..=8 {
+            for k in 0..=n + 1 {
+                check_encoding(
+                    n,
+                    |builder, literals| builder.at_most_k(literals, k, encoding),
+                    |count| count <= k,
+                );
+                check_encoding(
+                    n,
+                    |builder, literals| builder.at_least_k(literals, k, encoding),
+                    |count| count >= k,
+                );
+            }
+        }
+    }
+}
+
+// Boolean expressions
+/// A random expression of the given depth over the names `a` to `e`.
+fn random_expression(rng: &mut impl rand::Rng, depth: usize) -> Formula {
+    const NAMES: [&str; 5] = ["a", "b", "c", "d", "e"];
+
+    if depth == 0 || rng.gen_bool(0.2) {
+        return Formula::var(NAMES[rng.gen_range(0..NAMES.len())]);
+    }
+    let kind = rng.gen_range(0..8);
+    let arity = match kind {
+        0 => 1,
+        1 | 2 => rng.gen_range(0..=3),
+        6 => 3,
+        7 => 0,
+        _ => 2,
+    };
+    let mut operands: Vec<Formula> = (0..arity)
+        .map(|_| random_expression(rng, depth - 1))
+        .collect();
+    let mut operand = || operands.pop().unwrap();
+    match kind {
+        0 => !operand(),
+        1 => Formula::and(operands),
+        2 => Formula::or(operands),
+        3 => operand() ^ operand(),
+        4 => Formula::implies(operand(), operand()),
+        5 => Formula::iff(operand(), operand()),
+        6 => Formula::ite(operand(), operand(), operand()),
+        _ => Formula::Const(rng.gen()),
+    }
+}
+
+#[test]
+fn expression_transformations_preserve_models() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(39);
+    for _ in 0..200 {
+        let expression = random_expression(&mut rng, 4);
+        for transformation in Transformation::ALL {
+            let (formula, variables) = expression.to_cnf(transformation);
+            let names: Vec<&str> = (0..variables.len())
+                .map(|index| {
+                    variables
+                        .name(Variable::from_index(index).unwrap())
+                        .unwrap()
+                })
+                .collect();
+            let mut solver = CdclSolver::new(formula);
+
+            for bits in 0..1usize << names.len() {
+                let value_of = |name: &str| {
+                    let index = variables.get(name).unwrap().index();
+                    bits >> index & 1 == 1
+                };
+                let assumptions: Vec<Literal> = names
+                    .iter()
+                    .map(|name| Literal::new(variables.get(name).unwrap(), value_of(name)))
+                    .collect();
+                let result = solver.solve_with_assumptions(&assumptions);
+                assert_eq!(
+                    result.is_sat(),
+                    expression.value(&value_of),
+                    "{} with {}",
+                    expression,
+                    transformation
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn query_model_by_name() {
+    let (x, y, z) = (Formula::var("x"), Formula::var("y"), Formula::var("z"));
+    // x ∧ (x → y) ∧ (y ↔ ¬z)
+    let expression = Formula::and(vec![
+        x.clone(),
+        Formula::implies(x, y.clone()),
+        Formula::iff(y, !z),
+    ]);
+    let (formula, variables) = expression.to_cnf(Transformation::default());
+    assert_eq!(variables.len(), 3);
+
+    let result = CdclSolver::new(formula).solve();
+    let model = result.model().unwrap();
+    assert_eq!(variables.value(model, "x"), Some(true));
+    assert_eq!(variables.value(model, "y"), Some(true));
+    assert_eq!(variables.value(model, "z"), Some(false));
+    assert_eq!(variables.value(model, "w"), None);
+}
+
+// And-inverter graphs
+#[test]
+fn aiger_adder_miters() {
+    for path in [
+        "testcases/aiger/add8_miter.aag",
+        "testcases/aiger/add8_miter.aig",
+    ] {
+        let aig = parse_aiger_file(path).unwrap();
+        let formula = aig.to_cnf(aig.outputs()[0]);
+        assert!(CdclSolver::new(formula.clone()).solve().is_unsat());
+        assert!(DpllSolver::new(formula).solve().is_unsat());
+    }
+
+    for path in [
+        "testcases/aiger/add8_bug.aag",
+        "testcases/aiger/add8_bug.aig",
+    ] {
+        let aig = parse_aiger_file(path).unwrap();
+        let result = CdclSolver::new(aig.to_cnf(aig.outputs()[0])).solve();
+        // The inputs come first, so the model is a witness in simulation
+        let inputs = &result.model().unwrap().assignment()[..aig.inputs().len()];
+        assert!(aig.outputs()[0].value(&aig.evaluate(inputs, &[])));
+    }
+}
+
+#[test]
+fn aiger_ascii_matches_binary() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let ascii = parse_aiger_file("testcases/aiger/add8_bug.aag").unwrap();
+    let binary = parse_aiger_file("testcases/aiger/add8_bug.aig").unwrap();
+    assert_eq!(ascii.inputs(), binary.inputs());
+    assert_eq!(ascii.ands().len(), binary.ands().len());
+
+    let mut rng = StdRng::seed_from_u64(40);
+    for _ in 0..100 {
+        let inputs: Vec<bool> = (0..ascii.inputs().len()).map(|_| rng.gen()).collect();
+        assert_eq!(
+            ascii.outputs()[0].value(&ascii.evaluate(&inputs, &[])),
+            binary.outputs()[0].value(&binary.evaluate(&inputs, &[]))
+        );
+    }
+}
+
+#[test]
+fn aig_structural_hashing_and_constants() {
+    let literal = |variable: u32, negated: bool| AigLiteral::new(2 * variable + negated as u32);
+    let mut aig = Aig::new(6);
+    aig.add_input(literal(1, false));
+    aig.add_input(literal(2, false));
+    // Gates 3 and 4 are the same, gate 5 is constant false, and gate 6 is 3 ∧ ¬4
+    for (output, inputs) in [
+        (3, [literal(1, false), literal(2, true)]),
+        (4, [literal(2, true), literal(1, false)]),
+        (5, [literal(1, true), literal(1, false)]),
+        (6, [literal(3, false), literal(4, true)]),
+    ] {
+        aig.add_and(AndGate {
+            output: literal(output, false),
+            inputs,
+        });
+    }
+
+    // Only one gate is encoded with three clauses, plus the unit clause for the output
+    let formula = aig.to_cnf(literal(4, false));
+    assert_eq!(formula.num_variables(), 3);
+    assert_eq!(formula.clauses().len(), 4);
+    assert!(CdclSolver::new(formula).solve().is_sat());
+
+    assert!(CdclSolver::new(aig.to_cnf(literal(5, false)))
+        .solve()
+        .is_unsat());
+    assert!(CdclSolver::new(aig.to_cnf(literal(6, false)))
+        .solve()
+        .is_unsat());
+    assert!(CdclSolver::new(aig.to_cnf(AigLiteral::TRUE))
+        .solve()
+        .is_sat());
+
+    // The invariant constraint ¬x1 makes gate 4 = x1 ∧ ¬x2 unsatisfiable
+    aig.add_constraint(literal(1, true));
+    assert!(CdclSolver::new(aig.to_cnf(literal(4, false)))
+        .solve()
+        .is_unsat());
+    assert!(CdclSolver::new(aig.to_cnf(literal(4, true)))
+        .solve()
+        .is_sat());
+}
+
+#[test]
+fn parse_aiger_latches_and_errors() {
+    let parse =
+        |name: &str, text: &str| parse_aiger_file(TempFile::new(&format!("{}.aag", name), text));
+
+    // Gates out of order, and latches reset to 0, 1 and uninitialized
+    let aig = parse(
+        "latches",
+        "aag 6 1 3 1 2 1\n2\n4 12\n6 4 1\n8 9 8\n12\n11\n12 10 2\n10 4 7\ni0 enable\nc\ncomment\n",
+    )
+    .unwrap();
+    assert_eq!(
+        aig.latches()
+            .iter()
+            .map(|latch| latch.reset)
+            .collect::<Vec<_>>(),
+        [Some(false), Some(true), None]
+    );
+    assert_eq!(aig.bad(), [AigLiteral::new(11)]);
+    assert_eq!(aig.ands()[0].output, AigLiteral::new(10));
+    let values = aig.evaluate(&[true], &[true, false, false]);
+    assert!(aig.outputs()[0].value(&values));
+
+    assert!(parse("cycle", "aag 3 1 0 1 2\n2\n4\n4 6 2\n6 4 2\n").is_err());
+    assert!(parse("undefined", "aag 3 1 0 1 1\n2\n4\n4 6 2\n").is_err());
+    assert!(parse("redefined", "aag 2 1 0 1 1\n2\n4\n2 2 2\n").is_err());
+    assert!(parse("justice", "aag 1 1 0 0 0 0 0 1 0\n2\n1\n2\n").is_err());
+}
+
+// Bounded model checking
+#[test]
+fn bmc_aiger_counter() {
+    let aig = parse_aiger_file("testcases/aiger/counter.aag").unwrap();
+    let bad = aig.bad()[0];
+    assert!(matches!(
+        bmc::check_aig(&aig, bad, 9, CdclConfig::default()),
+        BmcResult::NoCounterexample { bound: 9 }
+    ));
+
+    // The shortest trace counts to 10, and simulation follows it
+    let result = bmc::check_aig(&aig, bad, 15, CdclConfig::default());
+    let trace = result.trace().unwrap();
+    assert_eq!(trace.len(), 11);
+    for (step, next) in trace.iter().zip(&trace[1..]) {
+        let values = aig.evaluate(&step.inputs, &step.state);
+        let latches: Vec<bool> = aig
+            .latches()
+            .iter()
+            .map(|latch| latch.next.value(&values))
+            .collect();
+        assert_eq!(latches, next.state);
+    }
+    let last = trace.last().unwrap();
+    assert!(bad.value(&aig.evaluate(&last.inputs, &last.state)));
+
+    // Steps beyond the counterexample are never unrolled
+    let result = bmc::check_aig(&aig, bad, 1_000_000_000, CdclConfig::default());
+    assert_eq!(result.trace().unwrap().len(), 11);
+
+    let aig = parse_aiger_file("testcases/aiger/counter_constrained.aag").unwrap();
+    assert!(!bmc::check_aig(&aig, aig.bad()[0], 15, CdclConfig::default()).is_counterexample());
+}
+
+#[test]
+fn bmc_transition_system() {
+    let var = Formula::var;
+    let state = |names: &[&str]| names.iter().map(|&name| name.to_owned()).collect();
+
+    // A 3-bit counter incremented when `go` is true
+    let (b0, b1, b2, go) = (var("b0"), var("b1"), var("b2"), var("go"));
+    let increment = Formula::and(vec![
+        Formula::iff(var("b0'"), b0.clone() ^ go.clone()),
+        Formula::iff(var("b1'"), b1.clone() ^ (b0.clone() & go.clone())),
+        Formula::iff(var("b2'"), b2.clone() ^ (b1.clone() & b0.clone() & go)),
+    ]);
+    let system = TransitionSystem::new(
+        state(&["b0", "b1", "b2"]),
+        !b0.clone() & !b1.clone() & !b2.clone(),
+        increment,
+        !(b0 & b1 & b2),
+    );
+    assert_eq!(system.inputs(), ["go"]);
+    let result = bmc::check(&system, 10, CdclConfig::default());
+    let trace = result.trace().unwrap();
+    assert_eq!(trace.len(), 8);
+    assert!(trace[..7].iter().all(|step| step.inputs == [true]));
+    assert_eq!(trace[7].state, [true, true, true]);
+
+    // Two variables swapping their values stay different
+    let (x, y) = (var("x"), var("y"));
+    let swap = Formula::iff(var("x'"), y.clone()) & Formula::iff(var("y'"), x.clone());
+    let system = TransitionSystem::new(state(&["x", "y"]), x.clone() & !y.clone(), swap, x ^ y);
+    assert!(matches!(
+        bmc::check(&system, 10, CdclConfig::default()),
+        BmcResult::NoCounterexample { bound: 10 }
+    ));
+
+    // A state without successors does not hide the counterexample reaching it
+    let s = var("s");
+    let system = TransitionSystem::new(state(&["s"]), !s.clone(), !s.clone() & var("s'"), !s);
+    assert_eq!(
+        bmc::check(&system, 5, CdclConfig::default())
+            .trace()
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+// Bit-vectors
+fn random_bit_vector(rng: &mut impl rand::Rng, width: usize, depth: usize) -> BitVector {
+    if depth == 0 || rng.gen_bool(0.2) {
+        return match rng.gen_range(0..3) {
+            0 => BitVector::constant(rng.gen::<u64>() >> (64 - width), width),
+            1 => BitVector::var("x", width),
+            _ => BitVector::var("y", width),
+        };
+    }
+    const OPERATORS: [BvOperator; 9] = [
+        BvOperator::And,
+        BvOperator::Or,
+        BvOperator::Xor,
+        BvOperator::Add,
+        BvOperator::Sub,
+        BvOperator::Mul,
+        BvOperator::Shl,
+        BvOperator::Lshr,
+        BvOperator::Ashr,
+    ];
+    const COMPARISONS: [Comparison; 5] = [
+        Comparison::Eq,
+        Comparison::Ult,
+        Comparison::Ule,
+        Comparison::Slt,
+        Comparison::Sle,
+    ];
+
+    match rng.gen_range(0..6) {
+        0 => !random_bit_vector(rng, width, depth - 1),
+        1 => -random_bit_vector(rng, width, depth - 1),
+        2 => {
+            // Rotate by a constant with extraction and concatenation
+            let operand = random_bit_vector(rng, width, depth - 1);
+            let split = rng.gen_range(1..width);
+            BitVector::concat(
+                BitVector::extract(split - 1, 0, operand.clone()),
+                BitVector::extract(width - 1, split, operand),
+            )
+        }
+        3 => {
+            let comparison = COMPARISONS[rng.gen_range(0..COMPARISONS.len())];
+            let condition = Bool::compare(
+                comparison,
+                random_bit_vector(rng, width, depth - 1),
+                random_bit_vector(rng, width, depth - 1),
+            );
+            BitVector::ite(
+                condition,
+                random_bit_vector(rng, width, depth - 1),
+                random_bit_vector(rng, width, depth - 1),
+            )
+        }
+        _ => {
+            let operator = OPERATORS[rng.gen_range(0..OPERATORS.len())];
+            let a = random_bit_vector(rng, width, depth - 1);
+            let b = if rng.gen_bool(0.5) {
+                random_bit_vector(rng, width, depth - 1)
+            } else {
+                // Extended variables test shifts by the width or more
+                BitVector::zero_extend(width - 3, BitVector::var("z", 3))
+            };
+            BitVector::binary(operator, a, b)
+        }
+    }
+}
+
+#[test]
+fn bit_blasting_matches_evaluation() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(42);
+    for _ in 0..300 {
+        let width = rng.gen_range(3..=8);
+        let term = random_bit_vector(&mut rng, width, 3);
+        let values: std::collections::HashMap<&str, u64> = [("x", width), ("y", width), ("z", 3)]
+            .iter()
+            .map(|&(name, width)| (name, rng.gen::<u64>() >> (64 - width)))
+            .collect();
+        let value_of = |name: &str| values[name];
+
+        let result = BitVector::var("result", width);
+        let (formula, variables) = Bool::and(vec![
+            Bool::equal(
+                BitVector::var("x", width),
+                BitVector::constant(values["x"], width),
+            ),
+            Bool::equal(
+                BitVector::var("y", width),
+                BitVector::constant(values["y"], width),
+            ),
+            Bool::equal(BitVector::var("z", 3), BitVector::constant(values["z"], 3)),
+            Bool::equal(result, term.clone()),
+        ])
+        .to_cnf();
+        let model = CdclSolver::new(formula).solve().into_model().unwrap();
+        assert_eq!(
+            variables.value(&model, "result"),
+            Some(term.value(&value_of)),
+            "{} with {:?}",
+            term,
+            values
+        );
+    }
+}
+
+#[test]
+fn factor_with_bit_vectors() {
+    let factors = |n: u64| {
+        let (x, y) = (BitVector::var("x", 8), BitVector::var("y", 8));
+        let product = BitVector::zero_extend(8, x.clone()) * BitVector::zero_extend(8, y.clone());
+        let (formula, variables) = Bool::and(vec![
+            Bool::equal(product, BitVector::constant(n, 16)),
+            Bool::ugt(x.clone(), BitVector::constant(1, 8)),
+            Bool::ule(x, y.clone()),
+            Bool::sgt(y, BitVector::constant(0, 8)),
+        ])
+        .to_cnf();
+        CdclSolver::new(formula).solve().into_model().map(|model| {
+            (
+                variables.value(&model, "x").unwrap(),
+                variables.value(&model, "y").unwrap(),
+            )
+        })
+    };
+
+    assert_eq!(factors(143), Some((11, 13)));
+    assert_eq!(factors(121), Some((11, 11)));
+    assert_eq!(factors(127), None);
+    // 251 * 2 is a factorization only if y is negative as a signed value
+    assert_eq!(factors(502), None);
+}
+
+// SMT-LIB
+fn run_smt(input: &str) -> String {
+    let mut output = Vec::new();
+    SmtSession::new(CdclSolver::new)
+        .run(input.as_bytes(), &mut output)
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn smt_factor_file() {
+    let input = std::fs::read_to_string("testcases/smt/factor.smt2").unwrap();
+    assert_eq!(
+        run_smt(&input),
+        "sat
+((x #x0b) (y #x0d))
+unsat
+sat
+(
+  (define-fun x () (_ BitVec 8) #x0b)
+  (define-fun y () (_ BitVec 8) #x0d)
+)
+"
+    );
+}
+
+#[test]
+fn smt_scopes_and_errors() {
+    let output = run_smt(
+        "(declare-const p Bool)
+         (declare-const q Bool)
+         (assert (xor p q (=> p q)))
+         (push)
+         (declare-const a (_ BitVec 3))
+         (assert (let ((p (bvsgt a #b001)) (b (bvneg a))) (and p (= b #b101))))
+         (check-sat)
+         (get-value (a (bvlshr a #b001)))
+         (assert (distinct a #b011 #b100))
+         (check-sat)
+         (get-model)
+         (pop)
+         (get-value (a))
+         (assert a)
+         (assert (and (not p) q))
+         (check-sat)
+         (set-info :status unsat)
+         (exit)
+         (check-sat)",
+    );
+    assert_eq!(
+        output,
+        "sat
+((a #b011) ((bvlshr a #b001) #b001))
+unsat
+(error \"No model is available\")
+(error \"No model is available\")
+(error \"Unknown symbol 'a'\")
+unsat
+"
+    );
+
+    let output = run_smt(
+        "(declare-const x (_ BitVec 4))
+         (declare-const x Bool)
+         (assert (bvult x #b1))
+         (assert (= x (_ bv16 4)))
+         (assert ((_ extract 4 0) x))
+         (assert (bvudiv x x))
+         (declare-const y (_ BitVec 65))
+         (check-sat)
+         (assert (and x",
+    );
+    let errors: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        errors,
+        [
+            "(error \"'x' is already declared\")",
+            "(error \"'#b1' is not of sort (_ BitVec 4)\")",
+            "(error \"Malformed term '(_ bv16 4)'\")",
+            "(error \"Malformed term '((_ extract 4 0) x)'\")",
+            "(error \"Unsupported operator 'bvudiv'\")",
+            "(error \"Unsupported sort '(_ BitVec 65)'\")",
+            "sat",
+            "(error \"Unbalanced parentheses\")",
+        ]
+    );
+}
+
+// Quantified Boolean formulas
+/// Decides the QBF by expanding every quantifier, with free variables existential,
+/// after fixing the variables of `fixed`.
+fn expand_qbf(qbf: &Qbf, fixed: &[Literal]) -> bool {
+    fn expand(order: &[(Quantifier, Variable)], matrix: &Cnf, assignment: &mut Vec<bool>) -> bool {
+        let (quantifier, variable) = match order.first() {
+            Some(&first) => first,
+            None => {
+                return matrix
+                    .clauses()
+                    .iter()
+                    .all(|clause| clause.iter().any(|literal| literal.value(assignment)))
+            }
+        };
+        let mut branch = |value| {
+            assignment[variable.index()] = value;
+            expand(&order[1..], matrix, assignment)
+        };
+        match quantifier {
+            Quantifier::Exists => branch(false) || branch(true),
+            Quantifier::Forall => branch(false) && branch(true),
+        }
+    }
+
+    let num_variables = qbf.matrix().num_variables();
+    let mut assignment = vec![false; num_variables];
+    for literal in fixed {
+        assignment[literal.index()] = literal.positive();
+    }
+    let is_fixed = |variable: Variable| fixed.iter().any(|literal| literal.variable() == variable);
+
+    let mut order: Vec<(Quantifier, Variable)> = (0..num_variables)
+        .map(|index| Variable::from_index(index).unwrap())
+        .filter(|&variable| !qbf.is_quantified(variable))
+        .map(|variable| (Quantifier::Exists, variable))
+        .collect();
+    for (quantifier, block) in qbf.prefix() {
+        order.extend(block.iter().map(|&variable| (*quantifier, variable)));
+    }
+    order.retain(|&(_, variable)| !is_fixed(variable));
+    expand(&order, qbf.matrix(), &mut assignment)
+}
+
+#[test]
+fn qbf_matches_expansion() {
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(44);
+    let num_variables = 8;
+    let (mut num_true, mut num_false) = (0, 0);
+    for _ in 0..200 {
+        let mut formula = Cnf::new(num_variables);
+        for _ in 0..rng.gen_range(4..16) {
+            let literals = (0..rng.gen_range(1..4))
+                .map(|_| {
+                    let index = rng.gen_range(0..num_variables);
+                    Literal::new(Variable::from_index(index).unwrap(), rng.gen())
+                })
+                .collect();
+            formula.add_clause(Clause::new(literals));
+        }
+
+        // Some variables are left free
+        let mut variables: Vec<Variable> = (0..num_variables)
+            .map(|index| Variable::from_index(index).unwrap())
+            .collect();
+        variables.shuffle(&mut rng);
+        variables.truncate(rng.gen_range(0..=num_variables));
+        let mut qbf = Qbf::new(formula);
+        while !variables.is_empty() {
+            let block = variables.split_off(rng.gen_range(0..variables.len()));
+            let quantifier = if rng.gen() {
+                Quantifier::Exists
+            } else {
+                Quantifier::Forall
+            };
+            qbf.add_block(quantifier, block);
+        }
+
+        let expected = expand_qbf(&qbf, &[]);
+        let result = qbf.solve(CdclSolver::new);
+        let other = qbf.solve(DpllSolver::new);
+        assert_eq!(matches!(other, QbfResult::True(_)), expected);
+
+        // The certificate fixes the outermost block to a winning move
+        let (value, certificate) = match result {
+            QbfResult::True(certificate) => (true, certificate),
+            QbfResult::False(certificate) => (false, certificate),
+            QbfResult::Unknown => panic!("Unknown result"),
+        };
+        assert_eq!(value, expected);
+        if value {
+            num_true += 1;
+        } else {
+            num_false += 1;
+        }
+        assert_eq!(expand_qbf(&qbf, &certificate), value);
+
+        // It assigns the whole block if the outermost player wins, and nothing otherwise
+        let free: Vec<Variable> = (0..num_variables)
+            .map(|index| Variable::from_index(index).unwrap())
+            .filter(|&variable| !qbf.is_quantified(variable))
+            .collect();
+        let (quantifier, mut block) = match qbf.prefix().first() {
+            Some((Quantifier::Exists, block)) => {
+                (Quantifier::Exists, [free, block.clone()].concat())
+            }
+            Some((Quantifier::Forall, block)) if free.is_empty() => {
+                (Quantifier::Forall, block.clone())
+            }
+            _ => (Quantifier::Exists, free),
+        };
+        if (quantifier == Quantifier::Exists) != value {
+            block.clear();
+        }
+        let mut assigned: Vec<Variable> = certificate
+            .iter()
+            .map(|literal| literal.variable())
+            .collect();
+        assigned.sort();
+        block.sort();
+        assert_eq!(assigned, block);
+    }
+    assert!(num_true > 20 && num_false > 20);
+
+    // ∀x ∃y. (x ∨ y) ∧ (x ∨ ¬y) is refuted by x = false
+    let x = Variable::from_index(0).unwrap();
+    let y = Variable::from_index(1).unwrap();
+    let mut formula = Cnf::new(2);
+    formula.add_clause(Clause::new(vec![
+        Literal::new(x, true),
+        Literal::new(y, true),
+    ]));
+    formula.add_clause(Clause::new(vec![
+        Literal::new(x, true),
+        Literal::new(y, false),
+    ]));
+    let mut qbf = Qbf::new(formula);
+    qbf.add_block(Quantifier::Forall, vec![x]);
+    qbf.add_block(Quantifier::Exists, vec![y]);
+    assert_eq!(
+        qbf.solve(CdclSolver::new),
+        QbfResult::False(vec![Literal::new(x, false)])
+    );
+}
+
+#[test]
+fn qbf_qdimacs_files() {
+    let solve = |name: &str| {
+        let qbf = parse_qdimacs_file(format!("testcases/qbf/{}.qdimacs", name)).unwrap();
+        qbf.solve(CdclSolver::new)
+    };
+    let literals = |dimacs: &[i64]| -> Vec<Literal> {
+        dimacs
+            .iter()
+            .map(|number| number.to_string().parse().unwrap())
+            .collect()
+    };
+
+    // The free variable joins the outermost existential block
+    assert_eq!(solve("true"), QbfResult::True(literals(&[3])));
+    assert_eq!(solve("false"), QbfResult::False(Vec::new()));
+    // The only truth table of the gate
+    assert_eq!(
+        solve("xor_gate"),
+        QbfResult::True(literals(&[-1, 2, 3, -4]))
+    );
+}
+
+#[test]
+fn parse_qdimacs_prefix_and_errors() {
+    let parse = |name: &str, text: &str| {
+        parse_qdimacs_file(TempFile::new(&format!("{}.qdimacs", name), text))
+    };
+
+    // Adjacent blocks of the same quantifier are merged
+    let qbf = parse("merge", "c\np cnf 4 1\ne 1 0\ne 2 0\na 4 0\n1 -2 4 0\n").unwrap();
+    let variables = |indices: &[usize]| -> Vec<Variable> {
+        indices
+            .iter()
+            .map(|&index| Variable::from_index(index).unwrap())
+            .collect()
+    };
+    assert_eq!(
+        qbf.prefix(),
+        [
+            (Quantifier::Exists, variables(&[0, 1])),
+            (Quantifier::Forall, variables(&[3])),
+        ]
+    );
+    assert!(!qbf.is_quantified(Variable::from_index(2).unwrap()));
+
+    assert!(parse("twice", "p cnf 2 1\ne 1 0\na 2 1 0\n1 2 0\n").is_err());
+    assert!(parse("range", "p cnf 2 1\ne 3 0\n1 2 0\n").is_err());
+    assert!(parse("negative", "p cnf 2 1\na -1 0\n1 2 0\n").is_err());
+    assert!(parse("misplaced", "p cnf 2 1\n1 2 0\na 1 0\n").is_err());
+    assert!(parse("clause", "p cnf 2 1\na 1 0\n1 3 0\n").is_err());
+    assert!(parse("count", "p cnf 2 2\na 1 0\n1 2 0\n").is_err());
+}
+
+// Symmetry breaking
+/// The pigeonhole formula of `num_holes + 1` pigeons in clauses.
+fn pigeonhole(num_holes: usize) -> Cnf {
+    let num_pigeons = num_holes + 1;
+    let literal = |pigeon: usize, hole: usize, positive: bool| {
+        Literal::new(
+            Variable::from_index(pigeon * num_holes + hole).unwrap(),
+            positive,
+        )
+    };
+
+    let mut formula = Cnf::new(num_pigeons * num_holes);
+    for pigeon in 0..num_pigeons {
+        formula.add_clause(Clause::new(
+            (0..num_holes)
+                .map(|hole| literal(pigeon, hole, true))
+                .collect(),
+        ));
+    }
+    for hole in 0..num_holes {
+        for a in 0..num_pigeons {
+            for b in a + 1..num_pigeons {
+                formula.add_clause(Clause::new(vec![
+                    literal(a, hole, false),
+                    literal(b, hole, false),
+                ]));
+            }
+        }
+    }
+    formula
+}
+
+#[test]
+fn pigeonhole_symmetries() {
+    let formula = pigeonhole(10);
+    let symmetries = find_symmetries(&formula);
+    // Generators of the permutations of pigeons and of holes
+    assert_eq!(symmetries.len(), 10 + 9);
+    assert!(symmetries
+        .iter()
+        .all(|symmetry| symmetry.preserves(&formula)));
+
+    // Hopeless without symmetry breaking
+    let broken = break_symmetries(&formula, &symmetries);
+    assert!(CdclSolver::new(broken).solve().is_unsat());
+}
+
+#[test]
+fn symmetry_breaking_preserves_satisfiability() {
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(47);
+    let num_variables = 12;
+    let (mut num_sat, mut num_unsat) = (0, 0);
+    for _ in 0..100 {
+        // Close random clauses under a random permutation with negations
+        let mut images: Vec<Literal> = (0..num_variables)
+            .map(|index| Literal::new(Variable::from_index(index).unwrap(), rng.gen()))
+            .collect();
+        images.shuffle(&mut rng);
+        let planted = Symmetry::new(images);
+
+        let mut formula = Cnf::new(num_variables);
+        for _ in 0..rng.gen_range(3..8) {
+            let mut clause: Vec<Literal> = (0..3)
+                .map(|_| {
+                    let index = rng.gen_range(0..num_variables);
+                    Literal::new(Variable::from_index(index).unwrap(), rng.gen())
+                })
+                .collect();
+            let first = clause.clone();
+            loop {
+                formula.add_clause(Clause::new(clause.clone()));
+                clause = clause
+                    .iter()
+                    .map(|&literal| planted.apply(literal))
+                    .collect();
+                if clause == first {
+                    break;
+                }
+            }
+        }
+
+        let symmetries = find_symmetries(&formula);
+        assert!(!symmetries.is_empty());
+        assert!(symmetries
+            .iter()
+            .all(|symmetry| symmetry.preserves(&formula)));
+
+        let broken = break_symmetries(&formula, &symmetries);
+        let expected = !enumerate_models(&formula).is_empty();
+        match CdclSolver::new(broken.clone()).solve() {
+            SolveResult::Sat(model) => {
+                assert!(expected);
+                num_sat += 1;
+                // The lex-leader clauses only restrict the original variables
+                let assignment = &model.assignment()[..num_variables];
+                assert!(formula
+                    .clauses()
+                    .iter()
+                    .all(|clause| clause.iter().any(|literal| literal.value(assignment))));
+            }
+            result => {
+                assert!(result.is_unsat() && !expected);
+                num_unsat += 1;
+            }
+        }
+    }
+    assert!(num_sat > 10 && num_unsat > 10);
+}
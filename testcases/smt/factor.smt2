@@ -0,0 +1,18 @@
+; Factor 143 into two 8-bit numbers greater than one
+(set-logic QF_BV)
+(set-option :produce-models true)
+(declare-const x (_ BitVec 8))
+(declare-const y (_ BitVec 8))
+(define-fun n () (_ BitVec 16) (_ bv143 16))
+(assert (= (bvmul ((_ zero_extend 8) x) ((_ zero_extend 8) y)) n))
+(assert (bvugt x #x01))
+(assert (bvule x y))
+(check-sat)
+(get-value (x y))
+(push 1)
+(assert (distinct x #x0b))
+(check-sat)
+(pop 1)
+(check-sat)
+(get-model)
+(exit)